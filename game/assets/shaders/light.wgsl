@@ -0,0 +1,10 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+#ifdef VERTEX_COLORS
+    return mesh.color;
+#else
+    return vec4(1.0);
+#endif
+}
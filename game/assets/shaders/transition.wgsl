@@ -0,0 +1,78 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+// progress, style, window width and height
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var<uniform> params: vec4<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var screenshot: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(2) var screenshot_sampler: sampler;
+
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2(127.1, 311.7))) * 43758.5453);
+}
+
+// transparent outside of the screenshot
+fn shot(uv: vec2<f32>) -> vec4<f32> {
+    let c = textureSampleLevel(screenshot, screenshot_sampler, clamp(uv, vec2(0.0), vec2(1.0)), 0.0);
+    let inside = all(uv >= vec2(0.0)) && all(uv <= vec2(1.0));
+    return select(vec4(0.0), c, inside);
+}
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let t = clamp(params.x, 0.0, 1.0);
+    let style = u32(params.y + 0.5);
+    let size = params.zw;
+    let uv = mesh.uv;
+    let px = uv * size;
+    var c = vec4(0.0);
+    switch style {
+        // fade
+        case 0u: {
+            if t < 0.5 {
+                c = vec4(shot(uv).rgb * (1.0 - t * 2.0), 1.0);
+            } else {
+                c = vec4(0.0, 0.0, 0.0, 2.0 - t * 2.0);
+            }
+        }
+        // wipe
+        case 1u: {
+            let edge = t * 1.2 - 0.1;
+            c = vec4(shot(uv).rgb, smoothstep(edge - 0.05, edge + 0.05, uv.x));
+        }
+        // iris
+        case 2u: {
+            let r = t * length(size) * 0.5;
+            c = vec4(shot(uv).rgb, step(r, length(px - size * 0.5)));
+        }
+        // pixelate
+        case 3u: {
+            let block = max(1.0, floor(t * t * 64.0));
+            let p = (floor(px / block) + 0.5) * block / size;
+            c = vec4(shot(p).rgb, 1.0 - smoothstep(0.6, 1.0, t));
+        }
+        // shatter, every shard is checked for whether it covers this pixel
+        case 4u: {
+            let cells = vec2(12.0, 7.0);
+            let cell_size = size / cells;
+            for (var x = 0; x < 12; x++) {
+                for (var y = 0; y < 7; y++) {
+                    let cell = vec2(f32(x), f32(y));
+                    let k = clamp((t - hash(cell) * 0.4) / 0.6, 0.0, 1.0);
+                    let center = (cell + 0.5) * cell_size;
+                    let moved = center + vec2((hash(cell + 7.0) - 0.5) * 0.4 * size.x * k, 1.2 * size.y * k * k);
+                    let angle = (hash(cell + 13.0) - 0.5) * 6.0 * k;
+                    let d = px - moved;
+                    let local = vec2(d.x * cos(angle) + d.y * sin(angle), d.y * cos(angle) - d.x * sin(angle)) / (1.0 - 0.5 * k);
+                    if all(abs(local) <= cell_size * 0.5) {
+                        c = vec4(shot((center + local) / size).rgb, 1.0);
+                    }
+                }
+            }
+        }
+        // slide
+        default: {
+            let e = t * t * (3.0 - 2.0 * t);
+            c = shot(uv + vec2(e, 0.0));
+        }
+    }
+    return c;
+}
@@ -0,0 +1,261 @@
+use super::plugin::*;
+use super::sim::*;
+use crate::prelude::*;
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Room {
+    ShowStage,
+    DiningArea,
+    Backstage,
+    Restrooms,
+    Kitchen,
+    WestHall,
+    EastHall,
+    LeftDoor,
+    RightDoor,
+}
+
+impl Room {
+    pub fn door(&self) -> Option<Side> {
+        match self {
+            Room::LeftDoor => Some(Side::Left),
+            Room::RightDoor => Some(Side::Right),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// What keeps an animatronic at the office door from getting in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttackCondition {
+    /// Gets in after `kill_time` at an open door.
+    Door,
+    /// Goes away shortly after it is lit, otherwise like `Door`.
+    Light,
+    /// Gets in after `kill_time` at an open door without being watched, watching resets the count.
+    CameraWatch,
+}
+
+/// How an animatronic is shown in the office and on the feeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimatronicLook {
+    /// Own door views on both sides and the lobster for a jumpscare.
+    Bear,
+    /// Stands at the left door.
+    Bonnie,
+    /// Stands at the right door.
+    Chicka,
+}
+
+/// Simulation side of an animatronic, see `FnafSim`.
+#[derive(Clone, Debug)]
+pub struct Animatronic {
+    /// Key into `NightConfig::animatronics`.
+    pub name: &'static str,
+    pub start: Room,
+    pub room: Room,
+    /// One way passages it takes on successful movement opportunities.
+    pub graph: &'static [(Room, Room)],
+    pub attack: AttackCondition,
+    pub look: AnimatronicLook,
+    pub opportunity: f32,
+    pub until_leaves: f32,
+    pub until_kills: f32,
+}
+
+impl Animatronic {
+    pub fn new(name: &'static str, start: Room, graph: &'static [(Room, Room)], attack: AttackCondition, look: AnimatronicLook) -> Self {
+        Self {
+            name,
+            start,
+            room: start,
+            graph,
+            attack,
+            look,
+            opportunity: 0.0,
+            until_leaves: 0.0,
+            until_kills: 0.0,
+        }
+    }
+
+    /// Everyone a night starts with, views refer to them by index.
+    pub fn roster() -> Vec<Self> {
+        vec![Self::bear(), Self::bonnie(), Self::chicka()]
+    }
+
+    pub fn bear() -> Self {
+        Self::new("bear", Room::ShowStage, &[
+            (Room::ShowStage, Room::LeftDoor),
+            (Room::ShowStage, Room::RightDoor),
+        ], AttackCondition::Door, AnimatronicLook::Bear)
+    }
+
+    pub fn bonnie() -> Self {
+        Self::new("bonnie", Room::ShowStage, &[
+            (Room::ShowStage, Room::DiningArea),
+            (Room::DiningArea, Room::Backstage),
+            (Room::DiningArea, Room::WestHall),
+            (Room::Backstage, Room::WestHall),
+            (Room::WestHall, Room::LeftDoor),
+        ], AttackCondition::CameraWatch, AnimatronicLook::Bonnie)
+    }
+
+    pub fn chicka() -> Self {
+        Self::new("chicka", Room::ShowStage, &[
+            (Room::ShowStage, Room::DiningArea),
+            (Room::DiningArea, Room::Restrooms),
+            (Room::DiningArea, Room::Kitchen),
+            (Room::Restrooms, Room::EastHall),
+            (Room::Kitchen, Room::EastHall),
+            (Room::EastHall, Room::RightDoor),
+        ], AttackCondition::Light, AnimatronicLook::Chicka)
+    }
+
+    pub fn next_rooms(&self) -> Vec<Room> {
+        self.graph.iter().filter(|(from, _)| *from == self.room).map(|(_, to)| *to).collect()
+    }
+}
+
+#[derive(Component)]
+pub struct AnimatronicView {
+    /// Index into `FnafSim::animatronics`.
+    pub index: usize,
+    /// Shown on defeat, the lobster when `None`.
+    pub jumpscare: Option<Entity>,
+    /// How it looks on the camera feeds.
+    pub portrait: Sprite,
+}
+
+/// Animatronic standing in `room`, shown while the light of that door is on.
+#[derive(Component)]
+pub struct AnimatronicSprite {
+    pub index: usize,
+    pub room: Room,
+}
+
+/// Animatronic that got into the office.
+#[derive(Resource)]
+pub struct Jumpscare(pub Option<Entity>);
+
+
+pub fn spawn_animatronics(
+    cmd: &mut Commands,
+    fnaf_assets: &FNAFAssets,
+) {
+    for (index, animatronic) in Animatronic::roster().iter().enumerate() {
+        let (name, image, room, x) = match animatronic.look {
+            AnimatronicLook::Bonnie => ("Bonnie", &fnaf_assets.bonnie, Room::LeftDoor, -ANIMATRONIC_DOOR_X),
+            AnimatronicLook::Chicka => ("Chicka", &fnaf_assets.chicka, Room::RightDoor, ANIMATRONIC_DOOR_X),
+            AnimatronicLook::Bear => {
+                cmd.spawn((
+                    DespawnOnExit(STATE),
+                    Name::new("Bear"),
+                    AnimatronicView {
+                        index,
+                        jumpscare: None,
+                        portrait: Sprite {
+                            image: fnaf_assets.freddy.clone(),
+                            rect: Some(freddy_rect()),
+                            ..default()
+                        },
+                    },
+                ));
+                for (room, image) in [(Room::LeftDoor, &fnaf_assets.fred_l), (Room::RightDoor, &fnaf_assets.fred_r)] {
+                    cmd.spawn((
+                        DespawnOnExit(STATE),
+                        Sprite {
+                            image: image.clone(),
+                            ..default()
+                        },
+                        AnimatronicSprite { index, room },
+                        Transform::from_xyz(0., 0., 2.),
+                        Visibility::Hidden,
+                    ));
+                }
+                continue;
+            }
+        };
+        let sprite = Sprite {
+            image: image.clone(),
+            rect: Some(animatronic_rect()),
+            ..default()
+        };
+        let jumpscare = cmd.spawn((
+            DespawnOnExit(STATE),
+            sprite.clone(),
+            Transform::from_xyz(0., 0., 100.).with_scale(Vec3::splat(ANIMATRONIC_JUMPSCARE_SCALE)),
+            Visibility::Hidden,
+        )).id();
+        cmd.spawn((
+            DespawnOnExit(STATE),
+            Name::new(name),
+            AnimatronicView { index, jumpscare: Some(jumpscare), portrait: sprite.clone() },
+        ));
+        cmd.spawn((
+            DespawnOnExit(STATE),
+            sprite,
+            AnimatronicSprite { index, room },
+            Transform::from_xyz(x, ANIMATRONIC_DOOR_Y, 2.).with_scale(Vec3::splat(ANIMATRONIC_DOOR_SCALE)),
+            Visibility::Hidden,
+        ));
+    }
+}
+
+
+/// Door views follow the lights and whoever is standing there.
+pub fn update_door_views(
+    mut cmd: Commands,
+    sim: Res<FnafSim>,
+    fnaf_assets: Res<FNAFAssets>,
+    mut views: Query<(&mut Visibility, &AnimatronicSprite), Without<Environment>>,
+    mut env: Query<(&mut Visibility, &Environment)>,
+) {
+    let mut occupied = [false, false];
+    for (mut visibility, view) in views.iter_mut() {
+        let Some(side) = view.room.door() else {continue;};
+        let here = sim.animatronics.get(view.index).is_some_and(|a| a.room == view.room);
+        let visible = here && sim.controls.light_on(side);
+        occupied[side as usize] |= visible;
+        if visible && *visibility == Visibility::Hidden {
+            cmd.spawn((
+                DespawnOnExit(STATE),
+                AudioPlayer(fnaf_assets.ur.clone()),
+                PlaybackSettings {
+                    mode: bevy::audio::PlaybackMode::Once,
+                    ..default()
+                },
+            ));
+        }
+        visibility.set_if_neq(if visible {Visibility::Visible} else {Visibility::Hidden});
+    }
+    for (mut visibility, env) in env.iter_mut() {
+        let side = match env {
+            Environment::LLight => Side::Left,
+            Environment::RLight => Side::Right,
+            _ => continue,
+        };
+        let visible = sim.controls.light_on(side) && !occupied[side as usize];
+        visibility.set_if_neq(if visible {Visibility::Visible} else {Visibility::Hidden});
+    }
+}
+
+
+fn animatronic_rect() -> Rect {
+    Rect::new(0.0, 16.0, 32.0, 32.0)
+}
+
+fn freddy_rect() -> Rect {
+    Rect::new(0.0, 0.0, 48.0, 48.0)
+}
+
+const ANIMATRONIC_DOOR_X: f32 = 560.0;
+const ANIMATRONIC_DOOR_Y: f32 = -40.0;
+const ANIMATRONIC_DOOR_SCALE: f32 = 12.0;
+const ANIMATRONIC_JUMPSCARE_SCALE: f32 = 40.0;
@@ -0,0 +1,392 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::camera::{ImageRenderTarget, RenderTarget, ScalingMode};
+use bevy::camera::visibility::RenderLayers;
+use bevy::color::palettes::css::{GRAY, WHITE};
+use bevy::math::curve::Curve;
+use bevy::render::render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages};
+use rand::Rng;
+
+use super::animatronics::*;
+use super::plugin::*;
+use super::sim::*;
+use crate::prelude::*;
+
+
+pub const FNAF_FEED_LAYERS: RenderLayers = RenderLayers::layer(4);
+
+pub struct CameraNode {
+    pub room: Room,
+    pub label: &'static str,
+    /// Button position on the map, fractions of the map panel.
+    pub map_pos: Vec2,
+}
+
+pub fn camera_nodes() -> [CameraNode; 9] {
+    [
+        CameraNode { room: Room::ShowStage, label: "1A", map_pos: vec2(0.45, 0.05) },
+        CameraNode { room: Room::DiningArea, label: "1B", map_pos: vec2(0.4, 0.25) },
+        CameraNode { room: Room::Backstage, label: "5", map_pos: vec2(0.05, 0.25) },
+        CameraNode { room: Room::Restrooms, label: "7", map_pos: vec2(0.85, 0.25) },
+        CameraNode { room: Room::Kitchen, label: "6", map_pos: vec2(0.8, 0.5) },
+        CameraNode { room: Room::WestHall, label: "2A", map_pos: vec2(0.25, 0.6) },
+        CameraNode { room: Room::LeftDoor, label: "2B", map_pos: vec2(0.25, 0.82) },
+        CameraNode { room: Room::EastHall, label: "4A", map_pos: vec2(0.6, 0.6) },
+        CameraNode { room: Room::RightDoor, label: "4B", map_pos: vec2(0.6, 0.82) },
+    ]
+}
+
+/// Camera tablet, flips up over the office while `open`.
+#[derive(Resource)]
+pub struct CameraMonitor {
+    pub open: bool,
+    /// 0 when down, 1 when fully up.
+    pub flip: f32,
+    pub selected: Room,
+    /// Static left from the last feed switch, fades to 0.
+    pub noise: f32,
+    pub feed: Handle<Image>,
+    pub static_image: Handle<Image>,
+}
+
+#[derive(Component)]
+pub struct FeedCamera;
+
+#[derive(Component)]
+pub struct TabletPanel;
+
+#[derive(Component)]
+pub struct FeedStatic;
+
+#[derive(Component)]
+pub struct FeedLabel;
+
+#[derive(Component)]
+pub struct MapButton(pub Room);
+
+/// Animatronic as seen on the feed of `room`.
+#[derive(Component)]
+pub struct FeedOverlay {
+    pub index: usize,
+    pub room: Room,
+}
+
+
+pub fn setup_cameras(
+    mut cmd: Commands,
+    fnaf_assets: Res<FNAFAssets>,
+    asset_server: Res<AssetServer>,
+    animatronics: Query<&AnimatronicView>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let extent = Extent3d {
+        width: FEED_SIZE.x,
+        height: FEED_SIZE.y,
+        ..default()
+    };
+    let mut feed = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size: extent,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    feed.resize(extent);
+    let feed = images.add(feed);
+    let static_image = images.add(Image::new_fill(
+        Extent3d {
+            width: FEED_STATIC_SIZE.x,
+            height: FEED_STATIC_SIZE.y,
+            ..default()
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    ));
+
+    let nodes = camera_nodes();
+    let first = nodes[0].room;
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("FeedCamera"),
+        FeedCamera,
+        Camera2d,
+        Camera {
+            order: -1,
+            is_active: false,
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..Default::default()
+        },
+        RenderTarget::Image(ImageRenderTarget{ handle: feed.clone(), scale_factor: 1.0 }),
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed { width: FEED_VIEW.x, height: FEED_VIEW.y },
+            ..OrthographicProjection::default_2d()
+        }),
+        Transform::from_translation(feed_position(first).extend(0.0)),
+        FNAF_FEED_LAYERS,
+    ));
+    // every room is a dim backdrop somewhere the office camera does not render
+    for (i, node) in nodes.iter().enumerate() {
+        let pos = feed_position(node.room);
+        cmd.spawn((
+            DespawnOnExit(STATE),
+            Name::new(format!("Feed {}", node.label)),
+            Sprite {
+                image: fnaf_assets.window.clone(),
+                color: Color::hsl(i as f32 * 40.0, 0.2, 0.35),
+                custom_size: Some(FEED_VIEW),
+                ..default()
+            },
+            Transform::from_translation(pos.extend(0.0)),
+            FNAF_FEED_LAYERS,
+        ));
+        for view in animatronics.iter() {
+            let mut sprite = view.portrait.clone();
+            sprite.color = Color::srgb(0.7, 0.7, 0.8);
+            let offset = vec2((view.index as f32 - 1.0) * FEED_OVERLAY_SPACING, -FEED_VIEW.y * 0.1);
+            cmd.spawn((
+                DespawnOnExit(STATE),
+                sprite,
+                FeedOverlay { index: view.index, room: node.room },
+                Transform::from_translation((pos + offset).extend(1.0)).with_scale(Vec3::splat(FEED_OVERLAY_SCALE)),
+                Visibility::Hidden,
+                FNAF_FEED_LAYERS,
+            ));
+        }
+    }
+
+    let font = asset_server.load("fonts/kaivs_minegram_v1.ttf");
+    let text_font = TextFont {
+        font: font.clone(),
+        font_size: 33.0,
+        ..default()
+    };
+    let panel = cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("Tablet"),
+        TabletPanel,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            top: Val::Percent(100.0),
+            ..default()
+        },
+        BackgroundColor(Color::srgb(0.05, 0.05, 0.06)),
+        ZIndex(10),
+    )).id();
+    cmd.spawn((
+        ChildOf(panel),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(3.0),
+            top: Val::Percent(6.0),
+            width: Val::Percent(68.0),
+            height: Val::Percent(80.0),
+            ..default()
+        },
+        ImageNode::new(feed.clone()),
+    )).with_child((
+        FeedStatic,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.0),
+            height: Val::Percent(100.0),
+            ..default()
+        },
+        ImageNode::new(static_image.clone()),
+    )).with_child((
+        FeedLabel,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(16.0),
+            top: Val::Px(12.0),
+            ..default()
+        },
+        Text::new(""),
+        text_font.clone(),
+        TextColor(Color::Srgba(WHITE)),
+    ));
+    let map = cmd.spawn((
+        ChildOf(panel),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Percent(3.0),
+            top: Val::Percent(30.0),
+            width: Val::Percent(24.0),
+            height: Val::Percent(50.0),
+            border: UiRect::all(Val::Px(2.0)),
+            ..default()
+        },
+        BorderColor::all(Color::Srgba(GRAY)),
+    )).id();
+    for node in nodes {
+        let room = node.room;
+        cmd.spawn((
+            ChildOf(map),
+            MapButton(room),
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(node.map_pos.x * 100.0),
+                top: Val::Percent(node.map_pos.y * 100.0),
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                ..default()
+            },
+            BackgroundColor(Color::Srgba(GRAY)),
+        )).with_child((
+            Text::new(node.label),
+            text_font.clone(),
+            TextColor(Color::Srgba(WHITE)),
+        )).observe(move |_: On<Pointer<Click>>, mut monitor: ResMut<CameraMonitor>| {
+            if monitor.selected == room {return;}
+            monitor.selected = room;
+            monitor.noise = 1.0;
+        });
+    }
+
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("TabletButton"),
+        Button,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(35.0),
+            bottom: Val::Percent(2.0),
+            width: Val::Percent(30.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BackgroundColor(Color::WHITE.with_alpha(0.15)),
+        ZIndex(11),
+    )).with_child((
+        Text::new("CAM"),
+        text_font,
+        TextColor(Color::Srgba(WHITE)),
+    )).observe(|_: On<Pointer<Click>>, state: Res<State<LocalState>>, mut monitor: ResMut<CameraMonitor>| {
+        if *state.get() != LocalState::Game {return;}
+        monitor.open = !monitor.open;
+    });
+
+    cmd.insert_resource(CameraMonitor {
+        open: false,
+        flip: 0.0,
+        selected: first,
+        noise: 1.0,
+        feed,
+        static_image,
+    });
+}
+
+pub fn toggle_tablet(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut monitor: ResMut<CameraMonitor>,
+) {
+    if keyboard_input.just_pressed(FNAF_TABLET_KEY) {
+        monitor.open = !monitor.open;
+    }
+}
+
+/// The simulation only cares where the tablet is pointed, the flip is for show.
+pub fn send_tablet_input(
+    monitor: Res<CameraMonitor>,
+    mut sim: ResMut<FnafSim>,
+    mut log: ResMut<InputLog>,
+) {
+    if sim.controls.tablet != monitor.open {
+        log.apply(&mut sim, SimInput::Tablet(monitor.open));
+    }
+    if sim.controls.camera != monitor.selected {
+        log.apply(&mut sim, SimInput::Camera(monitor.selected));
+    }
+}
+
+pub fn drop_tablet(
+    mut monitor: ResMut<CameraMonitor>,
+) {
+    monitor.open = false;
+}
+
+pub fn flip_tablet(
+    mut monitor: ResMut<CameraMonitor>,
+    mut panel: Single<&mut Node, With<TabletPanel>>,
+    mut camera: Single<&mut Camera, With<FeedCamera>>,
+    time: Res<Time>,
+) {
+    let target = if monitor.open {1.0} else {0.0};
+    let was_up = monitor.flip >= 1.0;
+    let step = time.delta_secs() / TABLET_FLIP_TIME;
+    monitor.flip = if target > monitor.flip {(monitor.flip + step).min(target)} else {(monitor.flip - step).max(target)};
+    // every look at the feeds starts from static
+    if monitor.flip >= 1.0 && !was_up {
+        monitor.noise = 1.0;
+    }
+    let shown = EaseFunction::SineOut.sample_clamped(monitor.flip);
+    panel.top = Val::Percent((1.0 - shown) * 100.0);
+    camera.is_active = monitor.flip > 0.0;
+}
+
+pub fn update_feed(
+    mut monitor: ResMut<CameraMonitor>,
+    mut camera: Single<&mut Transform, With<FeedCamera>>,
+    sim: Res<FnafSim>,
+    mut overlays: Query<(&mut Visibility, &FeedOverlay)>,
+    mut static_node: Single<&mut ImageNode, With<FeedStatic>>,
+    mut label: Single<&mut Text, With<FeedLabel>>,
+    mut buttons: Query<(&mut BackgroundColor, &MapButton)>,
+    mut images: ResMut<Assets<Image>>,
+    time: Res<Time>,
+) {
+    let selected = monitor.selected;
+    if monitor.flip <= 0.0 {return;}
+
+    camera.translation = feed_position(selected).extend(0.0);
+    for (mut visibility, overlay) in overlays.iter_mut() {
+        let here = overlay.room == selected && sim.animatronics.get(overlay.index).is_some_and(|a| a.room == overlay.room);
+        visibility.set_if_neq(if here {Visibility::Visible} else {Visibility::Hidden});
+    }
+    if let Some(node) = camera_nodes().iter().find(|n| n.room == selected) {
+        label.0 = format!("CAM {}", node.label);
+    }
+    for (mut color, button) in buttons.iter_mut() {
+        color.0 = if button.0 == selected {Color::srgb(0.2, 0.6, 0.2)} else {Color::Srgba(GRAY)};
+    }
+
+    monitor.noise = (monitor.noise - time.delta_secs() / FEED_STATIC_TIME).max(0.0);
+    static_node.color = Color::WHITE.with_alpha(FEED_STATIC_BASE + (1.0 - FEED_STATIC_BASE) * monitor.noise);
+    let Some(image) = images.get_mut(&monitor.static_image) else {return;};
+    let Some(data) = image.data.as_mut() else {return;};
+    let mut rng = rand::rng();
+    for px in data.chunks_mut(4) {
+        let v: u8 = rng.random();
+        px[0] = v;
+        px[1] = v;
+        px[2] = v;
+    }
+}
+
+
+fn feed_position(room: Room) -> Vec2 {
+    let i = camera_nodes().iter().position(|n| n.room == room).unwrap_or(0);
+    FEED_ORIGIN + vec2(i as f32 * FEED_VIEW.x * 2.0, 0.0)
+}
+
+
+pub const FNAF_TABLET_KEY: KeyCode = KeyCode::Space;
+const FEED_SIZE: UVec2 = UVec2::new(864, 486);
+const FEED_STATIC_SIZE: UVec2 = UVec2::new(160, 90);
+const FEED_VIEW: Vec2 = Vec2::new(1728.0, 972.0);
+const FEED_ORIGIN: Vec2 = Vec2::new(0.0, 10000.0);
+const FEED_OVERLAY_SCALE: f32 = 10.0;
+const FEED_OVERLAY_SPACING: f32 = 400.0;
+const FEED_STATIC_TIME: f32 = 0.4;
+const FEED_STATIC_BASE: f32 = 0.12;
+const TABLET_FLIP_TIME: f32 = 0.25;
@@ -1 +1,8 @@
-pub mod plugin;
\ No newline at end of file
+pub mod plugin;
+pub mod nights;
+pub mod animatronics;
+pub mod cameras;
+pub mod sim;
+
+#[cfg(test)]
+mod tests;
\ No newline at end of file
@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+
+use bevy::asset::{AssetLoader, LoadContext, io::Reader};
+use bevy::color::palettes::css::{GRAY, WHITE, YELLOW};
+use games::replay::RunSeed;
+use games::saves;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::plugin::*;
+use super::sim::*;
+use crate::prelude::*;
+
+
+/// Rules of one night, loaded from `data/fnaf/*.night.ron`.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct NightConfig {
+    pub name: String,
+    /// Hours until 6 AM, the first one is shown as 12.
+    pub hours: usize,
+    /// Seconds per in-game hour.
+    pub hour_length: f32,
+    /// Seconds before the animatronics get their first movement opportunity.
+    pub start_delay: f32,
+    pub drain: PowerDrain,
+    /// Seconds between movement opportunities.
+    pub movement_interval: f32,
+    pub animatronics: BTreeMap<String, AnimatronicConfig>,
+}
+
+/// Battery percent per second.
+#[derive(Deserialize, Clone, Debug)]
+pub struct PowerDrain {
+    pub idle: f32,
+    pub door: f32,
+    pub light: f32,
+    /// While the camera tablet is up.
+    pub camera: f32,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AnimatronicConfig {
+    /// AI level 0-20 for every hour, the last one holds until the end of the night.
+    pub aggression: Vec<u32>,
+    /// Seconds it waits at a door before leaving.
+    pub stay: (f32, f32),
+    /// Seconds at an open door before the jumpscare.
+    pub kill_time: f32,
+}
+
+impl NightConfig {
+    /// Chance to move on each opportunity is `level / MAX_AI_LEVEL`.
+    pub fn ai_level(&self, animatronic: &str, hour: usize) -> u32 {
+        self.animatronics.get(animatronic)
+            .and_then(|a| a.aggression.get(hour).or(a.aggression.last()))
+            .copied()
+            .unwrap_or(0)
+            .min(MAX_AI_LEVEL)
+    }
+
+    /// Catches rules the simulation can not run with.
+    pub fn validate(&self) -> Result<(), NightConfigLoaderError> {
+        for (name, rules) in self.animatronics.iter() {
+            // also false for NaN
+            if !(rules.stay.0 <= rules.stay.1) {
+                return Err(NightConfigLoaderError::Stay(name.clone(), rules.stay.0, rules.stay.1));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct NightConfigLoader;
+
+#[derive(Debug, Error)]
+pub enum NightConfigLoaderError {
+    #[error("Could not read night config: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse night config: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("Stay of {0} goes from {1} down to {2}")]
+    Stay(String, f32, f32),
+}
+
+impl AssetLoader for NightConfigLoader {
+    type Asset = NightConfig;
+    type Settings = ();
+    type Error = NightConfigLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let config: NightConfig = ron::de::from_bytes(&bytes)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["night.ron"]
+    }
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Night {
+    Story(u32),
+    Custom,
+}
+
+/// Night being played, its rules live in `FnafSim`.
+#[derive(Resource)]
+pub struct ActiveNight {
+    pub night: Night,
+    /// Replaying the recorded `InputLog` with it gives the same night.
+    pub seed: u64,
+}
+
+/// Survived nights and the custom night setup, kept between runs.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct FnafSave {
+    pub completed: Vec<u32>,
+    pub custom_levels: BTreeMap<String, u32>,
+    /// Highest sum of AI levels survived on the custom night.
+    pub best_custom: Option<u32>,
+}
+
+impl FnafSave {
+    pub fn load() -> Self {
+        saves::load(FNAF_SAVE_PATH)
+    }
+
+    pub fn store(&self) {
+        saves::store(FNAF_SAVE_PATH, self);
+    }
+
+    pub fn unlocked(&self, night: Night) -> bool {
+        let last = self.completed.iter().max().copied().unwrap_or(0);
+        match night {
+            Night::Story(n) => n <= last + 1,
+            Night::Custom => last >= STORY_NIGHTS,
+        }
+    }
+}
+
+
+#[derive(Component)]
+pub struct AiLevelText(pub String);
+
+pub fn setup_night_select(
+    mut cmd: Commands,
+    asset_server: Res<AssetServer>,
+    save: Option<Res<FnafSave>>,
+) {
+    let save = match save {
+        Some(save) => save.clone(),
+        None => {
+            let save = FnafSave::load();
+            cmd.insert_resource(save.clone());
+            save
+        }
+    };
+    let font = asset_server.load("fonts/kaivs_minegram_v1.ttf");
+    let root = cmd.spawn((
+        DespawnOnExit(LocalState::NightSelect),
+        menu_node(),
+        BackgroundColor(Color::BLACK.with_alpha(0.85)),
+    )).id();
+    cmd.spawn((ChildOf(root), menu_text("Select night", &font, WHITE)));
+    for n in 1..=STORY_NIGHTS {
+        let night = Night::Story(n);
+        let unlocked = save.unlocked(night);
+        let done = if save.completed.contains(&n) {" *"} else {""};
+        let button = spawn_button(&mut cmd, root, &format!("Night {n}{done}"), &font, unlocked);
+        if unlocked {
+            cmd.entity(button).observe(move |_: On<Pointer<Click>>, mut cmd: Commands| {
+                cmd.run_system_cached_with(start_night, night);
+            });
+        }
+    }
+    let unlocked = save.unlocked(Night::Custom);
+    let label = match save.best_custom {
+        Some(best) => format!("Custom night (best {best})"),
+        None => "Custom night".to_string(),
+    };
+    let button = spawn_button(&mut cmd, root, &label, &font, unlocked);
+    if unlocked {
+        cmd.entity(button).observe(|_: On<Pointer<Click>>, mut state: ResMut<NextState<LocalState>>| {
+            state.set(LocalState::CustomNight);
+        });
+    }
+}
+
+pub fn setup_custom_night(
+    mut cmd: Commands,
+    asset_server: Res<AssetServer>,
+    fnaf_assets: Res<FNAFAssets>,
+    configs: Res<Assets<NightConfig>>,
+    mut save: ResMut<FnafSave>,
+) {
+    let Some(config) = configs.get(&fnaf_assets.custom_night) else {return;};
+    for name in config.animatronics.keys() {
+        save.custom_levels.entry(name.clone()).or_insert(0);
+    }
+    let font = asset_server.load("fonts/kaivs_minegram_v1.ttf");
+    let root = cmd.spawn((
+        DespawnOnExit(LocalState::CustomNight),
+        menu_node(),
+        BackgroundColor(Color::BLACK.with_alpha(0.85)),
+    )).id();
+    cmd.spawn((ChildOf(root), menu_text("Custom night", &font, WHITE)));
+    for name in config.animatronics.keys() {
+        let row = cmd.spawn((
+            ChildOf(root),
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(16.0),
+                ..default()
+            },
+        )).id();
+        let minus = spawn_button(&mut cmd, row, "<", &font, true);
+        cmd.spawn((ChildOf(row), menu_text("", &font, YELLOW), AiLevelText(name.clone())));
+        let plus = spawn_button(&mut cmd, row, ">", &font, true);
+        for (button, step) in [(minus, -1), (plus, 1)] {
+            let name = name.clone();
+            cmd.entity(button).observe(move |_: On<Pointer<Click>>, mut save: ResMut<FnafSave>| {
+                let Some(level) = save.custom_levels.get_mut(&name) else {return;};
+                *level = (*level as i32 + step).clamp(0, MAX_AI_LEVEL as i32) as u32;
+            });
+        }
+    }
+    let start = spawn_button(&mut cmd, root, "Start", &font, true);
+    cmd.entity(start).observe(|_: On<Pointer<Click>>, mut cmd: Commands| {
+        cmd.run_system_cached_with(start_night, Night::Custom);
+    });
+    let back = spawn_button(&mut cmd, root, "Back", &font, true);
+    cmd.entity(back).observe(|_: On<Pointer<Click>>, mut state: ResMut<NextState<LocalState>>| {
+        state.set(LocalState::NightSelect);
+    });
+}
+
+pub fn update_custom_night_text(
+    save: Res<FnafSave>,
+    mut texts: Query<(&mut Text, &AiLevelText)>,
+) {
+    for (mut text, ai) in texts.iter_mut() {
+        let level = save.custom_levels.get(&ai.0).copied().unwrap_or(0);
+        **text = format!("{}: {level:>2}", ai.0);
+    }
+}
+
+fn start_night(
+    In(night): In<Night>,
+    mut cmd: Commands,
+    fnaf_assets: Res<FNAFAssets>,
+    configs: Res<Assets<NightConfig>>,
+    save: Res<FnafSave>,
+    mut run_seed: ResMut<RunSeed>,
+    mut state: ResMut<NextState<LocalState>>,
+) {
+    let handle = match night {
+        Night::Story(n) => fnaf_assets.nights.get(n as usize - 1),
+        Night::Custom => Some(&fnaf_assets.custom_night),
+    };
+    let Some(mut config) = handle.and_then(|h| configs.get(h)).cloned() else {
+        warn!("No config for {night:?}");
+        return;
+    };
+    if night == Night::Custom {
+        for (name, animatronic) in config.animatronics.iter_mut() {
+            animatronic.aggression = vec![save.custom_levels.get(name).copied().unwrap_or(0)];
+        }
+    }
+    let seed = run_seed.next();
+    info!("Starting {} with seed {seed}", config.name);
+    cmd.insert_resource(FnafSim::new(config, seed));
+    cmd.insert_resource(InputLog::default());
+    cmd.insert_resource(ActiveNight { night, seed });
+    state.set(LocalState::InitialAnim);
+}
+
+/// Called once 6 AM is reached.
+pub fn record_night(
+    night: Res<ActiveNight>,
+    mut save: ResMut<FnafSave>,
+) {
+    match night.night {
+        Night::Story(n) => {
+            if !save.completed.contains(&n) {
+                save.completed.push(n);
+            }
+        }
+        Night::Custom => {
+            let total = save.custom_levels.values().sum();
+            save.best_custom = Some(save.best_custom.map_or(total, |best| best.max(total)));
+        }
+    }
+    save.store();
+}
+
+
+fn menu_node() -> Node {
+    Node {
+        position_type: PositionType::Absolute,
+        width: Val::Percent(100.0),
+        height: Val::Percent(100.0),
+        flex_direction: FlexDirection::Column,
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        row_gap: Val::Px(12.0),
+        ..default()
+    }
+}
+
+fn menu_text(text: &str, font: &Handle<Font>, color: Srgba) -> impl Bundle {
+    (
+        Text::new(text),
+        TextFont {
+            font: font.clone(),
+            font_size: 33.0,
+            ..default()
+        },
+        TextColor(Color::Srgba(color)),
+    )
+}
+
+fn spawn_button(cmd: &mut Commands, parent: Entity, text: &str, font: &Handle<Font>, enabled: bool) -> Entity {
+    cmd.spawn((
+        ChildOf(parent),
+        Button,
+        Node {
+            padding: UiRect::axes(Val::Px(16.0), Val::Px(4.0)),
+            ..default()
+        },
+        BackgroundColor(Color::WHITE.with_alpha(0.1)),
+    )).with_child(menu_text(text, font, if enabled {WHITE} else {GRAY})).id()
+}
+
+
+pub const STORY_NIGHTS: u32 = 5;
+pub const MAX_AI_LEVEL: u32 = 20;
+const FNAF_SAVE_PATH: &str = "saves/fnaf.ron";
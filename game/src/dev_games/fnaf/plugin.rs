@@ -1,20 +1,27 @@
-use bevy::{audio::Volume, color::palettes::css::{RED, WHITE}};
+use bevy::{audio::Volume, color::palettes::css::WHITE};
 use bevy_asset_loader::asset_collection::AssetCollection;
+use games::clickable::Clickable;
 use games::hints::{HintAssets, KeyHint};
-use rand::Rng;
+use games::transition::RequestTransition;
 
+use super::animatronics::*;
+use super::cameras::*;
+use super::nights::*;
+use super::sim::*;
 use crate::prelude::*;
 
 pub struct FNAFPlugin;
 
-const STATE: AppState = AppState::Fnaf;
+pub const STATE: AppState = AppState::Fnaf;
 const NEXT_STATE: AppState = AppState::End;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, SubStates)]
 #[source(AppState = STATE)]
 #[states(scoped_entities)]
-enum LocalState {
+pub enum LocalState {
     #[default]
+    NightSelect,
+    CustomNight,
     InitialAnim,
     Game,
     Defeat,
@@ -40,7 +47,7 @@ pub struct FNAFAssets {
     #[asset(path = "sounds/fnaf/mem.mp3")]
     mem: Handle<AudioSource>,
     #[asset(path = "sounds/fnaf/ur.mp3")]
-    ur: Handle<AudioSource>,
+    pub ur: Handle<AudioSource>,
 
     #[asset(path = "images/fnaf/room.png")]
     pub room: Handle<Image>,
@@ -55,11 +62,32 @@ pub struct FNAFAssets {
     #[asset(path = "images/fnaf/light_R.png")]
     light_r: Handle<Image>,
     #[asset(path = "images/fnaf/fred_l.png")]
-    fred_l: Handle<Image>,
+    pub fred_l: Handle<Image>,
     #[asset(path = "images/fnaf/fred_r.png")]
-    fred_r: Handle<Image>,
+    pub fred_r: Handle<Image>,
+    #[asset(path = "maps/miami/bonnie.png")]
+    pub bonnie: Handle<Image>,
+    #[asset(path = "maps/miami/chicka.png")]
+    pub chicka: Handle<Image>,
+    #[asset(path = "maps/miami/faz.png")]
+    pub freddy: Handle<Image>,
+    #[asset(path = "images/fnaf/window.png")]
+    pub window: Handle<Image>,
+    #[asset(path = "images/fnaf/nose.png")]
+    nose: Handle<Image>,
     #[asset(path = "images/fnaf/lobster.jpg")]
     lobster_pic: Handle<Image>,
+
+    #[asset(paths(
+        "data/fnaf/night1.night.ron",
+        "data/fnaf/night2.night.ron",
+        "data/fnaf/night3.night.ron",
+        "data/fnaf/night4.night.ron",
+        "data/fnaf/night5.night.ron",
+    ), collection(typed))]
+    pub nights: Vec<Handle<NightConfig>>,
+    #[asset(path = "data/fnaf/custom.night.ron")]
+    pub custom_night: Handle<NightConfig>,
 }
 
 impl Plugin for FNAFPlugin {
@@ -67,26 +95,33 @@ impl Plugin for FNAFPlugin {
         app
             // .register_type::<CameraCenter>()
             // .register_type::<SpawnPoint>()
+            .init_asset::<NightConfig>()
+            .init_asset_loader::<NightConfigLoader>()
             .add_sub_state::<LocalState>()
             // .add_observer(spawnpoint_handler)
             // .add_observer(camera_handler)
             // .add_observer(on_collider_spawned)
-            .add_systems(OnEnter(STATE), (setup, init_rects))
+            .add_systems(OnEnter(STATE), (setup, setup_cameras).chain())
+            .add_systems(OnEnter(LocalState::NightSelect), setup_night_select)
+            .add_systems(OnEnter(LocalState::CustomNight), setup_custom_night)
+            .add_systems(Update, update_custom_night_text.run_if(in_state(LocalState::CustomNight)))
             .add_systems(Update, tick_transition.run_if(in_state(LocalState::InitialAnim)))
             // .add_systems(OnEnter(LocalState::Game), begin_game)
+            .add_systems(OnEnter(LocalState::Game), spawn_controls)
             .add_systems(Update, (
-                update_mouse_pos, update_text, handle_faz_time, play_mem, handle_faz,
-                (handle_rects, handle_game_logic).chain()).run_if(in_state(LocalState::Game)))
+                update_text, play_mem,
+                (toggle_tablet, send_tablet_input, advance_sim, handle_outcome, update_feed, update_door_views).chain()
+            ).run_if(in_state(LocalState::Game)))
+            .add_systems(OnExit(LocalState::Game), drop_tablet)
+            .add_systems(Update, flip_tablet.run_if(resource_exists::<CameraMonitor>))
             .add_systems(Update, defeat.run_if(in_state(LocalState::Defeat)))
+            .add_systems(OnEnter(LocalState::Win), record_night)
             .add_systems(Update, win.run_if(in_state(LocalState::Win)))
             .add_systems(OnExit(STATE), cleanup)
             ;
     }
 }
 
-#[derive(Resource)]
-pub struct MousePos(pub Option<Vec2>);
-
 #[derive(Component)]
 pub struct DbgSprite;
 
@@ -99,381 +134,136 @@ pub struct TimeText;
 #[derive(Component)]
 pub struct TimeText1;
 
-#[derive(Resource)]
-pub struct Rects {
-    pub red_right: Rect,
-    pub red_left: Rect,
-    pub white_right: Rect,
-    pub white_left: Rect,
-    pub faz: Rect,
-}
-
-#[derive(Resource, Default)]
-pub struct EnvironmentData {
-    left_door_open: bool,
-    right_door_open: bool,
-    left_light_on: bool,
-    right_light_on: bool,
-}
+#[derive(Component)]
+pub struct LightAudio;
 
-fn init_rects(
+/// Office buttons, hit where their lit overlays are drawn.
+fn spawn_controls(
     mut cmd: Commands,
+    fnaf_assets: Res<FNAFAssets>,
 ) {
-    let rects = Rects {
-        red_left: Rect::from_corners(
-            Vec2::new(-809.5165, -36.752216),
-            Vec2::new(-744.71643, 67.70151),
-        ),
-        red_right: Rect::from_corners(
-            Vec2::new(749.5523, -35.7851),
-            Vec2::new(813.3852, 75.4388),
-        ),
-        white_left: Rect::from_corners(
-            Vec2::new(-807.5821, -185.69551),
-            Vec2::new(-745.68365, -93.814964),
-        ),
-        white_right: Rect::from_corners(
-            Vec2::new(750.5195, -180.85966),
-            Vec2::new(803.7135, -90.91342),
-        ),
-        faz: Rect::from_corners(
-            Vec2::new(-156.68054, 95.74923),
-            Vec2::new(-127.665634, 116.0597),
-        ),
-    };
-    cmd.insert_resource(rects);
+    // the overlays are drawn for the right side and flipped for the left
+    for (side, flip_x) in [(Side::Left, true), (Side::Right, false)] {
+        cmd.spawn((
+            DespawnOnExit(LocalState::Game),
+            Name::new(format!("{side:?}DoorButton")),
+            Clickable::mask(fnaf_assets.red_button.clone(), flip_x),
+        )).observe(move |press: On<Pointer<Press>>, mut cmd: Commands| {
+            if press.button != PointerButton::Primary {return;}
+            cmd.run_system_cached_with(toggle_door, side);
+        });
+        // the light stays on while the button is held
+        cmd.spawn((
+            DespawnOnExit(LocalState::Game),
+            Name::new(format!("{side:?}LightButton")),
+            Clickable::mask(fnaf_assets.white_button.clone(), flip_x),
+        )).observe(move |press: On<Pointer<Press>>, mut cmd: Commands| {
+            if press.button != PointerButton::Primary {return;}
+            cmd.run_system_cached_with(set_light, (side, true));
+        }).observe(move |_: On<Pointer<Release>>, mut cmd: Commands| {
+            cmd.run_system_cached_with(set_light, (side, false));
+        }).observe(move |_: On<Pointer<Out>>, mut cmd: Commands| {
+            cmd.run_system_cached_with(set_light, (side, false));
+        });
+    }
+    cmd.spawn((
+        DespawnOnExit(LocalState::Game),
+        Name::new("FazNose"),
+        Clickable::mask(fnaf_assets.nose.clone(), false),
+    )).observe(|press: On<Pointer<Press>>, mut cmd: Commands, fnaf_assets: Res<FNAFAssets>, monitor: Res<CameraMonitor>| {
+        if press.button != PointerButton::Primary || monitor.open {return;}
+        cmd.spawn((
+            DespawnOnExit(STATE),
+            AudioPlayer(fnaf_assets.faz.clone()),
+            PlaybackSettings {
+                mode: bevy::audio::PlaybackMode::Once,
+                ..default()
+            },
+        ));
+    });
 }
 
-fn update_mouse_pos(
-    window: Single<&Window>,
-    camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
-    canvas: Res<camera::ViewportCanvas>,
-    mut pos: ResMut<MousePos>,
+fn toggle_door(
+    In(side): In<Side>,
+    mut cmd: Commands,
+    fnaf_assets: Res<FNAFAssets>,
+    mut sim: ResMut<FnafSim>,
+    mut log: ResMut<InputLog>,
+    mut env: Query<(&mut Visibility, &Environment)>,
+    monitor: Res<CameraMonitor>,
 ) {
-    let window = *window;
-    let Some(cursor_win) = window.cursor_position() else { return; }; // top-left origin (Bevy >= 0.11)
-    let (camera, cam_transform) = match camera_q.single() {
-        Ok(v) => v,
-        Err(_) => return,
-    };
-
-    let image_size = canvas.size;          // Vec2: image pixel size (physical/logical as you track it)
-    let window_size = canvas.window_size;  // Vec2: window size used during resize
-
-    // compute top-left offset where the image is blitted in the window
-    let offset = (window_size - image_size) * 0.5;
-
-    let local = cursor_win - offset;
-
-    let viewport_pos = if let Some(ur) = camera.physical_viewport_rect() {
-        let min = Vec2::new(ur.min.x as f32, ur.min.y as f32);
-        local - min
-    } else {
-        local
-    };
-
-    match camera.viewport_to_world_2d(cam_transform, viewport_pos) {
-        Ok(world_pos) => {
-            pos.0 = Some(world_pos)
-        }
-        Err(err) => {
-            warn!("viewport_to_world_2d failed: {:?}", err);
+    // the office is out of reach behind the tablet
+    if monitor.open {return;}
+    let close = sim.controls.door_open(side);
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        AudioPlayer(if close {fnaf_assets.open_door.clone()} else {fnaf_assets.close_door.clone()}),
+        PlaybackSettings {
+            mode: bevy::audio::PlaybackMode::Once,
+            ..default()
+        },
+    ));
+    for (mut visibility, env) in env.iter_mut() {
+        match (env, side) {
+            (Environment::LDoor | Environment::LRedlight, Side::Left)
+            | (Environment::RDoor | Environment::RRedlight, Side::Right) => {
+                *visibility = if close {Visibility::Visible} else {Visibility::Hidden};
+            }
+            _ => {}
         }
     }
+    log.apply(&mut sim, SimInput::ToggleDoor(side));
 }
 
-#[derive(Component)]
-pub struct LightAudio;
-
-fn handle_rects(
+fn set_light(
+    In((side, on)): In<(Side, bool)>,
     mut cmd: Commands,
-    rects: Res<Rects>,
-    mouse_pos: Res<MousePos>,
-    mut gizmos: Gizmos,
-    mouse_input: Res<ButtonInput<MouseButton>>,
     fnaf_assets: Res<FNAFAssets>,
-    mut env_data: ResMut<EnvironmentData>,
+    mut sim: ResMut<FnafSim>,
+    mut log: ResMut<InputLog>,
     mut env: Query<(&mut Visibility, &Environment)>,
     light_audio: Query<Entity, With<LightAudio>>,
-    bear_data: Res<BearData>,
+    monitor: Res<CameraMonitor>,
 ) {
-    gizmos.rect_2d(rects.faz.center(), rects.faz.size(), Color::Srgba(RED));
-    gizmos.rect_2d(rects.red_left.center(), rects.red_left.size(), Color::Srgba(RED));
-    if let Some(pos) = mouse_pos.0 {
-        if mouse_input.just_pressed(MouseButton::Left) {
-            if rects.faz.contains(pos) {
-                cmd.spawn((
-                    DespawnOnExit(STATE),
-                    AudioPlayer(fnaf_assets.faz.clone()),
-                    PlaybackSettings {
-                        mode: bevy::audio::PlaybackMode::Once,
-                        ..default()
-                    },
-                ));
-            } else if rects.red_left.contains(pos) {
-                if env_data.left_door_open {
-                    cmd.spawn((
-                        DespawnOnExit(STATE),
-                        AudioPlayer(fnaf_assets.open_door.clone()),
-                        PlaybackSettings {
-                            mode: bevy::audio::PlaybackMode::Once,
-                            ..default()
-                        },
-                    ));
-                    for (mut visibility, env) in env.iter_mut() {
-                        match *env {
-                            Environment::LDoor => {
-                                *visibility = Visibility::Visible
-                            },
-                            Environment::LRedlight => {
-                                *visibility = Visibility::Visible
-                            },
-                            _ => {}
-                        }
-                    }
-                } else {
-                    cmd.spawn((
-                        DespawnOnExit(STATE),
-                        AudioPlayer(fnaf_assets.close_door.clone()),
-                        PlaybackSettings {
-                            mode: bevy::audio::PlaybackMode::Once,
-                            ..default()
-                        },
-                    ));
-                    for (mut visibility, env) in env.iter_mut() {
-                        match *env {
-                            Environment::LDoor => {
-                                *visibility = Visibility::Hidden
-                            },
-                            Environment::LRedlight => {
-                                *visibility = Visibility::Hidden
-                            },
-                            _ => {}
-                        }
-                    }
-                }
-                env_data.left_door_open = !env_data.left_door_open;
-            } else if rects.red_right.contains(pos) {
-                if env_data.right_door_open {
-                    cmd.spawn((
-                        DespawnOnExit(STATE),
-                        AudioPlayer(fnaf_assets.open_door.clone()),
-                        PlaybackSettings {
-                            mode: bevy::audio::PlaybackMode::Once,
-                            ..default()
-                        },
-                    ));
-                    for (mut visibility, env) in env.iter_mut() {
-                        match *env {
-                            Environment::RDoor => {
-                                *visibility = Visibility::Visible
-                            },
-                            Environment::RRedlight => {
-                                *visibility = Visibility::Visible
-                            },
-                            _ => {}
-                        }
-                    }
-                } else {
-                    cmd.spawn((
-                        DespawnOnExit(STATE),
-                        AudioPlayer(fnaf_assets.close_door.clone()),
-                        PlaybackSettings {
-                            mode: bevy::audio::PlaybackMode::Once,
-                            ..default()
-                        },
-                    ));
-                    for (mut visibility, env) in env.iter_mut() {
-                        match *env {
-                            Environment::RDoor => {
-                                *visibility = Visibility::Hidden
-                            },
-                            Environment::RRedlight => {
-                                *visibility = Visibility::Hidden
-                            },
-                            _ => {}
-                        }
-                    }
-                }
-                env_data.right_door_open = !env_data.right_door_open;
-            }
+    if sim.controls.light_on(side) == on || (on && monitor.open) {return;}
+    log.apply(&mut sim, SimInput::Light(side, on));
+    if on {
+        cmd.spawn((
+            DespawnOnExit(STATE),
+            LightAudio,
+            AudioPlayer(fnaf_assets.light.clone()),
+            PlaybackSettings {
+                mode: bevy::audio::PlaybackMode::Once,
+                volume: Volume::Linear(0.5),
+                ..default()
+            },
+        ));
+    } else {
+        for audio in light_audio {
+            cmd.entity(audio).despawn();
         }
-
-        if rects.white_left.contains(pos) {
-            if mouse_input.just_pressed(MouseButton::Left) {
-                env_data.left_light_on = true;
-                cmd.spawn((
-                    DespawnOnExit(STATE),
-                    LightAudio,
-                    AudioPlayer(fnaf_assets.light.clone()),
-                    PlaybackSettings {
-                        mode: bevy::audio::PlaybackMode::Once,
-                        volume: Volume::Linear(0.5),
-                        ..default()
-                    },
-                ));
-                for (mut visibility, env) in env.iter_mut() {
-                    match *env {
-                        Environment::LWhitelight => {
-                            *visibility = Visibility::Visible
-                        }
-                        Environment::LLight => {
-                            if !bear_data.bear_here || bear_data.right {
-                                *visibility = Visibility::Visible
-                            }
-                        }
-                        Environment::LBear => {
-                            if bear_data.bear_here && !bear_data.right {
-                                *visibility = Visibility::Visible;
-                                cmd.spawn((
-                                    DespawnOnExit(STATE),
-                                    AudioPlayer(fnaf_assets.ur.clone()),
-                                    PlaybackSettings {
-                                        mode: bevy::audio::PlaybackMode::Once,
-                                        ..default()
-                                    },
-                                ));
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            if mouse_input.just_released(MouseButton::Left) {
-                env_data.left_light_on = false;
-                for audio in light_audio {
-                    cmd.entity(audio).despawn();
-                }
-                for (mut visibility, env) in env.iter_mut() {
-                    match *env {
-                        Environment::LWhitelight => {
-                            *visibility = Visibility::Hidden
-                        }
-                        Environment::LLight => {
-                            *visibility = Visibility::Hidden
-                        }
-                        Environment::LBear => {
-                            *visibility = Visibility::Hidden
-                        }
-                        _ => {}
-                    }
-                }
-            }
-        } else if rects.white_right.contains(pos) {
-            if mouse_input.just_pressed(MouseButton::Left) {
-                env_data.right_light_on = true;
-                cmd.spawn((
-                    DespawnOnExit(STATE),
-                    LightAudio,
-                    AudioPlayer(fnaf_assets.light.clone()),
-                    PlaybackSettings {
-                        mode: bevy::audio::PlaybackMode::Once,
-                        volume: Volume::Linear(0.5),
-                        ..default()
-                    },
-                ));
-                for (mut visibility, env) in env.iter_mut() {
-                    match *env {
-                        Environment::RWhitelight => {
-                            *visibility = Visibility::Visible
-                        }
-                        Environment::RLight => {
-                            if !bear_data.bear_here || !bear_data.right {
-                                *visibility = Visibility::Visible
-                            }
-                        }
-                        Environment::RBear => {
-                            if bear_data.bear_here && bear_data.right {
-                                *visibility = Visibility::Visible;
-                                cmd.spawn((
-                                    DespawnOnExit(STATE),
-                                    AudioPlayer(fnaf_assets.ur.clone()),
-                                    PlaybackSettings {
-                                        mode: bevy::audio::PlaybackMode::Once,
-                                        ..default()
-                                    },
-                                ));
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            if mouse_input.just_released(MouseButton::Left) {
-                env_data.right_light_on = false;
-                for audio in light_audio {
-                    cmd.entity(audio).despawn();
-                }
-                for (mut visibility, env) in env.iter_mut() {
-                    match *env {
-                        Environment::RWhitelight => {
-                            *visibility = Visibility::Hidden
-                        }
-                        Environment::RLight => {
-                            *visibility = Visibility::Hidden
-                        }
-                        Environment::RBear => {
-                            *visibility = Visibility::Hidden
-                        }
-                        _ => {}
-                    }
-                }
+    }
+    for (mut visibility, env) in env.iter_mut() {
+        match (env, side) {
+            (Environment::LWhitelight, Side::Left) | (Environment::RWhitelight, Side::Right) => {
+                *visibility = if on {Visibility::Visible} else {Visibility::Hidden};
             }
+            _ => {}
         }
     }
 }
 
-#[derive(Resource)]
-pub struct Battery {
-    pub charge: f32
-}
-
-#[derive(Resource)]
-pub struct FazTime{
-    pub start_time: f32,
-    pub time_to_show: usize,
-}
-
-fn handle_game_logic(
-    env_data: Res<EnvironmentData>,
-    mut battery: ResMut<Battery>,
-    time: Res<Time>,
-    mut state: ResMut<NextState<LocalState>>,
-) {
-    let dt = time.delta_secs();
-    const DOOR_DISCHARGE: f32 = 1.25;
-    const WINDOW_DISCHARGE: f32 = 0.4;
-    let mut discharge = 0.;
-    if !env_data.left_door_open {
-        discharge += DOOR_DISCHARGE * dt;
-    }
-    if !env_data.right_door_open {
-        discharge += DOOR_DISCHARGE * dt;
-    }
-    if env_data.left_light_on {
-        discharge += WINDOW_DISCHARGE * dt;
-    }
-    if env_data.right_light_on {
-        discharge += WINDOW_DISCHARGE * dt;
-    }
-    battery.charge -= discharge;
-    if battery.charge <= 0. {
-        state.set(LocalState::Defeat); //todo: add winscreen
-    }
-}
-
 fn update_text(
-    battery: Res<Battery>,
-    faz_time: Res<FazTime>,
+    sim: Res<FnafSim>,
     mut battery_text: Query<&mut TextSpan, (With<BatteryText>, Without<TimeText>, Without<TimeText1>)>,
     mut time_text: Query<&mut TextSpan, (With<TimeText>, Without<BatteryText>, Without<TimeText1>)>,
     mut time_text1: Query<&mut TextSpan, (With<TimeText1>, Without<BatteryText>, Without<TimeText>)>,
 ) {
-    let charge = battery.charge;
+    let charge = sim.battery;
     for mut text in battery_text.iter_mut() {
         **text = format!("{charge:.0}");
     }
-    let t = faz_time.time_to_show;
+    let t = match sim.hour() {0 => 12, hour => hour};
     for mut text in time_text.iter_mut() {
         **text = format!("{t:.0}");
     }
@@ -488,33 +278,28 @@ fn update_text(
     }
 }
 
-fn handle_faz_time(
-    mut faz_time: ResMut<FazTime>,
-    time: Res<Time>,
+/// Ends the night once the simulation has an outcome.
+fn handle_outcome(
+    mut cmd: Commands,
+    sim: Res<FnafSim>,
+    night: Res<ActiveNight>,
+    log: Res<InputLog>,
+    views: Query<&AnimatronicView>,
     mut state: ResMut<NextState<LocalState>>,
 ) {
-    if faz_time.start_time == 0. {
-        faz_time.start_time = time.elapsed_secs()
-    } else {
-        let elapsed = time.elapsed_secs() - faz_time.start_time;
-        if elapsed < 20. * 1. {
-            faz_time.time_to_show = 12;
-        } else if elapsed < 20. * 2. {
-            faz_time.time_to_show = 1;
-        } else if elapsed < 20. * 3. {
-            faz_time.time_to_show = 2;
-        } else if elapsed < 20. * 4. {
-            faz_time.time_to_show = 3;
-        } else if elapsed < 20. * 5. {
-            faz_time.time_to_show = 4;
-        } else if elapsed < 20. * 6. {
-            faz_time.time_to_show = 5;
-        } else if elapsed < 20. * 7. {
-            faz_time.time_to_show = 6;
-            state.set(LocalState::Win);
+    let Some(outcome) = sim.outcome else {return;};
+    info!("{outcome:?} at {:.1}s, seed {}, {} inputs", sim.time, night.seed, log.entries.len());
+    match outcome {
+        Outcome::Survived => state.set(LocalState::Win),
+        Outcome::PowerOut => state.set(LocalState::Defeat),
+        Outcome::Jumpscare(index) => {
+            let jumpscare = views.iter().find(|v| v.index == index).and_then(|v| v.jumpscare);
+            cmd.insert_resource(Jumpscare(jumpscare));
+            state.set(LocalState::Defeat);
         }
     }
 }
+
 fn tick_transition(
     mut state: ResMut<NextState<LocalState>>,
 ) {
@@ -533,8 +318,6 @@ pub enum Environment {
     LLight,
     RLight,
     Faz,
-    LBear,
-    RBear,
 }
 
 fn setup(
@@ -549,23 +332,13 @@ fn setup(
     let cam = cam.iter().next().expect("No cam!");
     crate::hints::show_hints(
         &mut cmd,
-        vec![KeyHint::KeysLmb],
+        vec![KeyHint::KeysLmb, KeyHint::KeysSpace],
         STATE,
         cam,
         hint_assets,
     );
     state.state = STATE;
-    cmd.insert_resource(MousePos(None));
-    cmd.insert_resource(EnvironmentData {
-        left_door_open: true,
-        right_door_open: true,
-        left_light_on: false,
-        right_light_on: false,
-    });
-    cmd.insert_resource(FazTime {start_time: 0., time_to_show: 12});
-    cmd.insert_resource(Battery {charge: 100.});
     cmd.insert_resource(MemTimer {timer: 0., disable: false});
-    cmd.insert_resource(BearData::default());
     cmd.insert_resource(WinscreenTimer::default());
     cmd.insert_resource(LobsterTimer::default());
     cmd.spawn((
@@ -669,26 +442,7 @@ fn setup(
         Transform::from_xyz(0., 0., 1.),
         Visibility::Hidden,
     ));
-    cmd.spawn((
-        DespawnOnExit(STATE),
-        Sprite {
-            image: fnaf_assets.fred_l.clone(),
-            ..default()
-        },
-        Environment::LBear,
-        Transform::from_xyz(0., 0., 2.),
-        Visibility::Hidden,
-    ));
-    cmd.spawn((
-        DespawnOnExit(STATE),
-        Sprite {
-            image: fnaf_assets.fred_r.clone(),
-            ..default()
-        },
-        Environment::RBear,
-        Transform::from_xyz(0., 0., 2.),
-        Visibility::Hidden,
-    ));
+    spawn_animatronics(&mut cmd, &fnaf_assets);
     cmd.spawn((
         DespawnOnExit(STATE),
         Sprite {
@@ -780,15 +534,14 @@ fn cleanup(
         },
         _ => {}
     }
-    cmd.remove_resource::<MousePos>();
-    cmd.remove_resource::<Rects>();
-    cmd.remove_resource::<EnvironmentData>();
-    cmd.remove_resource::<Battery>();
-    cmd.remove_resource::<FazTime>();
     cmd.remove_resource::<MemTimer>();
-    cmd.remove_resource::<BearData>();
+    cmd.remove_resource::<Jumpscare>();
+    cmd.remove_resource::<CameraMonitor>();
     cmd.remove_resource::<LobsterTimer>();
     cmd.remove_resource::<WinscreenTimer>();
+    cmd.remove_resource::<ActiveNight>();
+    cmd.remove_resource::<FnafSim>();
+    cmd.remove_resource::<InputLog>();
 }
 
 #[derive(Component)]
@@ -800,14 +553,19 @@ pub struct LobsterTimer(f32);
 fn defeat(
     mut cmd: Commands,
     fnaf_assets: Res<FNAFAssets>,
-    mut lobster: Query<&mut Visibility, With<Lobster>>,
+    lobster: Single<Entity, With<Lobster>>,
+    jumpscare: Option<Res<Jumpscare>>,
+    mut visibility: Query<&mut Visibility>,
     time: Res<Time>,
-    mut screenshot: ResMut<LastScreenshot>,
+    mut transitions: MessageWriter<RequestTransition>,
     mut lobster_timer: ResMut<LobsterTimer>,
 ) {
-    let mut visibility = lobster.single_mut().unwrap();
+    // running out of power is the lobster too
+    let target = jumpscare.and_then(|j| j.0).unwrap_or(*lobster);
     if lobster_timer.0 == 0. {
-        *visibility = Visibility::Visible;
+        if let Ok(mut visibility) = visibility.get_mut(target) {
+            *visibility = Visibility::Visible;
+        }
         cmd.spawn((
             DespawnOnExit(STATE),
             AudioPlayer(fnaf_assets.lobster_audio.clone()),
@@ -818,11 +576,7 @@ fn defeat(
         ));
     } else {
         if lobster_timer.0 > 1. {
-            if screenshot.awaiting == false {
-                cmd.spawn(bevy::render::view::screenshot::Screenshot::primary_window())
-                    .observe(await_screenshot_and_translate(AppState::Defeat));
-                screenshot.awaiting = true;
-            }
+            transitions.write(RequestTransition::capture(AppState::Defeat));
         }
     }
     lobster_timer.0 += time.delta_secs();
@@ -887,56 +641,3 @@ fn play_mem(
         mem_timer.disable = true
     }
 }
-
-#[derive(Resource, Default)]
-pub struct BearData {
-    pub initial_delay: f32,
-    pub bear_until_comes: f32,
-    pub bear_until_leaves: f32,
-    pub bear_until_kills: f32,
-    pub bear_here: bool,
-    pub right: bool,
-}
-
-fn handle_faz(
-    time: Res<Time>,
-    env_data: Res<EnvironmentData>,
-    mut bear_data: ResMut<BearData>,
-    mut state: ResMut<NextState<LocalState>>,
-) {
-    let mut rng = rand::rng();
-    let delta_secs = time.delta_secs();
-    if bear_data.initial_delay > 15. {
-        if bear_data.bear_here {
-            if bear_data.bear_until_leaves <= 0. {
-                println!("BEAR LEFT");
-                bear_data.bear_here = false;
-                bear_data.bear_until_kills = 0.;
-                bear_data.bear_until_comes = rng.random_range(20.0..30.0);
-                println!("BEAR COMES IN {}", bear_data.bear_until_comes);
-            } else {
-                bear_data.bear_until_leaves -= delta_secs;
-            }
-            if (env_data.left_door_open && !bear_data.right) || (env_data.right_door_open && bear_data.right) {
-                bear_data.bear_until_kills += delta_secs;
-            } else {
-                bear_data.bear_until_kills = 0.;
-            }
-            if bear_data.bear_until_kills >= 10. {
-                state.set(LocalState::Defeat);
-            }
-        } else {
-            if bear_data.bear_until_comes <= 0. {
-                println!("BEAR HERE");
-                bear_data.bear_here = true;
-                bear_data.right = rng.random_bool(0.5);
-                bear_data.bear_until_leaves = rng.random_range(10.0..20.0);
-                println!("BEAR LEAVES IN {}", bear_data.bear_until_leaves);
-            } else {
-                bear_data.bear_until_comes -= delta_secs;
-            }
-        }
-    } else {
-        bear_data.initial_delay += delta_secs;
-    }
-}
\ No newline at end of file
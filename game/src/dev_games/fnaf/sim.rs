@@ -0,0 +1,218 @@
+use rand::rngs::StdRng;
+use rand::seq::IndexedRandom;
+use rand::{Rng, SeedableRng};
+
+use super::animatronics::*;
+use super::nights::*;
+use crate::prelude::*;
+
+
+/// Office controls as the simulation sees them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Controls {
+    pub left_door_open: bool,
+    pub right_door_open: bool,
+    pub left_light_on: bool,
+    pub right_light_on: bool,
+    pub tablet: bool,
+    pub camera: Room,
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self {
+            left_door_open: true,
+            right_door_open: true,
+            left_light_on: false,
+            right_light_on: false,
+            tablet: false,
+            camera: Room::ShowStage,
+        }
+    }
+}
+
+impl Controls {
+    pub fn door_open(&self, side: Side) -> bool {
+        match side {
+            Side::Left => self.left_door_open,
+            Side::Right => self.right_door_open,
+        }
+    }
+
+    pub fn light_on(&self, side: Side) -> bool {
+        match side {
+            Side::Left => self.left_light_on,
+            Side::Right => self.right_light_on,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimInput {
+    ToggleDoor(Side),
+    Light(Side, bool),
+    Tablet(bool),
+    Camera(Room),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Survived,
+    PowerOut,
+    /// Index into `FnafSim::animatronics`.
+    Jumpscare(usize),
+}
+
+/// Whole state of a night. Same config, seed and inputs at the same steps give the same night.
+#[derive(Resource, Clone, Debug)]
+pub struct FnafSim {
+    pub config: NightConfig,
+    pub rng: StdRng,
+    /// Seconds since the night started.
+    pub time: f32,
+    pub battery: f32,
+    pub controls: Controls,
+    pub animatronics: Vec<Animatronic>,
+    pub outcome: Option<Outcome>,
+}
+
+impl FnafSim {
+    pub fn new(config: NightConfig, seed: u64) -> Self {
+        Self {
+            config,
+            rng: StdRng::seed_from_u64(seed),
+            time: 0.0,
+            battery: 100.0,
+            controls: Controls::default(),
+            animatronics: Animatronic::roster(),
+            outcome: None,
+        }
+    }
+
+    pub fn hour(&self) -> usize {
+        (self.time / self.config.hour_length) as usize
+    }
+
+    pub fn apply(&mut self, input: SimInput) {
+        let c = &mut self.controls;
+        match input {
+            SimInput::ToggleDoor(Side::Left) => c.left_door_open = !c.left_door_open,
+            SimInput::ToggleDoor(Side::Right) => c.right_door_open = !c.right_door_open,
+            SimInput::Light(Side::Left, on) => c.left_light_on = on,
+            SimInput::Light(Side::Right, on) => c.right_light_on = on,
+            SimInput::Tablet(up) => c.tablet = up,
+            SimInput::Camera(room) => c.camera = room,
+        }
+    }
+
+    pub fn step(&mut self, dt: f32) -> Option<Outcome> {
+        if self.outcome.is_some() {return self.outcome;}
+        self.time += dt;
+
+        let c = self.controls;
+        let drain = &self.config.drain;
+        let mut discharge = drain.idle;
+        for side in [Side::Left, Side::Right] {
+            if !c.door_open(side) {discharge += drain.door;}
+            if c.light_on(side) {discharge += drain.light;}
+        }
+        if c.tablet {discharge += drain.camera;}
+        self.battery -= discharge * dt;
+        if self.battery <= 0. {
+            self.battery = 0.;
+            self.outcome = Some(Outcome::PowerOut);
+            return self.outcome;
+        }
+        let hour = self.hour();
+        if hour >= self.config.hours {
+            self.outcome = Some(Outcome::Survived);
+            return self.outcome;
+        }
+        if self.time < self.config.start_delay {return None;}
+
+        let config = &self.config;
+        for (i, a) in self.animatronics.iter_mut().enumerate() {
+            let Some(rules) = config.animatronics.get(a.name) else {continue;};
+            let watched = c.tablet && c.camera == a.room;
+            if let Some(side) = a.room.door() {
+                let repelled = match a.attack {
+                    AttackCondition::Door => false,
+                    AttackCondition::Light => c.light_on(side),
+                    AttackCondition::CameraWatch => watched,
+                };
+                if c.door_open(side) && !repelled {
+                    a.until_kills += dt;
+                } else {
+                    a.until_kills = 0.;
+                }
+                if a.until_kills >= rules.kill_time {
+                    self.outcome = Some(Outcome::Jumpscare(i));
+                    return self.outcome;
+                }
+                if repelled && a.attack == AttackCondition::Light {
+                    a.until_leaves = a.until_leaves.min(LIGHT_REPEL_TIME);
+                }
+                a.until_leaves -= dt;
+                if a.until_leaves <= 0. {
+                    a.room = a.start;
+                    a.until_kills = 0.;
+                    a.opportunity = config.movement_interval;
+                }
+                continue;
+            }
+
+            a.opportunity -= dt;
+            if a.opportunity > 0. {continue;}
+            a.opportunity = config.movement_interval;
+            // nobody moves while being looked at
+            if watched {continue;}
+            let level = config.ai_level(a.name, hour);
+            if self.rng.random_range(1..=MAX_AI_LEVEL) > level {continue;}
+            let Some(next) = a.next_rooms().choose(&mut self.rng).copied() else {continue;};
+            a.room = next;
+            if next.door().is_some() {
+                a.until_leaves = self.rng.random_range(rules.stay.0..=rules.stay.1);
+                a.until_kills = 0.;
+            }
+        }
+        None
+    }
+}
+
+/// Inputs with the simulation time they were applied at, recorded while playing, fed back while replaying.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct InputLog {
+    pub entries: Vec<(f32, SimInput)>,
+    pub replay: bool,
+    /// Next entry to feed when replaying.
+    pub cursor: usize,
+}
+
+impl InputLog {
+    pub fn replay(entries: Vec<(f32, SimInput)>) -> Self {
+        Self { entries, replay: true, cursor: 0 }
+    }
+
+    /// Player input, ignored while replaying.
+    pub fn apply(&mut self, sim: &mut FnafSim, input: SimInput) {
+        if self.replay {return;}
+        self.entries.push((sim.time, input));
+        sim.apply(input);
+    }
+}
+
+pub fn advance_sim(
+    mut sim: ResMut<FnafSim>,
+    mut log: ResMut<InputLog>,
+    time: Res<Time>,
+) {
+    if sim.outcome.is_some() {return;}
+    while log.replay && let Some((t, input)) = log.entries.get(log.cursor).copied() && t <= sim.time {
+        sim.apply(input);
+        log.cursor += 1;
+    }
+    sim.step(time.delta_secs());
+}
+
+
+const LIGHT_REPEL_TIME: f32 = 1.0;
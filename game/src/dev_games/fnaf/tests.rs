@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use bevy::time::TimeUpdateStrategy;
+
+use super::animatronics::*;
+use super::nights::*;
+use super::sim::*;
+use crate::prelude::*;
+
+
+const STEP: f32 = 1.0 / 60.0;
+
+const NIGHTS: [&str; 6] = [
+    include_str!("../../../assets/data/fnaf/night1.night.ron"),
+    include_str!("../../../assets/data/fnaf/night2.night.ron"),
+    include_str!("../../../assets/data/fnaf/night3.night.ron"),
+    include_str!("../../../assets/data/fnaf/night4.night.ron"),
+    include_str!("../../../assets/data/fnaf/night5.night.ron"),
+    include_str!("../../../assets/data/fnaf/custom.night.ron"),
+];
+
+fn config(door_drain: f32, animatronics: &str) -> NightConfig {
+    ron::from_str(&format!(r#"(
+        name: "Test",
+        hours: 6,
+        hour_length: 20.0,
+        start_delay: 15.0,
+        drain: (idle: 0.0, door: {door_drain}, light: 0.4, camera: 0.3),
+        movement_interval: 5.0,
+        animatronics: {{ {animatronics} }},
+    )"#)).unwrap()
+}
+
+/// Plays a whole night headless at a fixed frame time.
+fn run_night(config: NightConfig, seed: u64, inputs: Vec<(f32, SimInput)>) -> FnafSim {
+    let frames = (config.hours as f32 * config.hour_length / STEP) as usize + 60;
+    let mut app = App::new();
+    app
+        .add_plugins(MinimalPlugins)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(STEP)))
+        .insert_resource(FnafSim::new(config, seed))
+        .insert_resource(InputLog::replay(inputs))
+        .add_systems(Update, advance_sim);
+    for _ in 0..frames {
+        app.update();
+        if app.world().resource::<FnafSim>().outcome.is_some() {break;}
+    }
+    app.world().resource::<FnafSim>().clone()
+}
+
+fn index_of(name: &str) -> usize {
+    Animatronic::roster().iter().position(|a| a.name == name).unwrap()
+}
+
+
+#[test]
+fn night_configs_parse() {
+    for night in NIGHTS {
+        let config: NightConfig = ron::from_str(night).unwrap();
+        assert!(config.validate().is_ok(), "{}", config.name);
+        assert!(config.hours > 0 && config.hour_length > 0.0, "{}", config.name);
+        for rules in config.animatronics.values() {
+            assert!(rules.aggression.iter().all(|l| *l <= MAX_AI_LEVEL), "{}", config.name);
+        }
+    }
+}
+
+#[test]
+fn backwards_stay_is_rejected() {
+    let backwards = config(0.5, r#""chicka": (aggression: [20], stay: (16.0, 10.0), kill_time: 8.0)"#);
+    assert!(matches!(backwards.validate(), Err(NightConfigLoaderError::Stay(name, _, _)) if name == "chicka"));
+    let fixed = config(0.5, r#""chicka": (aggression: [20], stay: (10.0, 10.0), kill_time: 8.0)"#);
+    assert!(fixed.validate().is_ok());
+}
+
+#[test]
+fn right_door_closed_from_1am_survives() {
+    let config = config(0.5, r#""chicka": (aggression: [20], stay: (10.0, 16.0), kill_time: 8.0)"#);
+    let sim = run_night(config, 42, vec![(20.0, SimInput::ToggleDoor(Side::Right))]);
+    assert_eq!(sim.outcome, Some(Outcome::Survived));
+}
+
+#[test]
+fn open_right_door_lets_chicka_in() {
+    let config = config(0.5, r#""chicka": (aggression: [20], stay: (10.0, 16.0), kill_time: 8.0)"#);
+    let sim = run_night(config, 42, vec![]);
+    assert_eq!(sim.outcome, Some(Outcome::Jumpscare(index_of("chicka"))));
+}
+
+#[test]
+fn lighting_chicka_drives_her_away() {
+    let config = config(0.5, r#""chicka": (aggression: [20], stay: (10.0, 16.0), kill_time: 8.0)"#);
+    // she can be at the door from 30s on, a short look every few seconds is enough
+    let mut inputs = vec![];
+    let mut t = 25.0;
+    while t < 120.0 {
+        inputs.push((t, SimInput::Light(Side::Right, true)));
+        inputs.push((t + 1.5, SimInput::Light(Side::Right, false)));
+        t += 5.0;
+    }
+    let sim = run_night(config, 42, inputs);
+    assert_eq!(sim.outcome, Some(Outcome::Survived));
+}
+
+#[test]
+fn closed_doors_run_out_of_power() {
+    let config = config(1.25, "");
+    let sim = run_night(config, 42, vec![
+        (0.0, SimInput::ToggleDoor(Side::Left)),
+        (0.0, SimInput::ToggleDoor(Side::Right)),
+    ]);
+    assert_eq!(sim.outcome, Some(Outcome::PowerOut));
+    assert!(sim.time < 41.0, "{}", sim.time);
+}
+
+#[test]
+fn same_seed_same_night() {
+    let config: NightConfig = ron::from_str(NIGHTS[4]).unwrap();
+    let inputs = vec![
+        (10.0, SimInput::Tablet(true)),
+        (12.0, SimInput::Camera(Room::LeftDoor)),
+        (30.0, SimInput::Tablet(false)),
+        (31.0, SimInput::ToggleDoor(Side::Right)),
+        (60.0, SimInput::ToggleDoor(Side::Right)),
+        (61.0, SimInput::Light(Side::Left, true)),
+        (63.0, SimInput::Light(Side::Left, false)),
+    ];
+    let a = run_night(config.clone(), 7, inputs.clone());
+    let b = run_night(config, 7, inputs);
+    assert!(a.outcome.is_some());
+    assert_eq!(a.outcome, b.outcome);
+    assert_eq!(a.time, b.time);
+    assert_eq!(a.battery, b.battery);
+    let rooms = |sim: &FnafSim| sim.animatronics.iter().map(|a| a.room).collect::<Vec<_>>();
+    assert_eq!(rooms(&a), rooms(&b));
+}
@@ -0,0 +1,410 @@
+use games::phase::Phases;
+use games::platformer_controller::ground::cast_solid;
+use games::platformer_controller::plugin::CharacterMotion;
+use games::transition::RequestTransition;
+
+use super::plugin::*;
+use crate::prelude::*;
+
+
+/// Placed on a Tiled point, the enemy is spawned there.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct PlatformerEnemySpawner {
+    pub archetype: EnemyArchetype,
+    pub look: EnemyLook,
+    /// Walking or crawling speed, for a shooter the speed of its shots, in px/s.
+    pub speed: f32,
+    /// Sets off to the right instead of the left.
+    pub start_right: bool,
+    /// Landing on it kills it, otherwise any touch is deadly.
+    pub stompable: bool,
+    /// Seconds between hops or shots.
+    pub interval: f32,
+    /// How close the player has to be for chasers and shooters to react.
+    pub range: f32,
+    /// Take-off speed of a hopper.
+    pub jump: f32,
+}
+
+impl Default for PlatformerEnemySpawner {
+    fn default() -> Self {
+        Self {
+            archetype: EnemyArchetype::Patrol,
+            look: EnemyLook::Toster,
+            speed: 100.0,
+            start_right: false,
+            stompable: true,
+            interval: 1.5,
+            range: 250.0,
+            jump: 250.0,
+        }
+    }
+}
+
+#[derive(Reflect, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[reflect(Default)]
+pub enum EnemyArchetype {
+    /// Stands where it was put.
+    Static,
+    /// Walks back and forth, turning at walls and ledges.
+    #[default]
+    Patrol,
+    /// Crawls along whatever it sits on, around corners, up walls and over ceilings.
+    WallCrawler,
+    /// Jumps forward every `interval`, turning at walls and ledges.
+    Hopper,
+    /// Stands still and shoots at the player in `range`.
+    Shooter,
+    /// Runs at the player in `range`, stops at ledges.
+    Chaser,
+}
+
+#[derive(Reflect, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[reflect(Default)]
+pub enum EnemyLook {
+    #[default]
+    Toster,
+    Frog,
+    Cactus,
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct PlatformerEnemy {
+    pub spawner: PlatformerEnemySpawner,
+    pub half_size: Vec2,
+    /// 1 heading right, -1 left.
+    pub facing: f32,
+    /// Counts up to the next hop or shot.
+    pub timer: f32,
+    /// Points away from the surface a wall-crawler holds on to.
+    pub normal: Vec2,
+}
+
+/// Sensor child of an enemy, touching it is resolved in `resolve_enemy_contacts`.
+#[derive(Component)]
+pub struct EnemyHurtbox;
+
+/// Killed by a stomp, left lying around until the level is left.
+#[derive(Component)]
+pub struct Stomped;
+
+#[derive(Component)]
+pub struct EnemyAnimation {
+    pub frames: usize,
+    pub dead_frame: Option<usize>,
+    /// The sprite looks left and is flipped while heading right.
+    pub flips: bool,
+    pub timer: f32,
+}
+
+#[derive(Component)]
+pub struct EnemyProjectile {
+    pub lifetime: f32,
+}
+
+pub fn spawn_enemy(
+    spawner: On<Add, PlatformerEnemySpawner>,
+    state: Res<State<AppState>>,
+    assets: Res<PlatformerAssets>,
+    spawners: Query<(&Transform, &PlatformerEnemySpawner)>,
+    mut cmd: Commands,
+) {
+    if state.get() != &STATE {return;}
+    let Ok((transform, spec)) = spawners.get(spawner.entity) else {return;};
+    let (name, image, layout, collider, half_size, animation) = match spec.look {
+        EnemyLook::Toster => (
+            "Toster",
+            assets.toster.clone(),
+            Some(assets.toster_layout.clone()),
+            Collider::rectangle(60., 40.),
+            Vec2::new(30., 20.),
+            EnemyAnimation {frames: 2, dead_frame: Some(2), flips: true, timer: 0.},
+        ),
+        EnemyLook::Frog => (
+            "Frog",
+            assets.frog.clone(),
+            Some(assets.frog_layout.clone()),
+            Collider::circle(26.),
+            Vec2::splat(26.),
+            EnemyAnimation {frames: 8, dead_frame: None, flips: false, timer: 0.},
+        ),
+        EnemyLook::Cactus => (
+            "Cactus",
+            assets.cactus.clone(),
+            None,
+            Collider::capsule(10., 20.),
+            Vec2::new(10., 20.),
+            EnemyAnimation {frames: 1, dead_frame: None, flips: false, timer: 0.},
+        ),
+    };
+    let (body, gravity) = match spec.archetype {
+        EnemyArchetype::Static => (RigidBody::Static, 0.),
+        EnemyArchetype::WallCrawler => (RigidBody::Dynamic, 0.),
+        _ => (RigidBody::Dynamic, PLATFORMER_GRAVITY_FORCE),
+    };
+    let mut hurtbox = collider.clone();
+    hurtbox.set_scale(Vec2::splat(HURTBOX_SCALE), 10);
+
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new(name),
+        PlatformerEnemy {
+            spawner: spec.clone(),
+            half_size,
+            facing: if spec.start_right {1.} else {-1.},
+            timer: 0.,
+            normal: Vec2::Y,
+        },
+        Sprite {
+            image,
+            texture_atlas: layout.map(|layout| TextureAtlas {layout, index: 0}),
+            color: player_color_yellow(),
+            ..default()
+        },
+        animation,
+        Transform::from_translation(transform.translation),
+        collider,
+        body,
+        GravityScale(gravity),
+        LockedAxes::ROTATION_LOCKED,
+        LinearVelocity::ZERO,
+        platformer_enemy_layer(),
+        children![(
+            EnemyHurtbox,
+            Transform::default(),
+            Sensor,
+            hurtbox,
+            platformer_enemy_layer(),
+            CollisionEventsEnabled,
+        )],
+    ));
+}
+
+pub fn drive_enemies(
+    mut cmd: Commands,
+    time: Res<Time>,
+    spatial: SpatialQuery,
+    phases: Res<Phases>,
+    mut enemies: Query<(&mut PlatformerEnemy, &Position, &mut LinearVelocity), Without<Stomped>>,
+    players: Query<&Position, (With<Player>, Without<PlatformerEnemy>)>,
+    solids: Query<(Has<Sensor>, Option<&ColliderOf>)>,
+    rigid_bodies: Query<&RigidBody>,
+    ignored: Query<(), Or<(With<Player>, With<PlatformerEnemy>)>>,
+) {
+    let dt = time.delta_secs();
+    let player = players.iter().next().map(|p| p.0);
+    let probe_shape = Collider::circle(PROBE_RADIUS);
+    // everything an enemy can stand on or bump into, tiles of every phase included
+    let filter = SpatialQueryFilter::from_mask(platformer_enemy_layer().filters | phases.all());
+    let probe = |origin: Vec2, dir: Dir2, distance: f32| {
+        cast_solid(&spatial, &solids, &rigid_bodies, &probe_shape, origin, dir, distance, filter.clone(), |hit| {
+            !ignored.contains(hit.entity)
+        })
+    };
+
+    for (mut enemy, position, mut velocity) in enemies.iter_mut() {
+        let pos = position.0;
+        let half = enemy.half_size;
+        let spec = enemy.spawner.clone();
+        let to_player = player.map(|p| p - pos).filter(|d| d.length() <= spec.range);
+        let grounded = velocity.y < 1. && probe(pos, Dir2::NEG_Y, half.y + PROBE).is_some();
+        // a wall right ahead or no floor a step ahead
+        let blocked = |facing: f32| {
+            let ahead = if facing > 0. {Dir2::X} else {Dir2::NEG_X};
+            probe(pos, ahead, half.x + PROBE).is_some()
+                || probe(pos + Vec2::X * facing * (half.x + PROBE), Dir2::NEG_Y, half.y + PROBE * 2.).is_none()
+        };
+
+        match spec.archetype {
+            EnemyArchetype::Static => {}
+            EnemyArchetype::Patrol => {
+                if grounded && blocked(enemy.facing) {
+                    enemy.facing = -enemy.facing;
+                }
+                velocity.x = enemy.facing * spec.speed;
+            }
+            EnemyArchetype::Chaser => {
+                match to_player {
+                    Some(d) if d.x.abs() > half.x => {
+                        enemy.facing = d.x.signum();
+                        velocity.x = if grounded && blocked(enemy.facing) {0.} else {enemy.facing * spec.speed};
+                    }
+                    _ => velocity.x = 0.,
+                }
+            }
+            EnemyArchetype::Hopper => {
+                if grounded {
+                    velocity.x = 0.;
+                    enemy.timer += dt;
+                    if enemy.timer >= spec.interval {
+                        enemy.timer = 0.;
+                        if blocked(enemy.facing) {
+                            enemy.facing = -enemy.facing;
+                        }
+                        velocity.0 = Vec2::new(enemy.facing * spec.speed, spec.jump);
+                    }
+                }
+            }
+            EnemyArchetype::Shooter => {
+                velocity.x = 0.;
+                enemy.timer += dt;
+                let Some(d) = to_player else {continue;};
+                if d.x != 0. {
+                    enemy.facing = d.x.signum();
+                }
+                let Ok(dir) = Dir2::new(d) else {continue;};
+                if enemy.timer >= spec.interval {
+                    enemy.timer = 0.;
+                    shoot(&mut cmd, pos + dir * (half.x + PROJECTILE_RADIUS * 2.), dir, spec.speed, spec.range);
+                }
+            }
+            EnemyArchetype::WallCrawler => {
+                let size = half.x;
+                let tangent = enemy.normal.perp() * -enemy.facing;
+                let ahead = Dir2::new(tangent).ok().and_then(|d| probe(pos, d, size + PROBE));
+                let below = Dir2::new(-enemy.normal).ok().and_then(|d| probe(pos, d, size + PROBE));
+                if let Some(hit) = ahead {
+                    // inner corner, climb what is ahead
+                    enemy.normal = hit.normal1;
+                } else if below.is_none() {
+                    let corner = Dir2::new(-enemy.normal - tangent).ok().and_then(|d| probe(pos, d, (size + PROBE) * 2.));
+                    if corner.is_some() {
+                        // went over an edge, wrap around it
+                        enemy.normal = tangent;
+                    } else {
+                        enemy.normal = Vec2::Y;
+                        velocity.0 = Vec2::NEG_Y * CRAWLER_FALL_SPEED;
+                        continue;
+                    }
+                }
+                let n = enemy.normal;
+                let tangent = n.perp() * -enemy.facing;
+                // keeps its distance by itself, the surface does not have to be solid for the body
+                let gap = Dir2::new(-n).ok()
+                    .and_then(|d| probe(pos, d, size + PROBE * 2.))
+                    .map_or(size, |hit| hit.distance + PROBE_RADIUS);
+                velocity.0 = tangent * spec.speed + n * (size - gap) * CRAWLER_STICK;
+            }
+        }
+    }
+}
+
+fn shoot(
+    cmd: &mut Commands,
+    from: Vec2,
+    dir: Dir2,
+    speed: f32,
+    range: f32,
+) {
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("EnemyProjectile"),
+        EnemyProjectile {lifetime: range * 2. / speed.max(1.)},
+        Sprite {
+            color: player_color_yellow(),
+            custom_size: Some(Vec2::splat(PROJECTILE_RADIUS * 2.)),
+            ..default()
+        },
+        Transform::from_translation(from.extend(0.)),
+        RigidBody::Dynamic,
+        GravityScale(0.),
+        Collider::circle(PROJECTILE_RADIUS),
+        Sensor,
+        LinearVelocity(dir * speed),
+        platformer_enemy_layer(),
+        CollisionEventsEnabled,
+    ));
+}
+
+pub fn age_projectiles(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut projectiles: Query<(Entity, &mut EnemyProjectile)>,
+) {
+    for (e, mut projectile) in projectiles.iter_mut() {
+        projectile.lifetime -= time.delta_secs();
+        if projectile.lifetime <= 0. {
+            cmd.entity(e).despawn();
+        }
+    }
+}
+
+pub fn animate_enemies(
+    time: Res<Time>,
+    mut enemies: Query<(&PlatformerEnemy, &mut EnemyAnimation, &mut Sprite), Without<Stomped>>,
+) {
+    for (enemy, mut animation, mut sprite) in enemies.iter_mut() {
+        if animation.flips {
+            sprite.flip_x = enemy.facing > 0.;
+        }
+        animation.timer += time.delta_secs();
+        if animation.timer < ENEMY_ANIM_DELAY {continue;}
+        animation.timer = 0.;
+        let frames = animation.frames;
+        let Some(atlas) = &mut sprite.texture_atlas else {continue;};
+        atlas.index = (atlas.index + 1) % frames;
+    }
+}
+
+/// Every enemy touch goes through here: a stompable enemy landed on from above dies and bounces the player,
+/// anything else, shots included, ends the run.
+pub fn resolve_enemy_contacts(
+    mut cmd: Commands,
+    mut collisions: MessageReader<CollisionStart>,
+    hurtboxes: Query<&ChildOf, With<EnemyHurtbox>>,
+    mut enemies: Query<(&PlatformerEnemy, &Position, &EnemyAnimation, &mut Sprite), Without<Stomped>>,
+    projectiles: Query<(), With<EnemyProjectile>>,
+    walls: Query<(), (Without<Sensor>, Without<PlatformerEnemy>, Without<Player>)>,
+    mut players: Query<(&Position, &mut CharacterMotion), (With<Player>, Without<Disabled>)>,
+    mut transitions: MessageWriter<RequestTransition>,
+) {
+    let mut defeat = false;
+    for event in collisions.read() {
+        for (a, b) in [(event.collider1, event.collider2), (event.collider2, event.collider1)] {
+            if projectiles.contains(a) {
+                if players.contains(b) {
+                    defeat = true;
+                }
+                if players.contains(b) || walls.contains(b) {
+                    cmd.entity(a).try_despawn();
+                }
+                continue;
+            }
+            let Ok(hurtbox) = hurtboxes.get(a) else {continue;};
+            let Ok((player_pos, mut motion)) = players.get_mut(b) else {continue;};
+            let enemy_entity = hurtbox.parent();
+            let Ok((enemy, enemy_pos, animation, mut sprite)) = enemies.get_mut(enemy_entity) else {continue;};
+            let from_above = motion.velocity.y < 0. && player_pos.y - enemy_pos.y > enemy.half_size.y;
+            if !enemy.spawner.stompable || !from_above {
+                defeat = true;
+                continue;
+            }
+            motion.velocity.y = PLATFORMER_STOMP_BOUNCE;
+            match (animation.dead_frame, &mut sprite.texture_atlas) {
+                (Some(frame), Some(atlas)) => {
+                    atlas.index = frame;
+                    cmd.entity(enemy_entity).insert((Stomped, RigidBody::Static)).despawn_children();
+                }
+                _ => {
+                    cmd.entity(enemy_entity).despawn();
+                }
+            }
+        }
+    }
+    if defeat {
+        transitions.write(RequestTransition::capture(AppState::Defeat));
+    }
+}
+
+
+/// Radius of the circle enemies feel around with.
+const PROBE_RADIUS: f32 = 2.;
+/// How far past its body an enemy looks for walls and floor.
+const PROBE: f32 = 4.;
+/// Hurtboxes stick out of the body so a character blocked by a static enemy still touches it.
+const HURTBOX_SCALE: f32 = 1.1;
+const CRAWLER_STICK: f32 = 10.;
+const CRAWLER_FALL_SPEED: f32 = 300.;
+const PROJECTILE_RADIUS: f32 = 4.;
+const ENEMY_ANIM_DELAY: f32 = 0.2;
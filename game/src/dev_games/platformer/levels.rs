@@ -0,0 +1,115 @@
+use camera::CameraController;
+use games::ghost::Ghost;
+use games::phase::{Phased, Phases};
+use room::RoomController;
+
+use super::enemies::{EnemyProjectile, PlatformerEnemy};
+use super::plugin::*;
+use crate::prelude::*;
+
+
+/// Map the platformer is on and where the player enters it.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct CurrentLevel {
+    /// File stem of a map in `PlatformerAssets::levels`.
+    pub level: String,
+    /// `SpawnPoint::name` to enter at.
+    pub spawn: String,
+    /// The player keeps its phase across levels.
+    pub phase: u32,
+}
+
+/// Best run from `level.spawn` to the next level, for its ghost.
+pub fn ghost_path(level: &CurrentLevel) -> String {
+    format!("saves/platformer_ghost_{}_{}.ron", level.level, level.spawn)
+}
+
+/// Fade out, swap the map, fade in.
+#[derive(Resource)]
+pub struct LevelTransition {
+    pub target: CurrentLevel,
+    pub timer: f32,
+    pub swapped: bool,
+}
+
+#[derive(Component)]
+pub struct LevelFade;
+
+pub fn start_level_transition(
+    cmd: &mut Commands,
+    next: &NextTrigger,
+) {
+    cmd.insert_resource(LevelTransition {
+        target: CurrentLevel {level: next.level.clone(), spawn: next.spawn.clone(), phase: 0},
+        timer: 0.,
+        swapped: false,
+    });
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        LevelFade,
+        Node {
+            position_type: PositionType::Absolute,
+            width: Val::Percent(100.),
+            height: Val::Percent(100.),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK.with_alpha(0.)),
+        ZIndex(100),
+    ));
+}
+
+pub fn tick_level_transition(
+    mut cmd: Commands,
+    time: Res<Time>,
+    assets: Res<PlatformerAssets>,
+    mut transition: ResMut<LevelTransition>,
+    mut phases: ResMut<Phases>,
+    mut camera_controller: ResMut<CameraController>,
+    mut fade: Query<(Entity, &mut BackgroundColor), With<LevelFade>>,
+    maps: Query<Entity, With<TiledMap>>,
+    players: Query<(Entity, &Phased), With<Player>>,
+    enemies: Query<Entity, Or<(With<PlatformerEnemy>, With<EnemyProjectile>)>>,
+    ghosts: Query<Entity, With<Ghost>>,
+) {
+    transition.timer += time.delta_secs();
+    let t = transition.timer / LEVEL_FADE_TIME;
+    if t >= 1. && !transition.swapped {
+        transition.swapped = true;
+        let Some(map) = assets.levels.get(transition.target.level.as_str()) else {
+            warn!("No platformer level {}", transition.target.level);
+            return;
+        };
+        for (player, phased) in players.iter() {
+            transition.target.phase = phased.phase;
+            cmd.entity(player).despawn();
+            camera_controller.focused_entities.retain(|e| *e != player);
+        }
+        // the next map declares its own phases
+        phases.colors.clear();
+        phases.active = transition.target.phase;
+        for e in maps.iter().chain(enemies.iter()).chain(ghosts.iter()) {
+            cmd.entity(e).despawn();
+        }
+        cmd.insert_resource(transition.target.clone());
+        cmd.insert_resource(RoomController::default());
+        cmd.spawn((
+            DespawnOnExit(STATE),
+            Name::new("Map"),
+            TiledMap(map.clone()),
+        ));
+    }
+    let alpha = if t < 1. {t} else {2. - t};
+    for (e, mut color) in fade.iter_mut() {
+        if alpha <= 0. {
+            cmd.entity(e).despawn();
+        } else {
+            color.0 = Color::BLACK.with_alpha(alpha.min(1.));
+        }
+    }
+    if alpha <= 0. {
+        cmd.remove_resource::<LevelTransition>();
+    }
+}
+
+
+const LEVEL_FADE_TIME: f32 = 0.6;
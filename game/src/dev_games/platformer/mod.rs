@@ -1,2 +1,4 @@
 pub mod plugin;
-pub mod map;
\ No newline at end of file
+pub mod map;
+pub mod levels;
+pub mod enemies;
\ No newline at end of file
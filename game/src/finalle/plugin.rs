@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use bevy::{color::palettes::css::BLACK, log::tracing, prelude::*, window::WindowResized};
 use bevy_asset_loader::asset_collection::AssetCollection;
-use games::prelude::{AppState, LastScreenshot};
+use games::{cutscene::{plugin::{CutsceneCue, CutscenePlayer}, sequence::Cutscene}, prelude::{AppState, LastScreenshot}};
 use properties::{HighresCamera, WorldCamera};
 use utils::WrappedDelta;
 
@@ -43,12 +43,9 @@ pub struct FinalAssets {
     #[asset(path = "images/final/bavy.png")]
     bavy: Handle<Image>,
 
-    #[asset(path = "sounds/final/131599__echocinematics__kill-switch-large-breaker-switch.mp3")]
-    lights_out: Handle<AudioSource>,
-    #[asset(path = "sounds/final/261042__johnthewizar__house-organ-f1.wav")]
-    freddy_appears: Handle<AudioSource>,
-    #[asset(path = "sounds/final/78127__jovica__layers-006-chrunched-church-organ-drone-pad-b7.mp3")]
-    zoom_out: Handle<AudioSource>,
+    /// Lights out, the bear and the start of the zoom out.
+    #[asset(path = "data/cutscenes/final.cutscene.ron")]
+    intro: Handle<Cutscene>,
     #[asset(path = "sounds/final/566195__scholzi982__press_button_02.wav")]
     button_press: Handle<AudioSource>,
     #[asset(path = "sounds/final/784061__newlocknew__comtv_turning-onoffor-switching-tv-channels.mp3")]
@@ -60,8 +57,7 @@ impl Plugin for FinalPlugin {
         app
         .add_sub_state::<LocalState>()
             .add_systems(OnEnter(STATE), (spawn_texture_holder, setup, debug_cameras).chain())
-            .add_systems(Update, (timing_system, resize_texture_holder).run_if(in_state(STATE)))
-            .add_systems(Update, start_zoom.run_if(in_state(LocalState::Bear)))
+            .add_systems(Update, (on_cues, resize_texture_holder).run_if(in_state(STATE)))
             .add_systems(Update, (zoom, handle_bear_background_shrink, despawn_shrinked).chain().run_if(in_state(LocalState::Zooming)))
             .add_systems(Update, cleanup.run_if(in_state(LocalState::Finished)))
             // .add_systems(Update, (t, update_mouse_pos).run_if(in_state(STATE)))
@@ -168,6 +164,12 @@ fn setup(
     localstate.set(LocalState::Bear);
     let cam = cam.iter().next().expect("No cam!");
 
+    cmd.spawn((
+        Name::new("Intro"),
+        DespawnOnExit(STATE),
+        CutscenePlayer::new(final_assets.intro.clone()),
+    ));
+
     cmd.spawn((
         Name::new("Bear"),
         DespawnOnExit(STATE),
@@ -305,89 +307,34 @@ fn setup(
         ZIndex(-5), // change
     ));
 
-    cmd.insert_resource(TimeCounter::default());
-    cmd.insert_resource(Played::default());
     cmd.insert_resource(StartZoomout::default());
     cmd.insert_resource(ToRemove{rm: None});
 }
 
-const LIGHTS_OUT_TIMING: f32 = 0.1;
-const QUESTION_TIMING: f32 = LIGHTS_OUT_TIMING + 1.;
-const QUESTION_TO_ZOOMOUT_DELTA: f32 = 2.;
-const START_ZOOMOUT_TIMING: f32 = QUESTION_TIMING + QUESTION_TO_ZOOMOUT_DELTA;
-
-#[derive(Resource, Default)]
-struct TimeCounter(f32);
-
-#[derive(Resource, Default)]
-struct Played([bool; 10]);
-
 #[derive(Resource, Default)]
 struct StartZoomout(bool);
 
-fn timing_system(
-    mut cmd: Commands,
-    final_assets: Res<FinalAssets>,
-    mut counter: ResMut<TimeCounter>,
-    mut played: ResMut<Played>,
-    time: Res<Time>,
+fn on_cues(
+    mut cues: MessageReader<CutsceneCue>,
     mut visibility: Query<(&mut Visibility, &SpriteType)>,
     mut start_zoomout: ResMut<StartZoomout>,
-) {
-    for (mut visibility, sprite_type) in visibility.iter_mut() {
-        if counter.0 >= START_ZOOMOUT_TIMING && !played.0[2] {
-            played.0[2] = true;
-            start_zoomout.0 = true;
-            cmd.spawn((
-                DespawnOnExit(STATE),
-                AudioPlayer(final_assets.zoom_out.clone()),
-                PlaybackSettings {
-                    mode: bevy::audio::PlaybackMode::Once,
-                    ..default()
-                },
-            ));
-        } else if counter.0 >= QUESTION_TIMING && !played.0[1] {
-            if let SpriteType::BearQuestion = sprite_type {
-                played.0[1] = true;
-                cmd.spawn((
-                    DespawnOnExit(STATE),
-                    AudioPlayer(final_assets.freddy_appears.clone()),
-                    PlaybackSettings {
-                        mode: bevy::audio::PlaybackMode::Once,
-                        ..default()
-                    },
-                ));
-                *visibility = Visibility::Visible
-            }
-        } else if counter.0 >= LIGHTS_OUT_TIMING && !played.0[0] {
-            played.0[0] = true;
-            cmd.spawn((
-                DespawnOnExit(STATE),
-                AudioPlayer(final_assets.lights_out.clone()),
-                PlaybackSettings {
-                    mode: bevy::audio::PlaybackMode::Once,
-                    ..default()
-                },
-            ));
-        }
-    }
-    counter.0 += time.delta_secs();
-}
-
-fn start_zoom(
-    visibility_q: Query<(&Visibility, &SpriteType)>,
     mut localstate: ResMut<NextState<LocalState>>,
-    time: Res<Time>,
-    mut timer: Local<f32>
 ) {
-    if *timer >= QUESTION_TO_ZOOMOUT_DELTA {
-        for (visibility, shrinking_type) in visibility_q {
-            if shrinking_type == &SpriteType::BearQuestion && visibility == Visibility::Visible {
+    for cue in cues.read() {
+        match cue.name.as_str() {
+            "question" => {
+                for (mut visibility, sprite_type) in visibility.iter_mut() {
+                    if *sprite_type == SpriteType::BearQuestion {
+                        *visibility = Visibility::Visible;
+                    }
+                }
+            }
+            "zoom_out" => {
+                start_zoomout.0 = true;
                 localstate.set(LocalState::Zooming);
             }
+            _ => {}
         }
-    } else {
-        *timer += time.delta_secs();
     }
 }
 
@@ -472,8 +419,6 @@ fn despawn_shrinked(
 fn cleanup(
     mut cmd: Commands,
 ) {
-    cmd.remove_resource::<TimeCounter>();
-    cmd.remove_resource::<Played>();
     cmd.remove_resource::<StartZoomout>();
     cmd.remove_resource::<ToRemove>();
 }
\ No newline at end of file
@@ -6,6 +6,7 @@ use crate::dev_games::plugin::*;
 use crate::prelude::*;
 
 use crate::core::plugin::CorePlugin;
+use games::replay::replay_start_state;
 pub mod prelude;
 pub mod properties;
 pub mod dev_games;
@@ -42,7 +43,7 @@ impl Plugin for GamesPlugin {
             .add_systems(OnExit(AppState::LoadingAssets), cleanup_loading_screen)
             .add_loading_state(
                 LoadingState::new(AppState::LoadingAssets)
-                    .continue_to_state(AppState::Platformer)
+                    .continue_to_state(replay_start_state(app).unwrap_or(AppState::Platformer))
                     .load_collection::<GameAssets>()
                     .load_collection::<HintAssets>()
                     .load_collection::<pacman_eat::plugin::PacmanEatAssets>()
@@ -55,6 +56,7 @@ impl Plugin for GamesPlugin {
                     .load_collection::<novel::plugin::NovelSoundEffectsAssets>()
                     .load_collection::<fake_end::plugin::FakeEndAssets>()
                     .load_collection::<fnaf::plugin::FNAFAssets>()
+                    .load_collection::<novel2fnaf::plugin::Novel2FnafAssets>()
                     .load_collection::<miami::plugin::MiamiAssets>()
                     .load_collection::<geometry_dash::plugin::GeometryDashAssets>()
                     .load_collection::<finalle::plugin::FinalAssets>()
@@ -102,7 +104,7 @@ impl Plugin for GamesPlugin {
             .add_systems(OnExit(AppState::LoadingAssets), cleanup_loading_screen)
             .add_loading_state(
                 LoadingState::new(AppState::LoadingAssets)
-                    .continue_to_state(AppState::Miami)
+                    .continue_to_state(replay_start_state(app).unwrap_or(AppState::Miami))
                     .load_collection::<GameAssets>()
                     .load_collection::<HintAssets>()
                     .load_collection::<pacman_eat::plugin::PacmanEatAssets>()
@@ -115,6 +117,7 @@ impl Plugin for GamesPlugin {
                     .load_collection::<novel::plugin::NovelSoundEffectsAssets>()
                     .load_collection::<fake_end::plugin::FakeEndAssets>()
                     .load_collection::<fnaf::plugin::FNAFAssets>()
+                    .load_collection::<novel2fnaf::plugin::Novel2FnafAssets>()
                     .load_collection::<miami::plugin::MiamiAssets>()
                     .load_collection::<geometry_dash::plugin::GeometryDashAssets>()
                     .load_collection::<finalle::plugin::FinalAssets>()
@@ -1,5 +1,6 @@
 use bevy::prelude::*;
-use games::prelude::{AppState, LastScreenshot};
+use bevy_asset_loader::asset_collection::AssetCollection;
+use games::{cutscene::{plugin::{CutsceneCue, CutscenePlayer}, sequence::Cutscene}, prelude::{AppState, LastScreenshot}};
 use properties::{HighresCamera, WorldCamera};
 
 use crate::dev_games::fnaf::plugin::FNAFAssets;
@@ -24,7 +25,7 @@ impl Plugin for Novel2FnafPlugin {
         app
         .add_sub_state::<LocalState>()
             .add_systems(OnEnter(STATE), setup)
-            .add_systems(Update, zoom.run_if(in_state(LocalState::Zooming)))
+            .add_systems(Update, on_cues.run_if(in_state(LocalState::Zooming)))
             .add_systems(Update, cleanup.run_if(in_state(LocalState::Finished)))
             // .add_systems(Update, (t, update_mouse_pos).run_if(in_state(STATE)))
             // .add_observer(collision_handler)
@@ -32,15 +33,19 @@ impl Plugin for Novel2FnafPlugin {
     }
 }
 
+/// Where the fnaf room is on the novel screenshot, the cutscene zooms in on its center.
 const RECT_BL: Vec2 = Vec2::new(-495.60846, -43.876827);
 const RECT_TR: Vec2 = Vec2::new(-22.935652, 217.38959);
 
+#[derive(AssetCollection, Resource)]
+pub struct Novel2FnafAssets {
+    #[asset(path = "data/cutscenes/novel2fnaf.cutscene.ron")]
+    zoom: Handle<Cutscene>,
+}
+
 #[derive(Component)]
 struct NovelScreenshot;
 
-#[derive(Resource)]
-struct FnafRect(Rect);
-
 fn setup(
     mut cmd: Commands,
     mut localstate: ResMut<NextState<LocalState>>,
@@ -48,6 +53,7 @@ fn setup(
     cam: Query<Entity, With<HighresCamera>>,
     mut camera_transform_q: Query<&mut Projection, With<WorldCamera>>,
     fnaf_assets: Res<FNAFAssets>,
+    assets: Res<Novel2FnafAssets>,
 ) {
     if let Some(image) = last.image.clone() {
         let fnaf_rect = Rect::from_corners(RECT_BL, RECT_TR);
@@ -79,50 +85,36 @@ fn setup(
             },
             NovelScreenshot,
         ));
-        cmd.insert_resource(FnafRect(fnaf_rect));
         if let Projection::Orthographic(proj) = &mut *camera_transform_q.single_mut().unwrap() {
             proj.scale = 2.77;
         }
+        cmd.spawn((
+            Name::new("Zoom"),
+            DespawnOnEnter(NEXT_STATE),
+            CutscenePlayer::new(assets.zoom.clone()),
+        ));
     }
 }
 
 
-fn zoom(
-    mut camera_transform_q: Query<(&mut Projection, &mut Transform), With<WorldCamera>>,
-    window_q: Query<&Window>,
+fn on_cues(
+    mut cmd: Commands,
+    mut cues: MessageReader<CutsceneCue>,
     mut localstate: ResMut<NextState<LocalState>>,
-    fnaf_rect: Res<FnafRect>,
 ) {
-    const TRANSLATION_PF: f32 = 1.;
-    const SCALE_PF: f32 = 0.003;
-    const THRESHOLD: f32 = 0.4;
-
-    if let Ok(window) = window_q.single() {
-        let window_size = Vec2::new(window.width(), window.height());
-        let (mut projection, mut transform) = camera_transform_q.single_mut().unwrap();
-        let direction_2d = (fnaf_rect.0.center() - transform.translation.xy()).normalize_or_zero();
-        transform.translation.x += direction_2d.x * TRANSLATION_PF;
-        transform.translation.y += direction_2d.y * TRANSLATION_PF;
-        
-        let target_scale = ((fnaf_rect.0.size().x / window_size.x).max(fnaf_rect.0.size().y / window_size.y)).abs();
-        if let Projection::Orthographic(proj) = &mut *projection {
-            // println!("{}", proj.scale - target_scale);
-            proj.scale += (target_scale - proj.scale) * SCALE_PF;
-            
-            if (proj.scale - target_scale).abs() < THRESHOLD {
-                localstate.set(LocalState::Finished);
-            }
+    for cue in cues.read() {
+        if cue.name == "zoomed" {
+            // or it keeps holding the camera over the reset in cleanup
+            cmd.entity(cue.player).despawn();
+            localstate.set(LocalState::Finished);
         }
     }
 }
 
 fn cleanup(
-    mut cmd: Commands,
     mut cam: Query<(&mut Transform, &mut Projection), With<WorldCamera>>,
     mut appstate: ResMut<NextState<AppState>>,
 ) {
-    cmd.remove_resource::<FnafRect>();
-
     let (mut transform, mut projection) = cam.single_mut().unwrap();
     transform.translation = Vec3::ZERO;
     appstate.set(NEXT_STATE);
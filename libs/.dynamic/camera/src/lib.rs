@@ -1,7 +1,7 @@
 use std::collections::VecDeque;
 
 use avian2d::prelude::*;
-use bevy::{camera::{ImageRenderTarget, RenderTarget, ScalingMode}, input::mouse::MouseWheel, prelude::*, render::{render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages}}, window::WindowResized};
+use bevy::{camera::{ImageRenderTarget, RenderTarget, ScalingMode}, input::mouse::MouseWheel, math::FloatExt, prelude::*, render::{render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages}}, window::WindowResized};
 use kaiv_utils::prelude::ExpDecay;
 use properties::*;
 use room::*;
@@ -21,7 +21,11 @@ impl Plugin for CameraPlugin {
         app
             .add_systems(PreStartup, setup_camera)
             .insert_resource(CameraController{target_zoom: self.initial_target_zoom, ..default()})
-            .add_systems(Update, window_resize)
+            .init_resource::<CameraShake>()
+            .add_message::<ShakeCamera>()
+            .add_message::<PanCamera>()
+            .add_systems(Update, (window_resize, start_pans))
+            .add_systems(PostUpdate, shake_canvas.before(TransformSystems::Propagate))
             // .add_systems(PhysicsSchedule, tick_camera)
             // .add_systems(PhysicsSchedule, tick_camera.in_set(NarrowPhaseSystems::First))
             .add_systems(PhysicsSchedule, tick_camera.in_set(NarrowPhaseSystems::Last))
@@ -34,6 +38,10 @@ impl Plugin for CameraPlugin {
 #[derive(Component)]
 pub struct WorldUiRoot;
 
+/// Sprite showing the world camera's canvas on the highres layers.
+#[derive(Component)]
+pub struct ViewportSprite;
+
 
 #[derive(Resource)]
 pub struct ViewportCanvas {
@@ -42,6 +50,19 @@ pub struct ViewportCanvas {
     pub window_size: Vec2
 }
 
+impl ViewportCanvas {
+    /// Window position (top-left origin) to world position of the camera drawing into the canvas.
+    pub fn window_to_world(&self, camera: &Camera, camera_transform: &GlobalTransform, window_pos: Vec2) -> Option<Vec2> {
+        // the canvas is blitted centered in the window
+        let local = window_pos - (self.window_size - self.size) * 0.5;
+        let viewport_pos = match camera.physical_viewport_rect() {
+            Some(r) => local - r.min.as_vec2(),
+            None => local,
+        };
+        camera.viewport_to_world_2d(camera_transform, viewport_pos).ok()
+    }
+}
+
 pub fn setup_camera(
     mut commands: Commands,
     mut images: ResMut<Assets<Image>>,
@@ -119,7 +140,8 @@ pub fn setup_camera(
         }
     ));
     
-    commands.spawn((Sprite::from_image(image_handle), HIGHRES_LAYERS));
+    // world picking goes through `ViewportCanvas::window_to_world`, not the canvas sprite
+    commands.spawn((Name::new("ViewportSprite"), ViewportSprite, Sprite::from_image(image_handle), HIGHRES_LAYERS, Pickable::IGNORE));
 }
 
 fn window_resize(
@@ -176,6 +198,22 @@ pub struct CameraController {
     pub target_zoom: f32,
     pub follow_speed: f32,
     pub zoom_speed: f32,
+    /// Where the focused entity aims, the camera leads it by `look_ahead` that way.
+    pub aim: Vec2,
+    pub look_ahead: f32,
+    pan: Option<ActivePan>,
+}
+
+impl CameraController {
+    /// Whether a `PanCamera` is playing instead of the follow.
+    pub fn is_panning(&self) -> bool {
+        self.pan.is_some()
+    }
+
+    /// Stops a running `PanCamera`, the follow takes over from where it left the camera.
+    pub fn cancel_pan(&mut self) {
+        self.pan = None;
+    }
 }
 
 impl Default for CameraController {
@@ -185,7 +223,118 @@ impl Default for CameraController {
             camera_mode: CameraMode::default(),
             target_zoom: 1.0,
             follow_speed: 0.7,
-            zoom_speed: 0.9
+            zoom_speed: 0.9,
+            aim: Vec2::ZERO,
+            look_ahead: 0.0,
+            pan: None,
+        }
+    }
+}
+
+/// Kept in view together with the focused entity, zooming out if needed.
+/// The focused entity weighs 1, heavier targets pull the center towards them.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct CameraTarget {
+    pub weight: f32,
+}
+
+impl CameraTarget {
+    pub fn new(weight: f32) -> Self {
+        Self {weight}
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum PanTo {
+    Entity(Entity),
+    Point(Vec2),
+    /// To where the camera would be without the pan.
+    Back,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PanStep {
+    pub to: PanTo,
+    /// Keeps the zoom when `None`, `PanTo::Back` goes back to the followed zoom.
+    pub zoom: Option<f32>,
+    pub duration: f32,
+    /// Held at the end of the step.
+    pub hold: f32,
+}
+
+/// Takes the camera through `steps` instead of following, replacing a running pan.
+#[derive(Message, Clone, Debug)]
+pub struct PanCamera {
+    pub steps: Vec<PanStep>,
+}
+
+impl PanCamera {
+    /// Over to `entity`, a look for `hold` seconds and back.
+    pub fn to_and_back(entity: Entity, zoom: Option<f32>, duration: f32, hold: f32) -> Self {
+        Self {steps: vec![
+            PanStep {to: PanTo::Entity(entity), zoom, duration, hold},
+            PanStep {to: PanTo::Back, zoom: None, duration, hold: 0.0},
+        ]}
+    }
+}
+
+struct ActivePan {
+    steps: Vec<PanStep>,
+    step: usize,
+    time: f32,
+    /// Camera translation and zoom the step started from.
+    from: Option<(Vec3, f32)>,
+}
+
+/// Adds trauma to the `CameraShake`.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct ShakeCamera {
+    pub trauma: f32,
+}
+
+/// Shakes the `ViewportSprite` while there is trauma, the shake grows with its square.
+/// The world camera keeps following undisturbed.
+#[derive(Resource)]
+pub struct CameraShake {
+    /// From 0 to 1.
+    pub trauma: f32,
+    /// Trauma lost per second.
+    pub decay: f32,
+    pub max_offset: f32,
+    /// In radians.
+    pub max_angle: f32,
+    /// How fast the noise moves.
+    pub frequency: f32,
+    time: f32,
+}
+
+impl CameraShake {
+    /// Trauma is capped at 1.
+    pub fn add(&mut self, trauma: f32) {
+        self.trauma = (self.trauma + trauma).min(1.0);
+    }
+
+    /// Moves the shake `dt` seconds on, returning the offset in world units and the angle to shake by.
+    pub fn advance(&mut self, dt: f32) -> (Vec2, f32) {
+        if self.trauma <= 0.0 {return (Vec2::ZERO, 0.0);}
+        self.time += dt * self.frequency;
+        let k = self.trauma * self.trauma;
+        let offset = Vec2::new(noise(1.0, self.time), noise(2.0, self.time)) * self.max_offset * k;
+        let angle = noise(3.0, self.time) * self.max_angle * k;
+        self.trauma = (self.trauma - self.decay * dt).max(0.0);
+        (offset, angle)
+    }
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 2.5,
+            max_offset: 8.0,
+            max_angle: 0.03,
+            frequency: 30.0,
+            time: 0.0,
         }
     }
 }
@@ -212,6 +361,8 @@ pub fn tick_camera(
     mut camera_controller: ResMut<CameraController>,   
     room_controller: Option<Res<RoomController>>,
     targets: Query<&Transform, Without<WorldCamera>>,
+    framed: Query<(&Transform, &CameraTarget), Without<WorldCamera>>,
+    shapes: Query<(&CameraShape, Option<&CameraRail>, Option<&CameraLookAt>, Option<&CameraZone>)>,
     // window: Query<&Window>,
     canvas: Res<ViewportCanvas>,
     keys: Res<ButtonInput<KeyCode>>,
@@ -234,6 +385,46 @@ pub fn tick_camera(
     let mut target_zoom = camera_controller.target_zoom + z * 0.05;
     let mut zoom_speed = camera_controller.zoom_speed;
     let mut follow_speed = camera_controller.follow_speed;
+    let view = screen_rect_in_world(cam, &gt, Vec2::ZERO, canvas.size).map(|(min, max)| (max - min).abs() / p.scale);
+    if !framed.is_empty() {
+        let framed: Vec<_> = framed.iter().map(|(t, f)| (t.translation.truncate(), f.weight)).collect();
+        let (center, reach) = frame_targets(target.truncate(), &framed);
+        target = center.extend(target.z);
+        if let Some(view) = view {
+            target_zoom = target_zoom.max(framing_zoom(reach, view));
+        }
+    }
+    target += (camera_controller.aim * camera_controller.look_ahead).extend(0.0);
+    let focus = cam_target.translation.truncate();
+    // the nearest rail in reach holds the camera center
+    let rail = shapes.iter()
+        .filter_map(|(shape, rail, ..)| match (shape, rail) {
+            (CameraShape::Rail(points), Some(rail)) => {
+                let d = focus.distance(closest_on_polyline(points, focus)?);
+                (rail.reach <= 0.0 || d <= rail.reach).then_some((d, points))
+            }
+            _ => None,
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+    if let Some(on_rail) = rail.and_then(|(_, points)| closest_on_polyline(points, target.truncate())) {
+        target = on_rail.extend(target.z);
+    }
+    for (shape, _, look_at, zone) in shapes.iter() {
+        match (shape, look_at, zone) {
+            (CameraShape::Point(point), Some(look_at), _) if look_at.radius > 0.0 => {
+                let k = (1.0 - focus.distance(*point) / look_at.radius).max(0.0) * look_at.weight;
+                target = target.lerp(point.extend(target.z), k.min(1.0));
+            }
+            (CameraShape::Zone(rect), _, Some(zone)) => {
+                let k = zone_weight(*rect, zone.blend, focus);
+                target += (zone.offset * k).extend(0.0);
+                if zone.zoom > 0.0 {
+                    target_zoom = target_zoom.lerp(zone.zoom, k);
+                }
+            }
+            _ => {}
+        }
+    }
     'a : {
         'b : {
             let (s, t) = &mut *freecam;
@@ -259,6 +450,11 @@ pub fn tick_camera(
         }
         let Some(room_controller) = room_controller else {break 'a;};
         let Some(EnteredRoom{room: RoomBounds{ld, ru, ..}, ..}) = room_controller.rooms.front() else {break 'a;};
+        // framing does not zoom out past the room
+        if let Some(view) = view {
+            let room_zoom = ((*ru - *ld).truncate() / view).min_element();
+            target_zoom = target_zoom.min(room_zoom.max(camera_controller.target_zoom));
+        }
 
         let half = canvas.size.extend(0.0) * 0.5;
         let Some((sld, sru)) = screen_rect_in_world(cam, &gt, Vec2::new(-half.x, -half.y), Vec2::new(half.x, half.y)) else {break 'a;};
@@ -283,6 +479,28 @@ pub fn tick_camera(
         }
     }
     
+    if let Some(mut pan) = camera_controller.pan.take() {
+        let Some(step) = pan.steps.get(pan.step).copied() else {return;};
+        let (from, from_zoom) = *pan.from.get_or_insert((t.translation, p.scale));
+        let (to, to_zoom) = match step.to {
+            PanTo::Entity(e) => (targets.get(e).map_or(from, |e| e.translation.truncate().extend(from.z)), step.zoom.unwrap_or(from_zoom)),
+            PanTo::Point(point) => (point.extend(from.z), step.zoom.unwrap_or(from_zoom)),
+            PanTo::Back => (target, step.zoom.unwrap_or(target_zoom)),
+        };
+        // pans are timed, unlike the follow
+        pan.time += time.delta_secs();
+        let k = if step.duration > 0.0 {(pan.time / step.duration).min(1.0)} else {1.0};
+        let k = k * k * (3.0 - 2.0 * k);
+        t.translation = from.lerp(to, k);
+        p.scale = from_zoom.lerp(to_zoom, k);
+        if pan.time >= step.duration + step.hold {
+            pan.step += 1;
+            pan.time = 0.0;
+            pan.from = None;
+        }
+        camera_controller.pan = Some(pan);
+        return;
+    }
     t.translation = if camera_controller.follow_speed <= 0.0 { target } else {t.translation.exp_decay(target, follow_speed, dt)};
     p.scale = p.scale.exp_decay(target_zoom, zoom_speed, dt);
 }
@@ -296,4 +514,159 @@ fn screen_rect_in_world(
     let world_min = camera.viewport_to_world_2d(camera_transform, min).ok()?;
     let world_max = camera.viewport_to_world_2d(camera_transform, max).ok()?;
     Some((world_min, world_max))
-}
\ No newline at end of file
+}
+
+fn shake_canvas(
+    time: Res<Time>,
+    mut shakes: MessageReader<ShakeCamera>,
+    mut shake: ResMut<CameraShake>,
+    canvas: Res<ViewportCanvas>,
+    camera: Query<&Projection, With<WorldCamera>>,
+    mut sprite: Query<&mut Transform, With<ViewportSprite>>,
+) {
+    for s in shakes.read() {
+        shake.add(s.trauma);
+    }
+    let Ok(mut t) = sprite.single_mut() else {return;};
+    let (offset, angle) = shake.advance(time.delta_secs());
+    let scale = match camera.single() {
+        Ok(Projection::Orthographic(p)) => p.scale,
+        _ => 1.0,
+    };
+    // the canvas shows `TARGET_WIDTH` world units at zoom 1
+    let offset = offset * canvas.size.x / (TARGET_WIDTH as f32 * scale);
+    let size = canvas.size;
+    // grown just enough to keep the window covered
+    let cover = angle.cos() + angle.abs().sin() * (size.x / size.y).max(size.y / size.x) + (offset.abs() * 2.0 / size).max_element();
+    t.set_if_neq(Transform {
+        translation: offset.extend(0.0),
+        rotation: Quat::from_rotation_z(angle),
+        scale: Vec3::new(cover, cover, 1.0),
+    });
+}
+
+fn start_pans(
+    mut pans: MessageReader<PanCamera>,
+    mut camera_controller: ResMut<CameraController>,
+) {
+    let Some(pan) = pans.read().last() else {return;};
+    camera_controller.pan = Some(ActivePan {steps: pan.steps.clone(), step: 0, time: 0.0, from: None});
+}
+
+fn closest_on_polyline(points: &[Vec2], p: Vec2) -> Option<Vec2> {
+    if let [point] = points {return Some(*point);}
+    points.windows(2)
+        .map(|w| {
+            let (a, b) = (w[0], w[1]);
+            let ab = b - a;
+            let k = if ab.length_squared() > 0.0 {((p - a).dot(ab) / ab.length_squared()).clamp(0.0, 1.0)} else {0.0};
+            a + ab * k
+        })
+        .min_by(|a, b| a.distance_squared(p).total_cmp(&b.distance_squared(p)))
+}
+
+/// Weighted center of the focus and `targets`, the focus weighing 1, and how far they reach from it on each axis.
+fn frame_targets(focused: Vec2, targets: &[(Vec2, f32)]) -> (Vec2, Vec2) {
+    let (sum, weights) = targets.iter().fold((focused, 1.0), |(sum, weights), (p, weight)| {
+        (sum + *p * *weight, weights + weight)
+    });
+    let center = sum / weights;
+    let reach = targets.iter().fold((focused - center).abs(), |reach, (p, _)| reach.max((*p - center).abs()));
+    (center, reach)
+}
+
+/// Zoom fitting `reach` around the center with a margin, `view` being the world size seen at zoom 1.
+fn framing_zoom(reach: Vec2, view: Vec2) -> f32 {
+    ((reach + FRAMING_MARGIN) * 2.0 / view).max_element()
+}
+
+/// 1 deeper than `blend` inside `rect`, 0 outside.
+fn zone_weight(rect: Rect, blend: f32, p: Vec2) -> f32 {
+    if !rect.contains(p) {return 0.0;}
+    if blend <= 0.0 {return 1.0;}
+    let depth = (p - rect.min).min(rect.max - p).min_element();
+    (depth / blend).min(1.0)
+}
+
+/// Smooth noise from -1 to 1, a different one for every `seed`.
+fn noise(seed: f32, t: f32) -> f32 {
+    (t + seed * 13.7).sin() * 0.5 + (t * 2.3 + seed * 7.1).sin() * 0.3 + (t * 4.7 + seed * 3.3).sin() * 0.2
+}
+
+const FRAMING_MARGIN: Vec2 = Vec2::new(48.0, 32.0);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polyline_projects_onto_the_nearest_segment() {
+        let rail = [vec2(0.0, 0.0), vec2(100.0, 0.0), vec2(100.0, 100.0)];
+        assert_eq!(closest_on_polyline(&rail, vec2(40.0, 30.0)), Some(vec2(40.0, 0.0)));
+        assert_eq!(closest_on_polyline(&rail, vec2(130.0, 60.0)), Some(vec2(100.0, 60.0)));
+        assert_eq!(closest_on_polyline(&rail, vec2(-50.0, -10.0)), Some(vec2(0.0, 0.0)));
+    }
+
+    #[test]
+    fn polyline_degenerate_cases() {
+        assert_eq!(closest_on_polyline(&[], Vec2::ONE), None);
+        assert_eq!(closest_on_polyline(&[vec2(5.0, 5.0)], Vec2::ZERO), Some(vec2(5.0, 5.0)));
+        assert_eq!(closest_on_polyline(&[vec2(5.0, 5.0), vec2(5.0, 5.0)], Vec2::ZERO), Some(vec2(5.0, 5.0)));
+    }
+
+    #[test]
+    fn zone_weight_ramps_over_blend() {
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        assert_eq!(zone_weight(rect, 20.0, vec2(-1.0, 50.0)), 0.0);
+        assert_eq!(zone_weight(rect, 20.0, vec2(5.0, 50.0)), 0.25);
+        assert_eq!(zone_weight(rect, 20.0, vec2(50.0, 90.0)), 0.5);
+        assert_eq!(zone_weight(rect, 20.0, vec2(50.0, 50.0)), 1.0);
+    }
+
+    #[test]
+    fn zone_without_blend_is_all_or_nothing() {
+        let rect = Rect::new(0.0, 0.0, 100.0, 100.0);
+        assert_eq!(zone_weight(rect, 0.0, vec2(0.5, 50.0)), 1.0);
+        assert_eq!(zone_weight(rect, 0.0, vec2(150.0, 50.0)), 0.0);
+    }
+
+    #[test]
+    fn framing_centers_on_weights() {
+        let (center, reach) = frame_targets(Vec2::ZERO, &[(vec2(100.0, 0.0), 1.0)]);
+        assert_eq!(center, vec2(50.0, 0.0));
+        assert_eq!(reach, vec2(50.0, 0.0));
+        let (center, reach) = frame_targets(Vec2::ZERO, &[(vec2(100.0, 40.0), 3.0)]);
+        assert_eq!(center, vec2(75.0, 30.0));
+        assert_eq!(reach, vec2(75.0, 30.0));
+    }
+
+    #[test]
+    fn framing_zooms_out_to_fit_the_margin() {
+        let view = vec2(400.0, 200.0);
+        assert_eq!(framing_zoom(vec2(152.0, 0.0), view), 1.0);
+        assert_eq!(framing_zoom(vec2(0.0, 168.0), view), 2.0);
+    }
+
+    #[test]
+    fn shake_trauma_is_capped_and_decays() {
+        let mut shake = CameraShake::default();
+        shake.add(0.7);
+        shake.add(0.7);
+        assert_eq!(shake.trauma, 1.0);
+        shake.advance(0.25);
+        assert_eq!(shake.trauma, 0.375);
+        shake.advance(1.0);
+        assert_eq!(shake.trauma, 0.0);
+        assert_eq!(shake.advance(0.1), (Vec2::ZERO, 0.0));
+    }
+
+    #[test]
+    fn shake_stays_within_its_bounds() {
+        let mut shake = CameraShake {trauma: 0.5, decay: 0.0, ..default()};
+        for _ in 0..100 {
+            let (offset, angle) = shake.advance(1.0 / 60.0);
+            assert!(offset.abs().max_element() <= shake.max_offset * 0.25);
+            assert!(angle.abs() <= shake.max_angle * 0.25);
+        }
+    }
+}
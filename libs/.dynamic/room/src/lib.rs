@@ -21,6 +21,10 @@ impl Plugin for RoomPlugin {
     fn build(&self, app: &mut App) {
         app
             .register_type::<MapRoom>()
+            .register_type::<CameraRail>()
+            .register_type::<CameraLookAt>()
+            .register_type::<CameraZone>()
+            .add_systems(PostUpdate, bake_camera_shapes.after(TransformSystems::Propagate))
             .add_observer(on_room_entered)
             .add_observer(on_room_exited)
             ;
@@ -55,6 +59,39 @@ pub struct EnteredRoom {
     pub room: RoomBounds
 }
 
+/// Tiled polyline the camera center is kept on while the focus is within `reach` of it, `0` reaches everywhere.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct CameraRail {
+    pub reach: f32,
+}
+
+/// Tiled point the camera leans towards by `weight` while the focus is within `radius`.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct CameraLookAt {
+    pub radius: f32,
+    pub weight: f32,
+}
+
+/// Tiled rectangle easing the camera to `zoom` and by `offset` inside, over `blend` pixels from its edge.
+/// A `zoom` of `0` keeps the zoom.
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct CameraZone {
+    pub zoom: f32,
+    pub offset: Vec2,
+    pub blend: f32,
+}
+
+/// World space shape of a camera rail, look-at or zone, baked once the object is placed.
+#[derive(Component, Clone, Debug)]
+pub enum CameraShape {
+    Rail(Vec<Vec2>),
+    Point(Vec2),
+    Zone(Rect),
+}
+
 #[derive(Resource, Default)]
 pub struct RoomController {
     // pub dynamic_rooms: Vec<Entity>,
@@ -122,4 +159,31 @@ fn on_room_exited(
     if player.contains(other_entity) {
         controller.rooms.retain(|e| e.entity != room_entity);
     }
-}
\ No newline at end of file
+}
+
+fn bake_camera_shapes(
+    mut cmd: Commands,
+    objects: Query<
+        (Entity, &TiledObject, &GlobalTransform, Has<CameraRail>, Has<CameraZone>),
+        (Or<(With<CameraRail>, With<CameraLookAt>, With<CameraZone>)>, Without<CameraShape>),
+    >,
+) {
+    for (e, object, gt, rail, zone) in objects.iter() {
+        let t = gt.translation().truncate();
+        let shape = match object {
+            TiledObject::Polyline {vertices} | TiledObject::Polygon {vertices} if rail => {
+                CameraShape::Rail(vertices.iter().map(|v| gt.transform_point(v.extend(0.0)).truncate()).collect())
+            }
+            TiledObject::Rectangle {width, height} if zone => {
+                CameraShape::Zone(Rect::from_corners(t - vec2(0.0, *height), t + vec2(*width, 0.0)))
+            }
+            _ if !rail && !zone => CameraShape::Point(t),
+            _ => {
+                warn!("Camera rails should be polylines and zones rectangles");
+                cmd.entity(e).remove::<(CameraRail, CameraZone)>();
+                continue;
+            }
+        };
+        cmd.entity(e).insert(shape);
+    }
+}
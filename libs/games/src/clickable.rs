@@ -0,0 +1,103 @@
+use bevy::picking::PickingSystems;
+use bevy::picking::backend::{HitData, PointerHits};
+use bevy::picking::hover::HoverMap;
+use bevy::picking::pointer::{PointerId, PointerLocation};
+use bevy::sprite::Anchor;
+use bevy::window::{CursorIcon, PrimaryWindow, SystemCursorIcon};
+use camera::ViewportCanvas;
+use crate::prelude::*;
+
+
+/// Picking for world sprites drawn through the `ViewportCanvas`.
+/// Clickable sprites get the usual `Pointer<Over/Out/Press/Release/Click>` events, observe them on the entity.
+pub struct ClickablePlugin;
+
+impl Plugin for ClickablePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(PreUpdate, pick_clickables.in_set(PickingSystems::Backend))
+            .add_systems(Update, update_cursor)
+            ;
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+#[require(Sprite, Pickable)]
+pub struct Clickable {
+    /// Pixels with alpha at or below it let the pointer through, `None` hits the whole sprite rect.
+    pub alpha_threshold: Option<f32>,
+    /// Cursor while hovered.
+    pub cursor: SystemCursorIcon,
+}
+
+impl Default for Clickable {
+    fn default() -> Self {
+        Self {
+            alpha_threshold: Some(0.1),
+            cursor: SystemCursorIcon::Pointer,
+        }
+    }
+}
+
+impl Clickable {
+    /// Invisible sprite hit where `image` is opaque, for buttons that are drawn as part of a bigger image.
+    pub fn mask(image: Handle<Image>, flip_x: bool) -> impl Bundle {
+        (
+            Self {alpha_threshold: Some(0.5), ..default()},
+            Sprite {
+                image,
+                color: Color::NONE,
+                flip_x,
+                ..default()
+            },
+        )
+    }
+}
+
+fn pick_clickables(
+    pointers: Query<(&PointerId, &PointerLocation)>,
+    camera: Query<(Entity, &Camera, &GlobalTransform), With<WorldCamera>>,
+    canvas: Option<Res<ViewportCanvas>>,
+    clickables: Query<(Entity, &Clickable, &Sprite, &Anchor, &GlobalTransform, &InheritedVisibility)>,
+    images: Res<Assets<Image>>,
+    atlases: Res<Assets<TextureAtlasLayout>>,
+    mut hits: MessageWriter<PointerHits>,
+) {
+    let Some(canvas) = canvas else {return;};
+    let Ok((cam_entity, camera, cam_transform)) = camera.single() else {return;};
+    for (pointer, location) in pointers.iter() {
+        let Some(location) = location.location() else {continue;};
+        let Some(world_pos) = canvas.window_to_world(camera, cam_transform, location.position) else {continue;};
+        let picks = clickables.iter()
+            .filter(|(.., visibility)| visibility.get())
+            .filter_map(|(entity, clickable, sprite, anchor, transform, _)| {
+                let local = transform.affine().inverse().transform_point3(world_pos.extend(transform.translation().z));
+                let pixel = sprite.compute_pixel_space_point(local.truncate(), *anchor, &images, &atlases).ok()?;
+                if let Some(threshold) = clickable.alpha_threshold {
+                    let image = images.get(&sprite.image)?;
+                    let alpha = image.get_color_at(pixel.x as u32, pixel.y as u32).ok()?.alpha();
+                    if alpha <= threshold {return None;}
+                }
+                let depth = cam_transform.translation().z - transform.translation().z;
+                Some((entity, HitData::new(cam_entity, depth, Some(world_pos.extend(transform.translation().z)), None)))
+            })
+            .collect::<Vec<_>>();
+        // below the ui, which reports at camera order + 0.5
+        hits.write(PointerHits::new(*pointer, picks, camera.order as f32));
+    }
+}
+
+fn update_cursor(
+    mut cmd: Commands,
+    window: Single<Entity, With<PrimaryWindow>>,
+    hover_map: Res<HoverMap>,
+    clickables: Query<&Clickable>,
+    mut current: Local<Option<SystemCursorIcon>>,
+) {
+    let hovered = hover_map.get(&PointerId::Mouse)
+        .and_then(|hovered| hovered.keys().find_map(|e| clickables.get(*e).ok()))
+        .map(|c| c.cursor);
+    if *current == hovered {return;}
+    *current = hovered;
+    cmd.entity(*window).insert(CursorIcon::System(hovered.unwrap_or_default()));
+}
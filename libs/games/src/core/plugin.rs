@@ -8,7 +8,15 @@ use debug_utils::{
     inspector::plugin::SwitchableEguiInspectorPlugin,
 };
 use room::RoomPlugin;
+use crate::clickable::ClickablePlugin;
 use crate::global_music::plugin::GlobalMusicPlugin;
+use crate::phase::PhasePlugin;
+use crate::platformer_controller::plugin::PlatformerControllerPlugin;
+use crate::cutscene::plugin::CutscenePlugin;
+use crate::ghost::GhostPlugin;
+use crate::replay::ReplayPlugin;
+use crate::tick_input::TickInputPlugin;
+use crate::transition::TransitionPlugin;
 
 use crate::{shaders::bg::BGPlugin, tilemap::plugin::MapPlugin};
 
@@ -48,11 +56,16 @@ impl Plugin for CorePlugin {
                 MapPlugin,
                 TweeningPlugin,
                 BGPlugin,
+                ClickablePlugin,
+                PlatformerControllerPlugin,
+                PhasePlugin,
                 // GameStatesPlugin,
                 // PixelCameraPlugin,
                 // CameraControllerPlugin,
                 // bevy_framepace::FramepacePlugin,
             ))
+            // the plugin tuple above is full
+            .add_plugins((TickInputPlugin, ReplayPlugin, GhostPlugin, CutscenePlugin, TransitionPlugin))
             // .insert_resource(bevy_framepace::FramepaceSettings{limiter: bevy_framepace::Limiter::from_framerate(60.0)})
             .add_systems(PreUpdate, super::egui_font::init_egui_font.after(EguiPreUpdateSet::InitContexts).run_if(run_once))
             // .add_systems(PreStartup, 
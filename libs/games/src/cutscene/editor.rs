@@ -0,0 +1,56 @@
+use bevy_inspector_egui::{bevy_egui::EguiContexts, egui};
+
+use super::plugin::CutscenePlayer;
+use super::sequence::Cutscene;
+use crate::prelude::*;
+
+
+/// Whether the cutscene panel is open.
+#[derive(Resource, Default)]
+pub struct CutsceneEditor(pub bool);
+
+pub(super) fn toggle_editor(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut editor: ResMut<CutsceneEditor>,
+) {
+    if keys.just_pressed(KeyCode::F3) {
+        editor.0 = !editor.0;
+    }
+}
+
+/// Play, pause and scrub every running cutscene.
+pub(super) fn cutscene_panel(
+    mut contexts: EguiContexts,
+    cutscenes: Res<Assets<Cutscene>>,
+    mut players: Query<(Entity, Option<&Name>, &mut CutscenePlayer)>,
+) {
+    let Ok(ctx) = contexts.ctx_mut() else {return;};
+    egui::Window::new("Cutscenes").show(ctx, |ui| {
+        if players.is_empty() {
+            ui.label("No cutscene is playing");
+        }
+        for (entity, name, mut player) in players.iter_mut() {
+            let length = cutscenes.get(&player.cutscene).map_or(0., Cutscene::length);
+            ui.separator();
+            ui.label(name.map_or_else(|| format!("{entity}"), |n| n.to_string()));
+            ui.horizontal(|ui| {
+                if ui.button(if player.paused {"Play"} else {"Pause"}).clicked() {
+                    player.paused = !player.paused;
+                }
+                if ui.button("Restart").clicked() {
+                    player.restart();
+                }
+                if player.is_waiting() {
+                    ui.label("waiting for input");
+                }
+            });
+            let mut time = player.time;
+            // scrubbing does not fire audio, cues or state changes
+            if ui.add(egui::Slider::new(&mut time, 0.0..=length).text("time")).changed() {
+                player.paused = true;
+                player.seek(time);
+            }
+            ui.add(egui::Slider::new(&mut player.speed, 0.0..=4.0).text("speed"));
+        }
+    });
+}
@@ -0,0 +1,6 @@
+pub mod plugin;
+pub mod sequence;
+pub mod editor;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,247 @@
+use bevy::{math::FloatExt, platform::collections::HashMap};
+use bevy_inspector_egui::bevy_egui::EguiPrimaryContextPass;
+
+use super::editor::{CutsceneEditor, cutscene_panel, toggle_editor};
+use super::sequence::{Cutscene, CutsceneLoader, Track, progress};
+use crate::{prelude::*, transition::RequestTransition};
+
+
+/// Plays `CutscenePlayer`s, F3 opens a panel to pause and scrub them.
+pub struct CutscenePlugin;
+
+impl Plugin for CutscenePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_asset::<Cutscene>()
+            .init_asset_loader::<CutsceneLoader>()
+            .init_resource::<CutsceneEditor>()
+            .add_message::<CutsceneCue>()
+            .add_systems(Update, (reload_cutscenes, advance_cutscenes, apply_cutscenes).chain())
+            .add_systems(Update, toggle_editor)
+            .add_systems(EguiPrimaryContextPass, cutscene_panel.run_if(|editor: Res<CutsceneEditor>| editor.0))
+            ;
+    }
+}
+
+/// Sent by `Track::Cue` for the game to do what a track can not.
+#[derive(Message, Clone, Debug)]
+pub struct CutsceneCue {
+    pub player: Entity,
+    pub name: String,
+}
+
+/// Plays a cutscene, its sprites are spawned under this entity.
+#[derive(Component)]
+#[require(Transform, Visibility)]
+pub struct CutscenePlayer {
+    pub cutscene: Handle<Cutscene>,
+    pub time: f32,
+    pub speed: f32,
+    pub paused: bool,
+    /// Events up to this time were fired.
+    fired: f32,
+    /// Held by a `Track::Wait` for its key.
+    waiting: Option<Option<KeyCode>>,
+    actors: HashMap<String, Entity>,
+    spawned: bool,
+    /// World camera translation and zoom before the camera tracks.
+    camera_start: Option<(Vec3, f32)>,
+}
+
+impl CutscenePlayer {
+    pub fn new(cutscene: Handle<Cutscene>) -> Self {
+        Self {
+            cutscene,
+            time: 0.,
+            speed: 1.,
+            paused: false,
+            fired: f32::NEG_INFINITY,
+            waiting: None,
+            actors: default(),
+            spawned: false,
+            camera_start: None,
+        }
+    }
+
+    /// Jumps to `time` without firing the events in between.
+    pub fn seek(&mut self, time: f32) {
+        self.time = time;
+        self.fired = time;
+        self.waiting = None;
+    }
+
+    /// Plays from the start, events included.
+    pub fn restart(&mut self) {
+        self.seek(0.);
+        self.fired = f32::NEG_INFINITY;
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.waiting.is_some()
+    }
+}
+
+/// Edited cutscenes respawn their sprites.
+fn reload_cutscenes(
+    mut cmd: Commands,
+    mut events: MessageReader<AssetEvent<Cutscene>>,
+    mut players: Query<&mut CutscenePlayer>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified {id} = event else {continue;};
+        for mut player in players.iter_mut().filter(|p| p.cutscene.id() == *id) {
+            for (_, actor) in player.actors.drain() {
+                cmd.entity(actor).despawn();
+            }
+            player.spawned = false;
+        }
+    }
+}
+
+fn advance_cutscenes(
+    mut cmd: Commands,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    cutscenes: Res<Assets<Cutscene>>,
+    mut players: Query<(Entity, &mut CutscenePlayer)>,
+    mut cues: MessageWriter<CutsceneCue>,
+    mut state: ResMut<NextState<AppState>>,
+    mut transitions: MessageWriter<RequestTransition>,
+) {
+    for (entity, mut player) in players.iter_mut() {
+        let Some(cutscene) = cutscenes.get(&player.cutscene) else {continue;};
+        if let Some(key) = player.waiting {
+            let pressed = match key {
+                Some(key) => keys.just_pressed(key),
+                None => keys.get_just_pressed().next().is_some(),
+            };
+            if !pressed {continue;}
+            player.waiting = None;
+        }
+        if !player.paused {
+            player.time += time.delta_secs() * player.speed;
+        }
+        let from = player.fired;
+        let wait = cutscene.tracks.iter()
+            .filter_map(|t| match t {
+                Track::Wait {at, key} if from < *at && *at <= player.time => Some((*at, *key)),
+                _ => None,
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((at, key)) = wait {
+            player.time = at;
+            player.waiting = Some(key);
+        }
+        let to = player.time;
+        for track in cutscene.tracks.iter().filter(|t| t.is_event() && from < t.at() && t.at() <= to) {
+            match track {
+                Track::Audio {path, ..} => {
+                    let Some(handle) = cutscene.handles.get(path) else {continue;};
+                    cmd.spawn((
+                        ChildOf(entity),
+                        AudioPlayer(handle.clone().typed::<AudioSource>()),
+                        PlaybackSettings::DESPAWN,
+                    ));
+                }
+                Track::Cue {name, ..} => {
+                    cues.write(CutsceneCue {player: entity, name: name.clone()});
+                }
+                Track::State {state: next, transition: Some(style), ..} => {
+                    transitions.write(RequestTransition::new(*next, *style));
+                }
+                Track::State {state: next, ..} => state.set(*next),
+                _ => {}
+            }
+        }
+        player.fired = to;
+    }
+}
+
+fn apply_cutscenes(
+    mut cmd: Commands,
+    cutscenes: Res<Assets<Cutscene>>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+    mut players: Query<(Entity, &mut CutscenePlayer)>,
+    mut actors: Query<(&mut Transform, &mut Sprite, &mut Visibility), Without<CutscenePlayer>>,
+    mut camera: Query<(&mut Transform, &mut Projection), (With<WorldCamera>, Without<CutscenePlayer>, Without<Sprite>)>,
+) {
+    for (entity, mut player) in players.iter_mut() {
+        let Some(cutscene) = cutscenes.get(&player.cutscene) else {continue;};
+        if !player.spawned {
+            spawn_actors(&mut cmd, entity, &mut player, cutscene, &mut layouts);
+            continue;
+        }
+        let time = player.time;
+        for track in cutscene.tracks.iter() {
+            let Track::Sprite {name, color, ..} = track else {continue;};
+            let Some(&actor) = player.actors.get(name) else {continue;};
+            let Ok((mut transform, mut sprite, mut visibility)) = actors.get_mut(actor) else {continue;};
+            let Some(pose) = cutscene.pose(track, time) else {
+                visibility.set_if_neq(Visibility::Hidden);
+                continue;
+            };
+            visibility.set_if_neq(Visibility::Inherited);
+            transform.translation = pose.translation;
+            transform.scale = Vec3::splat(pose.scale);
+            transform.rotation = Quat::from_rotation_z(pose.rotation);
+            sprite.color = color.with_alpha(pose.alpha);
+            if let (Some(frame), Some(atlas)) = (pose.frame, &mut sprite.texture_atlas) {
+                atlas.index = frame;
+            }
+        }
+        if !cutscene.tracks.iter().any(|t| matches!(t, Track::Camera {..})) {continue;}
+        let Ok((mut transform, mut projection)) = camera.single_mut() else {continue;};
+        let Projection::Orthographic(projection) = &mut *projection else {continue;};
+        let (mut translation, mut zoom) = *player.camera_start.get_or_insert((transform.translation, projection.scale));
+        for track in cutscene.tracks.iter() {
+            let Track::Camera {at, duration, translation: to, zoom: to_zoom, ease} = track else {continue;};
+            if time < *at {continue;}
+            let t = progress(time, *at, *duration, *ease);
+            if let Some(to) = to {
+                translation = translation.lerp(to.extend(translation.z), t);
+            }
+            if let Some(to) = to_zoom {
+                zoom = zoom.lerp(*to, t);
+            }
+        }
+        transform.translation = translation;
+        projection.scale = zoom;
+    }
+}
+
+fn spawn_actors(
+    cmd: &mut Commands,
+    entity: Entity,
+    player: &mut CutscenePlayer,
+    cutscene: &Cutscene,
+    layouts: &mut Assets<TextureAtlasLayout>,
+) {
+    let mut parents = vec![];
+    for track in cutscene.tracks.iter() {
+        let Track::Sprite {name, image, atlas, parent, size, color, flip_x, ..} = track else {continue;};
+        let mut sprite = Sprite {
+            custom_size: *size,
+            color: *color,
+            flip_x: *flip_x,
+            ..default()
+        };
+        if let Some(handle) = image.as_ref().and_then(|path| cutscene.handles.get(path)) {
+            sprite.image = handle.clone().typed();
+        }
+        if let Some(atlas) = atlas {
+            sprite.texture_atlas = Some(TextureAtlas {
+                layout: layouts.add(TextureAtlasLayout::from_grid(atlas.tile, atlas.columns, atlas.rows, None, None)),
+                index: atlas.frames.first().copied().unwrap_or(0),
+            });
+        }
+        let actor = cmd.spawn((Name::new(name.clone()), sprite, Visibility::Hidden)).id();
+        player.actors.insert(name.clone(), actor);
+        parents.push((actor, parent.clone()));
+    }
+    player.spawned = true;
+    // parents are named before or after their children
+    for (actor, parent) in parents {
+        let parent = parent.and_then(|p| player.actors.get(&p).copied()).unwrap_or(entity);
+        cmd.entity(actor).insert(ChildOf(parent));
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::{asset::{AssetLoader, LoadContext, io::Reader}, math::{FloatExt, curve::{Curve, EaseFunction}}, platform::collections::HashMap};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{prelude::*, transition::TransitionStyle};
+
+
+/// Timed tracks, loaded from `*.cutscene.ron`. Sprites and tweens only depend on the time,
+/// so a cutscene can be scrubbed, the other tracks fire when playback passes them.
+#[derive(Asset, TypePath, Deserialize, Clone, Debug)]
+pub struct Cutscene {
+    pub tracks: Vec<Track>,
+    /// Images and sounds of the tracks by path, loaded with the cutscene.
+    #[serde(skip)]
+    pub handles: HashMap<String, UntypedHandle>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum Track {
+    /// Shown from `at` until `until`, drawn with `image` or as a `size` rectangle of `color`.
+    Sprite {
+        name: String,
+        #[serde(default)]
+        image: Option<String>,
+        #[serde(default)]
+        atlas: Option<AtlasTrack>,
+        #[serde(default)]
+        parent: Option<String>,
+        #[serde(default)]
+        at: f32,
+        #[serde(default)]
+        until: Option<f32>,
+        #[serde(default)]
+        translation: Vec3,
+        #[serde(default = "one")]
+        scale: f32,
+        #[serde(default)]
+        size: Option<Vec2>,
+        #[serde(default = "white")]
+        color: Color,
+        #[serde(default)]
+        flip_x: bool,
+    },
+    /// Moves a sprite from wherever the tweens before left it.
+    Tween {
+        target: String,
+        at: f32,
+        duration: f32,
+        to: TweenTo,
+        #[serde(default = "linear")]
+        ease: EaseFunction,
+    },
+    Audio {
+        at: f32,
+        path: String,
+    },
+    /// Moves the world camera from where the cutscene found it.
+    Camera {
+        at: f32,
+        duration: f32,
+        #[serde(default)]
+        translation: Option<Vec2>,
+        #[serde(default)]
+        zoom: Option<f32>,
+        #[serde(default = "linear")]
+        ease: EaseFunction,
+    },
+    /// Sends `CutsceneCue` for the game to react to.
+    Cue {
+        at: f32,
+        name: String,
+    },
+    /// Goes to `state`, behind `transition` if there is one.
+    State {
+        at: f32,
+        state: AppState,
+        #[serde(default)]
+        transition: Option<TransitionStyle>,
+    },
+    /// Holds the cutscene at `at` until `key` is pressed, any key without one.
+    Wait {
+        at: f32,
+        #[serde(default)]
+        key: Option<KeyCode>,
+    },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct AtlasTrack {
+    pub tile: UVec2,
+    pub columns: u32,
+    pub rows: u32,
+    /// Played in a loop, `frame_time` apart.
+    pub frames: Vec<usize>,
+    #[serde(default)]
+    pub frame_time: f32,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub enum TweenTo {
+    Translation(Vec3),
+    Scale(f32),
+    Rotation(f32),
+    Alpha(f32),
+}
+
+fn one() -> f32 {1.}
+fn white() -> Color {Color::WHITE}
+fn linear() -> EaseFunction {EaseFunction::Linear}
+
+impl Track {
+    /// When the track starts.
+    pub fn at(&self) -> f32 {
+        match self {
+            Track::Sprite {at, ..} | Track::Tween {at, ..} | Track::Audio {at, ..} | Track::Camera {at, ..}
+                | Track::Cue {at, ..} | Track::State {at, ..} | Track::Wait {at, ..} => *at,
+        }
+    }
+
+    /// When the track is done.
+    pub fn end(&self) -> f32 {
+        match self {
+            Track::Sprite {at, until, ..} => until.unwrap_or(*at),
+            Track::Tween {at, duration, ..} | Track::Camera {at, duration, ..} => at + duration,
+            _ => self.at(),
+        }
+    }
+
+    /// Whether it fires once when playback passes it, rather than following the time.
+    pub fn is_event(&self) -> bool {
+        matches!(self, Track::Audio {..} | Track::Cue {..} | Track::State {..} | Track::Wait {..})
+    }
+}
+
+/// Eased progress of a track that started at `at`, 0 before and 1 after.
+pub fn progress(time: f32, at: f32, duration: f32, ease: EaseFunction) -> f32 {
+    if duration <= 0. {return if time >= at {1.} else {0.};}
+    ease.sample_clamped(((time - at) / duration).clamp(0., 1.))
+}
+
+/// Sprite of a `Track::Sprite` at `time`, `None` while it is not shown.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpritePose {
+    pub translation: Vec3,
+    pub scale: f32,
+    pub rotation: f32,
+    pub alpha: f32,
+    pub frame: Option<usize>,
+}
+
+impl Cutscene {
+    pub fn length(&self) -> f32 {
+        self.tracks.iter().map(Track::end).fold(0., f32::max)
+    }
+
+    pub fn pose(&self, track: &Track, time: f32) -> Option<SpritePose> {
+        let Track::Sprite {name, atlas, at, until, translation, scale, color, ..} = track else {return None;};
+        if time < *at || until.is_some_and(|u| time >= u) {return None;}
+        let mut pose = SpritePose {
+            translation: *translation,
+            scale: *scale,
+            rotation: 0.,
+            alpha: color.alpha(),
+            frame: atlas.as_ref().and_then(|a| {
+                let i = if a.frame_time > 0. {((time - at) / a.frame_time) as usize} else {0};
+                a.frames.get(i % a.frames.len().max(1)).copied()
+            }),
+        };
+        for tween in self.tracks.iter() {
+            let Track::Tween {target, at, duration, to, ease} = tween else {continue;};
+            if target != name || time < *at {continue;}
+            let t = progress(time, *at, *duration, *ease);
+            match to {
+                TweenTo::Translation(to) => pose.translation = pose.translation.lerp(*to, t),
+                TweenTo::Scale(to) => pose.scale = pose.scale.lerp(*to, t),
+                TweenTo::Rotation(to) => pose.rotation = pose.rotation.lerp(*to, t),
+                TweenTo::Alpha(to) => pose.alpha = pose.alpha.lerp(*to, t),
+            }
+        }
+        Some(pose)
+    }
+}
+
+
+#[derive(Default)]
+pub struct CutsceneLoader;
+
+#[derive(Debug, Error)]
+pub enum CutsceneLoaderError {
+    #[error("Could not read cutscene: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse cutscene: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for CutsceneLoader {
+    type Asset = Cutscene;
+    type Settings = ();
+    type Error = CutsceneLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let mut cutscene: Cutscene = ron::de::from_bytes(&bytes)?;
+        // loading them here makes the cutscene wait for them in a loading state
+        for track in cutscene.tracks.iter() {
+            match track {
+                Track::Sprite {image: Some(path), ..} => {
+                    cutscene.handles.insert(path.clone(), load_context.load::<Image>(path.clone()).untyped());
+                }
+                Track::Audio {path, ..} => {
+                    cutscene.handles.insert(path.clone(), load_context.load::<AudioSource>(path.clone()).untyped());
+                }
+                _ => {}
+            }
+        }
+        Ok(cutscene)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["cutscene.ron"]
+    }
+}
@@ -0,0 +1,116 @@
+use bevy::math::curve::EaseFunction;
+
+use super::sequence::*;
+use crate::prelude::*;
+
+
+fn cutscene(tracks: &str) -> Cutscene {
+    ron::from_str(&format!("(tracks: [{tracks}])")).unwrap()
+}
+
+/// Pose of the first track at `time`.
+fn pose(cutscene: &Cutscene, time: f32) -> Option<SpritePose> {
+    cutscene.pose(&cutscene.tracks[0], time)
+}
+
+fn close(a: f32, b: f32) -> bool {
+    (a - b).abs() < 1e-4
+}
+
+
+#[test]
+fn progress_is_clamped_and_eased() {
+    assert_eq!(progress(0.5, 1., 2., EaseFunction::Linear), 0.);
+    assert_eq!(progress(4., 1., 2., EaseFunction::Linear), 1.);
+    assert!(close(progress(2., 1., 2., EaseFunction::Linear), 0.5));
+    assert!(close(progress(2., 1., 2., EaseFunction::QuadraticIn), 0.25));
+}
+
+#[test]
+fn zero_duration_jumps_at_its_time() {
+    assert_eq!(progress(0.99, 1., 0., EaseFunction::Linear), 0.);
+    assert_eq!(progress(1., 1., 0., EaseFunction::Linear), 1.);
+
+    let c = cutscene(r#"
+        Sprite(name: "A"),
+        Tween(target: "A", at: 1.0, duration: 0.0, to: Translation((5.0, 0.0, 0.0))),
+    "#);
+    assert_eq!(pose(&c, 0.5).unwrap().translation, Vec3::ZERO);
+    assert_eq!(pose(&c, 1.).unwrap().translation, Vec3::new(5., 0., 0.));
+}
+
+#[test]
+fn length_is_the_latest_end() {
+    assert_eq!(cutscene("").length(), 0.);
+    let c = cutscene(r#"
+        Sprite(name: "A", at: 1.0, until: Some(6.0)),
+        Tween(target: "A", at: 2.0, duration: 3.0, to: Scale(2.0)),
+        Cue(at: 4.0, name: "cue"),
+    "#);
+    assert_eq!(c.length(), 6.);
+    let c = cutscene(r#"
+        Sprite(name: "A"),
+        Camera(at: 3.0, duration: 1.5, zoom: Some(2.0)),
+        Cue(at: 7.0, name: "cue"),
+    "#);
+    assert_eq!(c.length(), 7.);
+}
+
+#[test]
+fn sprite_is_only_shown_until_until() {
+    let c = cutscene(r#"Sprite(name: "A", at: 1.0, until: Some(2.0))"#);
+    assert!(pose(&c, 0.5).is_none());
+    assert!(pose(&c, 1.).is_some());
+    assert!(pose(&c, 1.99).is_some());
+    assert!(pose(&c, 2.).is_none());
+    assert!(pose(&cutscene(r#"Sprite(name: "A")"#), 100.).is_some());
+    // events have no pose
+    let c = cutscene(r#"Cue(at: 0.0, name: "cue")"#);
+    assert!(pose(&c, 1.).is_none());
+}
+
+#[test]
+fn tweens_stack_from_where_the_last_one_left() {
+    let c = cutscene(r#"
+        Sprite(name: "A", translation: (0.0, 0.0, 1.0)),
+        Tween(target: "A", at: 0.0, duration: 1.0, to: Translation((10.0, 0.0, 1.0))),
+        Tween(target: "A", at: 2.0, duration: 1.0, to: Translation((10.0, 20.0, 1.0))),
+        Tween(target: "B", at: 0.0, duration: 1.0, to: Translation((99.0, 99.0, 99.0))),
+    "#);
+    assert_eq!(pose(&c, 0.5).unwrap().translation, Vec3::new(5., 0., 1.));
+    // holds between them
+    assert_eq!(pose(&c, 1.5).unwrap().translation, Vec3::new(10., 0., 1.));
+    assert_eq!(pose(&c, 2.5).unwrap().translation, Vec3::new(10., 10., 1.));
+    assert_eq!(pose(&c, 5.).unwrap().translation, Vec3::new(10., 20., 1.));
+}
+
+#[test]
+fn tweens_of_different_kinds_do_not_mix() {
+    let c = cutscene(r#"
+        Sprite(name: "A", scale: 2.0),
+        Tween(target: "A", at: 0.0, duration: 1.0, to: Scale(4.0)),
+        Tween(target: "A", at: 0.0, duration: 1.0, to: Rotation(1.0)),
+        Tween(target: "A", at: 0.0, duration: 1.0, to: Alpha(0.0)),
+    "#);
+    let pose = pose(&c, 0.5).unwrap();
+    assert!(close(pose.scale, 3.));
+    assert!(close(pose.rotation, 0.5));
+    assert!(close(pose.alpha, 0.5));
+    assert_eq!(pose.translation, Vec3::ZERO);
+}
+
+#[test]
+fn atlas_frames_loop() {
+    let c = cutscene(r#"
+        Sprite(name: "A", at: 1.0, atlas: Some((tile: (16, 16), columns: 8, rows: 1, frames: [3, 4, 5], frame_time: 0.1))),
+    "#);
+    let frames: Vec<_> = [1.05, 1.15, 1.25, 1.35, 1.45].iter().map(|&t| pose(&c, t).unwrap().frame).collect();
+    assert_eq!(frames, [Some(3), Some(4), Some(5), Some(3), Some(4)]);
+
+    // without a frame time it stays on the first one
+    let c = cutscene(r#"
+        Sprite(name: "A", atlas: Some((tile: (16, 16), columns: 8, rows: 1, frames: [6, 7]))),
+    "#);
+    assert_eq!(pose(&c, 3.).unwrap().frame, Some(6));
+    assert_eq!(pose(&cutscene(r#"Sprite(name: "A")"#), 0.).unwrap().frame, None);
+}
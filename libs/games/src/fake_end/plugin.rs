@@ -3,21 +3,19 @@ use std::f32::consts::PI;
 use bevy::{asset::RenderAssetUsages, camera::{RenderTarget, visibility::RenderLayers}, render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages}};
 use bevy_asset_loader::asset_collection::AssetCollection;
 
-use crate::prelude::*;
+use crate::{cutscene::{plugin::{CutsceneCue, CutscenePlayer}, sequence::{Cutscene, Track}}, prelude::*, transition::{Transition, TransitionStyle}};
 
 const STATE: AppState = AppState::FakeEnd;
-const NEXT_STATE: AppState = AppState::Novel;
-
 const RECT_HS: f32 = 3.;
+const SCREENSHOT_TRANSITION_TIME: f32 = 2.5;
 #[derive(Component)]
 struct TextureRect;
 
 #[derive(AssetCollection, Resource)]
 pub struct FakeEndAssets {
-    #[asset(path = "sounds/creak1.mp3")]
-    creek1: Handle<AudioSource>,
-    #[asset(path = "sounds/creak2.mp3")]
-    creek2: Handle<AudioSource>,
+    /// Climb and fall of the joker, the rect follows the rotation of its "Joker" track.
+    #[asset(path = "data/cutscenes/fake_end.cutscene.ron")]
+    joker: Handle<Cutscene>,
 }
 
 #[derive(Resource)]
@@ -56,7 +54,7 @@ impl Plugin for FakeEndPlugin {
             .add_systems(OnEnter(STATE), setup)
             // .add_systems(OnEnter(LocalState::Game), begin_game)
             .add_systems(Update, 
-                (monke_fall, paste_screenshot).run_if(not(in_state(SuperLocalState::JokerDown))).run_if(resource_equals(JokerSetUp(true))))
+                (monke_fall, on_cues).run_if(not(in_state(SuperLocalState::JokerDown))).run_if(resource_equals(JokerSetUp(true))))
             // .add_systems(Update, tick_defat.run_if(in_state(LocalState::Defeat)))
             // .add_systems(Update, tick_win.run_if(in_state(LocalState::Win)))
             .add_systems(Update, cleanup.run_if(in_state(SuperLocalState::JokerDown)))
@@ -148,136 +146,62 @@ fn global_setup(
 
 fn setup(
     mut cmd: Commands,
-    mut joker_rect: Query<&mut Transform, With<TextureRect>>,
     mut super_local_state: ResMut<NextState<SuperLocalState>>,
     mut joker_set_up: ResMut<JokerSetUp>,
+    last: Res<LastScreenshot>,
+    assets: Res<FakeEndAssets>,
 ) {
     joker_set_up.0 = true;
+    // the joker climbs over the fading last frame of the previous game
+    if let Some(screenshot) = last.image.clone() {
+        cmd.spawn(Transition::new(screenshot, TransitionStyle::Fade, SCREENSHOT_TRANSITION_TIME));
+    }
     super_local_state.set(SuperLocalState::Game);
-    cmd.insert_resource(FallStart {start: false, num: 0, timer: 0.});
-    cmd.insert_resource(ClimbStart {climbed: false, num: 0, timer: 0.});
-    let pivot_point = Vec3::new(0.0, -RECT_HS, -2.);
-    let q = Quat::from_axis_angle(Vec3::X, PI / 2.);
-    let mut t = joker_rect.single_mut().expect("NO JOKER");
-    *t = Transform::from_translation(Vec3::new(0.0, 0.0, -2.0));
-    t.rotate_around(pivot_point, q);
-}
-
-#[derive(Resource)]
-pub struct FallStart {
-    pub start: bool,
-    pub num: usize,
-    pub timer: f32,
-}
-
-#[derive(Resource)]
-pub struct ClimbStart {
-    pub num: usize,
-    pub timer: f32,
-    pub climbed: bool,
+    // falls over the next state, so it outlives this one
+    cmd.spawn((
+        Name::new("Joker cutscene"),
+        DespawnOnEnter(SuperLocalState::JokerDown),
+        CutscenePlayer::new(assets.joker.clone()),
+    ));
 }
 
+/// Turns the joker around its bottom edge as far as the "Joker" track is rotated.
 fn monke_fall(
-    mut cmd: Commands,
     mut transform_q: Query<&mut Transform, With<TextureRect>>,
-    fall_start: Option<ResMut<FallStart>>,
-    climb_start: Option<ResMut<ClimbStart>>,
-    time: Res<Time>,
-    fake_assets: Option<Res<FakeEndAssets>>,
-    mut super_local_state: ResMut<NextState<SuperLocalState>>,
-    mut appstate: ResMut<NextState<AppState>>,
+    players: Query<&CutscenePlayer>,
+    cutscenes: Res<Assets<Cutscene>>,
+    assets: Res<FakeEndAssets>,
 ) {
-    if let Some(mut fall_start) = fall_start {
-        let mut climb_start = climb_start.unwrap();
-        let fake_assets = fake_assets.unwrap();
-        const DEGPF: f32 = -0.01;
-        if climb_start.num as f32 * -DEGPF > PI / 2. {
-            climb_start.timer += time.delta_secs();
-            if climb_start.timer >= 2.0 {
-                fall_start.start = true;
-            }
-        } else {
-            if climb_start.num == 0 {
-                cmd.spawn((
-                    DespawnOnEnter(SuperLocalState::JokerDown),
-                    AudioPlayer(fake_assets.creek1.clone()),
-                    PlaybackSettings {
-                        mode: bevy::audio::PlaybackMode::Once,
-                        ..default()
-                    },
-                ));
-            }
-            let pivot_point = Vec3::new(0.0, -RECT_HS, -2.);
-            let q = Quat::from_axis_angle(Vec3::X, DEGPF);
-            transform_q.single_mut().expect("no rect").rotate_around(pivot_point, q);
-            climb_start.num += 1;
-        }
-        if fall_start.start {
-            if fall_start.num == 0 {
-                appstate.set(NEXT_STATE);
-                super_local_state.set(SuperLocalState::JokerUp);
-                cmd.spawn((
-                    DespawnOnEnter(SuperLocalState::JokerDown),
-                    AudioPlayer(fake_assets.creek2.clone()),
-                    PlaybackSettings {
-                        mode: bevy::audio::PlaybackMode::Once,
-                        ..default()
-                    },
-                ));
-            }
-            if fall_start.num as f32 * -DEGPF > PI {
-                super_local_state.set(SuperLocalState::JokerDown);
-            }
-            let pivot_point = Vec3::new(0.0, -RECT_HS, -2.);
-            let q = Quat::from_axis_angle(Vec3::X, DEGPF);
-            transform_q.single_mut().expect("no rect").rotate_around(pivot_point, q);
-            fall_start.num += 1;
-        }
-    }
+    let Some(player) = players.iter().find(|p| p.cutscene == assets.joker) else {return;};
+    let Some(cutscene) = cutscenes.get(&player.cutscene) else {return;};
+    let Some(pose) = cutscene.tracks.iter()
+        .find(|t| matches!(t, Track::Sprite {name, ..} if name == "Joker"))
+        .and_then(|t| cutscene.pose(t, player.time)) else {return;};
+    let Ok(mut t) = transform_q.single_mut() else {return;};
+    let pivot_point = Vec3::new(0.0, -RECT_HS, -2.);
+    *t = Transform::from_translation(Vec3::new(0.0, 0.0, -2.0));
+    t.rotate_around(pivot_point, Quat::from_axis_angle(Vec3::X, PI / 2. + pose.rotation));
 }
 
-fn paste_screenshot(
-    last: Res<LastScreenshot>,
-    mut cmd: Commands,
-    cam: Query<Entity, With<HighresCamera>>,
-    mut ran: Local<bool>
+fn on_cues(
+    mut cues: MessageReader<CutsceneCue>,
+    mut super_local_state: ResMut<NextState<SuperLocalState>>,
 ) {
-    if !*ran {
-        if let Some(img) = last.image.clone() {
-            println!("ASDSASDSD");
-            *ran = true;
-            let cam = cam.iter().next().expect("No cam!");
-            cmd.spawn((
-                Name::new("Screenshot"),
-                DespawnOnEnter(SuperLocalState::JokerUp),
-                Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
-                // RenderLayers::layer(3),
-                UiTargetCamera(cam),
-                ImageNode {
-                    image: img,
-                    ..default()
-                },
-                Node {
-                    position_type: PositionType::Absolute,
-                    width: Val::Percent(100.),
-                    height: Val::Percent(100.),
-                    ..default()
-                },
-                ZIndex(1000),
-            ));
+    for cue in cues.read() {
+        match cue.name.as_str() {
+            "fall" => super_local_state.set(SuperLocalState::JokerUp),
+            "down" => super_local_state.set(SuperLocalState::JokerDown),
+            _ => {}
         }
     }
 }
 
 fn cleanup(
-    mut cmd: Commands,
     mut cam: Query<&mut Transform, With<WorldCamera>>,
     mut ran: Local<bool>
 ) {
     if !*ran {
         *ran = true;
         cam.iter_mut().next().expect("No cam!").translation = Vec3::ZERO;
-        cmd.remove_resource::<FallStart>();
-        cmd.remove_resource::<ClimbStart>();
     }
 }
\ No newline at end of file
@@ -1 +1,5 @@
-pub mod plugin;
\ No newline at end of file
+pub mod plugin;
+pub mod run;
+
+#[cfg(test)]
+mod tests;
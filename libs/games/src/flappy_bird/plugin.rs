@@ -1,7 +1,8 @@
 use crate::{hints::{HintAssets, KeyHint}, prelude::{AppState, LastState, *}};
 use bevy_asset_loader::asset_collection::AssetCollection;
-use rand::Rng;
 use crate::global_music::plugin::NewBgMusic;
+use super::run::{Flight, FlappyMode, FlappyRun, FlappySave, FlappySettings, PipeSpec, fly};
+use crate::{ghost::{Ghost, GhostRecorder, GhostRun, spawn_ghost}, replay::{GameplayStarted, RunSeed}, tick_input::TickInput, transition::{RequestTransition, TransitionStyle}};
 
 pub struct FlappyBirdPlugin;
 
@@ -25,13 +26,17 @@ impl Plugin for FlappyBirdPlugin {
         app
             .insert_resource(LocalRes::default())
             .insert_resource(Pipes::default())
+            .insert_resource(FlappySettings::from_args())
             .add_sub_state::<LocalState>()
             .add_systems(OnEnter(STATE), setup)
             .add_systems(Update, tick_transition.run_if(in_state(LocalState::InitialAnim)))
             .add_systems(OnEnter(LocalState::Game), begin_game)
-            .add_systems(Update, tick_game.run_if(in_state(LocalState::Game)))
+            .add_systems(FixedUpdate, (fly, tick_game).chain().run_if(in_state(LocalState::Game)))
+            .add_systems(Update, (animate_pacman, interpolate_pipes).run_if(in_state(STATE)))
             // .add_systems(Update, tick_defat.run_if(in_state(LocalState::Defeat)))
+            .add_systems(OnEnter(LocalState::Win), finish_run)
             .add_systems(Update, tick_win.run_if(in_state(LocalState::Win)))
+            .add_systems(Update, update_score_text.run_if(resource_exists::<FlappyRun>))
             .add_systems(OnExit(STATE), cleanup)
             .add_observer(collision_handler)
             ;
@@ -44,8 +49,6 @@ pub struct FlappyBirdAssets {
     pipe: Handle<Image>,
     #[asset(path = "images/flappy.png")]
     pacman: Handle<Image>,
-    #[asset(path = "images/flappy_bird_transition.png")]
-    transition_bg: Handle<Image>,
     #[asset(path = "images/flappy_bird_bg.png")]
     bg: Handle<Image>,
     #[asset(texture_atlas_layout(tile_size_x = 792, tile_size_y = 1000, columns = 2, rows = 1))]
@@ -60,8 +63,9 @@ pub struct FlappyBirdAssets {
 #[derive(Component)]
 pub struct Pacman;
 
+
 #[derive(Component)]
-pub struct TransitionScreen;
+struct ScoreText;
 
 #[derive(Resource, Default)]
 pub struct LocalRes {
@@ -72,13 +76,40 @@ pub struct LocalRes {
 fn setup(
     mut cmd: Commands,
     assets: Res<FlappyBirdAssets>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
     // mut texture_atlas_layouts: ResMut<Assets<TextureAtlasLayout>>,
     mut state: ResMut<LastState>,
+    save: Option<Res<FlappySave>>,
+    settings: Res<FlappySettings>,
+    mut run_seed: ResMut<RunSeed>,
 ) {
     state.state = STATE;
 
+    let save = match save {
+        Some(save) => save.clone(),
+        None => {
+            let save = FlappySave::load();
+            cmd.insert_resource(save.clone());
+            save
+        }
+    };
+    let mode = if save.cleared {settings.mode} else {FlappyMode::Story};
+    let seed = settings.seed.unwrap_or_else(|| run_seed.next());
+    info!("Flappy {mode:?} run with seed {seed}");
+    cmd.insert_resource(FlappyRun::new(mode, seed));
+
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        ScoreText,
+        Text::new(""),
+        TextColor::WHITE,
+        Node {
+            top: Val::Px(8.0),
+            right: Val::Px(12.0),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+    ));
+
     cmd.spawn((
         NewBgMusic{handle: Some(assets.bg_music.clone()), instant_translation: false},
     ));
@@ -115,26 +146,7 @@ fn setup(
         Transform::from_translation(Vec3::new(0.0, FLAPPY_HALF_HEIGHT, 0.0)),
     ));
 
-    meshes.add(Rectangle::new(50.0, 100.0));
-
-    let r = meshes.add(Rectangle::new(FLAPPY_WIDTH, FLAPPY_HALF_HEIGHT*2. + 20.0));
-
-    cmd.spawn((
-        DespawnOnExit(STATE),
-        Name::new("Transition"),
-        Sprite {
-            image: assets.transition_bg.clone(),
-            ..default()
-        },
-        Transform::from_translation(Vec3::new(FLAPPY_WIDTH * 0.5, 0.0, 10.0)),
-        TransitionScreen,
-        children![(
-            Mesh2d(r),
-            MeshMaterial2d(materials.add(Color::BLACK)),
-            Transform::from_translation(Vec3::new(-FLAPPY_WIDTH * 0.5 - 33.0, 0.0, 0.0))
-        )]
-    ));
-
+    // the pacman intro slides away over this, see `pacman_intro.cutscene.ron`
     cmd.spawn((
         DespawnOnExit(STATE),
         Name::new("Pacman"),
@@ -160,7 +172,12 @@ fn begin_game (
     q: Query<Entity, With<Pacman>>,
     hint_assets: Res<HintAssets>,
     cam: Query<Entity, With<WorldCamera>>,
+    assets: Res<FlappyBirdAssets>,
+    run: Res<FlappyRun>,
+    ghosts: Query<Entity, With<Ghost>>,
+    mut started: MessageWriter<GameplayStarted>,
 ) {
+    started.write(GameplayStarted);
     let cam = cam.iter().next().expect("No cam!");
     crate::hints::show_hints(
         &mut cmd,
@@ -169,15 +186,26 @@ fn begin_game (
         cam,
         hint_assets,
     );
-    cmd.spawn((
-
-    ));
-    cmd.entity(q.iter().next().expect("No pacman!")).insert(GravityScale(FLAPPY_GRAVITY_AFFECT));
+    // moved by `fly` from now on, the rendering catches up with the ticks
+    cmd.entity(q.iter().next().expect("No pacman!")).insert((Flight::default(), TransformInterpolation, GhostRecorder::default()));
+    // an endless run after the story one races its own ghost
+    for ghost in ghosts.iter() {
+        cmd.entity(ghost).despawn();
+    }
+    if let Some(ghost) = GhostRun::load(run.mode.ghost_path()) {
+        spawn_ghost(&mut cmd, ghost, Sprite {
+            image: assets.pacman.clone(),
+            texture_atlas: Some(TextureAtlas {
+                layout: assets.layout.clone(),
+                index: 0,
+            }),
+            ..default()
+        }, STATE);
+    }
 }
 
 fn tick_transition(
-    mut pacman: Query<&mut Transform, (With<Pacman>, Without<TransitionScreen>)>,
-    mut transition: Query<&mut Transform, (With<TransitionScreen>, Without<Pacman>)>,
+    mut pacman: Query<&mut Transform, With<Pacman>>,
     mut res: ResMut<LocalRes>,
     t: Res<Time>,
     mut state: ResMut<NextState<LocalState>>
@@ -190,9 +218,6 @@ fn tick_transition(
             state.set(LocalState::Game);
         }
     }
-    for mut t in transition.iter_mut() {
-        t.translation.x -= FLAPPY_BG_TRANSITION_SPEED * dt;
-    }
 }
 
 
@@ -203,24 +228,39 @@ struct Pipes {
     buffer: Vec<Entity>,
 }
 
+#[derive(Component, Default)]
+struct Pipe {
+    /// Where the last two ticks left it, drawn in between.
+    pos: Vec2,
+    prev: Vec2,
+    spec: Option<PipeSpec>,
+    age: f32,
+    /// Already counted in the score.
+    passed: bool,
+}
+
+/// Bottom is -1, top is 1.
 #[derive(Component)]
-struct Pipe;
+struct PipeHalf(f32);
 
 fn spawn_pipe(
     cmd: &mut Commands,
     assets: &FlappyBirdAssets,
+    spec: PipeSpec,
 ) -> Entity {
    cmd.spawn((
         DespawnOnExit(STATE),
         Name::new("Pipe"),
-        Pipe,
+        Pipe {pos: vec2(FLAPPY_WIDTH, spec.y), prev: vec2(FLAPPY_WIDTH, spec.y), spec: Some(spec), ..default()},
+        Transform::from_translation(vec3(FLAPPY_WIDTH, spec.y, 0.0)),
         Visibility::default(),
         InheritedVisibility::VISIBLE,
         GlobalTransform::default(),
         children![
             (
                 Collider::rectangle(30.0 * (1.0 / FLAPPY_PIPE_SCALE), FLAPPY_HALF_HEIGHT * (1.0 / FLAPPY_PIPE_SCALE) * 1.7),
-                Transform::from_translation(Vec3::new(0.0, -FLAPPY_HALF_HEIGHT * 0.5 - FLAPPY_PIPE_GAP, 5.0)).with_scale(Vec3::splat(FLAPPY_PIPE_SCALE)),
+                Transform::from_translation(Vec3::new(0.0, -FLAPPY_HALF_HEIGHT * 0.5 - spec.gap, 5.0)).with_scale(Vec3::splat(FLAPPY_PIPE_SCALE)),
+                PipeHalf(-1.0),
                 CollisionEventsEnabled,
                 Sensor,
                 RigidBody::Kinematic,
@@ -233,7 +273,8 @@ fn spawn_pipe(
             ),
             (
                 Collider::rectangle(30.0 * (1.0 / FLAPPY_PIPE_SCALE), FLAPPY_HALF_HEIGHT * (1.0 / FLAPPY_PIPE_SCALE) * 1.7),
-                Transform::from_translation(Vec3::new(0.0, FLAPPY_HALF_HEIGHT * 0.5 + FLAPPY_PIPE_GAP, 5.0)).with_scale(Vec3::splat(FLAPPY_PIPE_SCALE)),
+                Transform::from_translation(Vec3::new(0.0, FLAPPY_HALF_HEIGHT * 0.5 + spec.gap, 5.0)).with_scale(Vec3::splat(FLAPPY_PIPE_SCALE)),
+                PipeHalf(1.0),
                 CollisionEventsEnabled,
                 Sensor,
                 RigidBody::Kinematic,
@@ -251,71 +292,144 @@ fn spawn_pipe(
 
 fn tick_game(
     mut state: ResMut<NextState<LocalState>>,
-    mut pacman: Query<(&mut Transform, &mut LinearVelocity, &mut Sprite), (With<Pacman>, Without<Pipe>)>,
-    mut pipe_q: Query<&mut Transform, (With<Pipe>, Without<Pacman>)>,
+    mut pacman: Query<&mut Position, (With<Pacman>, Without<Pipe>)>,
+    mut pipe_q: Query<(&mut Transform, &mut Pipe, &Children), (Without<Pacman>, Without<PipeHalf>)>,
+    mut halves: Query<(&mut Transform, &PipeHalf), (Without<Pacman>, Without<Pipe>)>,
     mut res: ResMut<LocalRes>,
     t: Res<Time>,
-    keys: Res<ButtonInput<KeyCode>>,
-    mut at: Local<f32>,
+    input: Res<TickInput>,
     mut cmd: Commands,
     mut pipes: ResMut<Pipes>,
+    mut run: ResMut<FlappyRun>,
+    mut ghost: Query<&mut GhostRecorder, With<Pacman>>,
     assets: Res<FlappyBirdAssets>
 ){
-    let mut rng = rand::rng();
-    let dt = t.delta_secs().min(MAX_DT);
-    let (mut t, mut v, mut s) = pacman.iter_mut().next().expect("No pacman!");
+    let dt = t.delta_secs();
+    let Ok(mut position) = pacman.single_mut() else {return;};
     res.fake_x += (FLAPPY_PARALLAX_SPEED - FLAPPY_BIRD_PROGRESS_SPEED) * dt;
-    if keys.just_pressed(KeyCode::Space) {
-        v.y = FLAPPY_BIRD_JUMP_STRENGTH;
-        *at = 0.3;
+    if input.just_pressed(KeyCode::Space) {
         cmd.spawn((
             DespawnOnEnter(NEXT_STATE),
             AudioPlayer::new(assets.woosh.clone()),
         ));
     }
-    t.translation += dt * FLAPPY_BIRD_PROGRESS_SPEED;
-    let Some(a) = &mut s.texture_atlas else {return;};
-    if *at > 0.0 {
-        *at -= dt;
-        a.index = 1;
-    } else {
-        a.index = 0;
-    }
-    if t.translation.x >= FLAPPY_RIGHT_BOUND {
-        state.set(LocalState::Win);
+    match run.mode {
+        FlappyMode::Story => if position.x >= FLAPPY_RIGHT_BOUND {
+            state.set(LocalState::Win);
+        },
+        FlappyMode::Endless => position.x = position.x.min(FLAPPY_RIGHT_BOUND),
     }
 
     pipes.since_prev += dt;
+    let speed = run.pipes.speed();
     for entity in pipes.pipes.clone().iter() {
-        let Ok(mut e) = pipe_q.get_mut(*entity) else {continue;};
-        e.translation.x -= FLAPPY_PIPE_SPEED * dt;
-        if e.translation.x <= -FLAPPY_WIDTH * 0.5 {
+        let Ok((mut e, mut pipe, _)) = pipe_q.get_mut(*entity) else {continue;};
+        let Some(spec) = pipe.spec else {continue;};
+        pipe.age += dt;
+        pipe.prev = pipe.pos;
+        pipe.pos.x -= speed * dt;
+        pipe.pos.y = spec.y + spec.sway * (pipe.age * FLAPPY_PIPE_SWAY_SPEED + spec.sway_phase).sin();
+        // physics sees where the pipe really is, `interpolate_pipes` draws it in between
+        e.translation = pipe.pos.extend(e.translation.z);
+        if !pipe.passed && pipe.pos.x < position.x {
+            pipe.passed = true;
+            run.score += 1;
+            if let Ok(mut ghost) = ghost.single_mut() {
+                ghost.checkpoint();
+            }
+        }
+        if pipe.pos.x <= -FLAPPY_WIDTH * 0.5 && !pipes.buffer.contains(entity) {
             pipes.buffer.push(*entity);
         }
     }
 
-    if pipes.since_prev > FLAPPY_PIPE_SPAWN_DELAY {
+    if pipes.since_prev > run.pipes.delay() {
         pipes.since_prev = 0.0;
-        let e = if let Some(p) = pipes.buffer.pop() {
-            p
+        let spec = run.pipes.next();
+        if let Some(e) = pipes.buffer.pop() {
+            let Ok((mut transform, mut pipe, children)) = pipe_q.get_mut(e) else {return;};
+            let pos = vec2(FLAPPY_WIDTH, spec.y);
+            transform.translation = pos.extend(0.0);
+            *pipe = Pipe {pos, prev: pos, spec: Some(spec), ..default()};
+            for child in children.iter() {
+                let Ok((mut transform, half)) = halves.get_mut(child) else {continue;};
+                transform.translation.y = half.0 * (FLAPPY_HALF_HEIGHT * 0.5 + spec.gap);
+            }
         } else {
             info!("Spawning new pipe");
-            let e = spawn_pipe(&mut cmd, &assets);
+            let e = spawn_pipe(&mut cmd, &assets, spec);
             pipes.pipes.push(e);
-            e
-        };
-        cmd.entity(e).insert(
-            Transform::from_translation(vec3(FLAPPY_WIDTH, rng.random_range(-FLAPPY_PIPE_SPREAD..=FLAPPY_PIPE_SPREAD), 0.0))
-        );
+        }
     }
 
 }
 
+fn animate_pacman(
+    mut pacman: Query<(&Flight, &mut Sprite), With<Pacman>>,
+) {
+    for (flight, mut sprite) in pacman.iter_mut() {
+        let Some(a) = &mut sprite.texture_atlas else {continue;};
+        a.index = if flight.flap > 0.0 {1} else {0};
+    }
+}
+
+fn interpolate_pipes(
+    fixed: Res<Time<Fixed>>,
+    mut pipe_q: Query<(&mut Transform, &Pipe)>,
+) {
+    let alpha = fixed.overstep_fraction();
+    for (mut transform, pipe) in pipe_q.iter_mut() {
+        if pipe.spec.is_none() {continue;}
+        transform.translation = pipe.prev.lerp(pipe.pos, alpha).extend(transform.translation.z);
+    }
+}
+
+fn update_score_text(
+    run: Res<FlappyRun>,
+    save: Res<FlappySave>,
+    state: Option<Res<State<LocalState>>>,
+    mut text: Query<&mut Text, With<ScoreText>>,
+) {
+    let Ok(mut text) = text.single_mut() else {return;};
+    let mode = match run.mode {
+        FlappyMode::Story => "",
+        FlappyMode::Endless => "endless ",
+    };
+    text.0 = format!("{mode}{}  best {}  seed {}", run.score, save.best(run.mode).max(run.score), run.seed);
+    let won = state.is_some_and(|s| s.get() == &LocalState::Win);
+    if won && run.mode == FlappyMode::Story && save.cleared {
+        text.0.push_str("\nE - endless, Space - continue");
+    }
+}
+
+/// Keeps the high score and unlocks endless after a story clear.
+fn record_run(run: &FlappyRun, save: &mut FlappySave, cleared: bool, ghost: Option<&GhostRecorder>) {
+    if let Some(ghost) = ghost {
+        ghost.store_if_best(run.mode.ghost_path(), run.score as f32);
+    }
+    let mut changed = save.record(run.mode, run.score);
+    if cleared && !save.cleared {
+        save.cleared = true;
+        changed = true;
+    }
+    if changed {
+        save.store();
+    }
+}
+
+fn finish_run(
+    run: Res<FlappyRun>,
+    mut save: ResMut<FlappySave>,
+    ghost: Query<&GhostRecorder, With<Pacman>>,
+) {
+    record_run(&run, &mut save, run.mode == FlappyMode::Story, ghost.single().ok());
+}
 
 fn cleanup(
     mut cmd: Commands,
 ) {
     cmd.remove_resource::<Pipes>();
+    cmd.remove_resource::<FlappyRun>();
 }
 
 fn collision_handler(
@@ -324,32 +438,77 @@ fn collision_handler(
     q: Query<Entity, With<Pacman>>,
     s: Res<State<AppState>>,
     ls: Option<Res<State<LocalState>>>,
-    mut screenshot: ResMut<crate::properties::LastScreenshot>,
+    mut local_state: ResMut<NextState<LocalState>>,
+    run: Option<Res<FlappyRun>>,
+    save: Option<ResMut<FlappySave>>,
+    mut transitions: MessageWriter<RequestTransition>,
+    ghost: Query<&GhostRecorder, With<Pacman>>,
 ){
     if s.get() != &STATE {return;}
     let Some(l) = ls else {return;};
     if l.get() != &LocalState::Game {return;}
     let p = q.iter().next().expect("No pacman!");
     if _e.collider1 != p && _e.collider2 != p {return;}
-    if screenshot.awaiting == false {
-        cmd.spawn(bevy::render::view::screenshot::Screenshot::primary_window())
-            .observe(await_screenshot_and_translate(AppState::Defeat));
-        screenshot.awaiting = true;
+    // both are inserted on flappy setup, this observer sees every game's collisions
+    let (Some(run), Some(mut save)) = (run, save) else {return;};
+    // an endless run is only over, not lost
+    if run.mode == FlappyMode::Endless {
+        local_state.set(LocalState::Win);
+        return;
     }
-    cmd.entity(q.iter().next().expect("No pacman!")).remove::<RigidBody>();
+    record_run(&run, &mut save, false, ghost.single().ok());
+    transitions.write(RequestTransition::capture(AppState::Defeat));
+    cmd.entity(q.iter().next().expect("No pacman!")).remove::<(RigidBody, Flight, TransformInterpolation)>();
 }
 
 fn tick_win(
-    mut state: ResMut<NextState<AppState>>,
-    mut q: Query<(Entity, &mut Transform), With<Pacman>>,
+    mut transitions: MessageWriter<RequestTransition>,
+    mut local_state: ResMut<NextState<LocalState>>,
+    mut q: Query<(Entity, &mut Transform, &mut Position), With<Pacman>>,
+    mut pipe_q: Query<(&mut Transform, &mut Pipe), Without<Pacman>>,
+    mut pipes: ResMut<Pipes>,
+    mut run: ResMut<FlappyRun>,
+    save: Res<FlappySave>,
+    settings: Res<FlappySettings>,
+    mut run_seed: ResMut<RunSeed>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut leaving: Local<bool>,
     t: Res<Time>,
     mut cmd: Commands
 ) {
     let dt = t.delta_secs().min(MAX_DT);
-    let (e, mut t) = q.iter_mut().next().expect("No pacman!");
-    cmd.entity(e).remove::<RigidBody>();
+    let (e, mut t, mut position) = q.iter_mut().next().expect("No pacman!");
+    cmd.entity(e).remove::<(RigidBody, Flight, TransformInterpolation)>();
+    // after a story clear the pacman waits for a choice
+    if !*leaving && run.mode == FlappyMode::Story && save.cleared {
+        if keys.just_pressed(KeyCode::KeyE) {
+            let seed = settings.seed.unwrap_or_else(|| run_seed.next());
+            info!("Flappy endless run with seed {seed}");
+            // only this run, the next flappy run starts in the chosen mode again
+            *run = FlappyRun::new(FlappyMode::Endless, seed);
+            for entity in pipes.pipes.clone().iter() {
+                let Ok((mut transform, mut pipe)) = pipe_q.get_mut(*entity) else {continue;};
+                pipe.pos.x = -FLAPPY_WIDTH;
+                pipe.prev = pipe.pos;
+                transform.translation.x = -FLAPPY_WIDTH;
+                if !pipes.buffer.contains(entity) {
+                    pipes.buffer.push(*entity);
+                }
+            }
+            pipes.since_prev = 0.0;
+            t.translation.x = FLAPPY_LEFT_BOUND;
+            position.x = FLAPPY_LEFT_BOUND;
+            // `begin_game` gives the flight back
+            cmd.entity(e).insert(RigidBody::Dynamic);
+            local_state.set(LocalState::Game);
+            return;
+        }
+        if !keys.just_pressed(KeyCode::Space) {return;}
+        *leaving = true;
+    }
     t.translation.x += dt * FLAPPY_BIRD_OUT_SPEED;
     if t.translation.x >= FLAPPY_WIDTH * 0.5 + 100.0 {
-        state.set(NEXT_STATE)
+        *leaving = false;
+        transitions.write(RequestTransition::new(NEXT_STATE, TransitionStyle::Iris));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,209 @@
+use bevy::math::FloatExt;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, saves, tick_input::TickInput};
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlappyMode {
+    /// Ends at `FLAPPY_RIGHT_BOUND`.
+    #[default]
+    Story,
+    /// Goes on until a pipe is hit, unlocked by the first story clear.
+    Endless,
+}
+
+impl FlappyMode {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "story" => Some(Self::Story),
+            "endless" => Some(Self::Endless),
+            _ => None,
+        }
+    }
+
+    /// Best run of the mode for its ghost, next to `FlappySave`.
+    pub fn ghost_path(self) -> &'static str {
+        match self {
+            FlappyMode::Story => "saves/flappy_ghost.ron",
+            FlappyMode::Endless => "saves/flappy_endless_ghost.ron",
+        }
+    }
+}
+
+/// Kept between runs, `seed` makes the next run use the same pipes.
+#[derive(Resource, Default, Debug)]
+pub struct FlappySettings {
+    pub mode: FlappyMode,
+    pub seed: Option<u64>,
+}
+
+impl FlappySettings {
+    /// `--flappy-seed <seed>` replays a shared run, `--flappy-mode endless` starts endless once story is cleared.
+    /// `FLAPPY_SEED` and `FLAPPY_MODE` are read when the flags are missing.
+    pub fn from_args() -> Self {
+        #[allow(unused_mut)]
+        let mut settings = Self::default();
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            settings.seed = std::env::var("FLAPPY_SEED").ok().and_then(|s| s.parse().ok());
+            settings.mode = std::env::var("FLAPPY_MODE").ok().and_then(|m| FlappyMode::parse(&m)).unwrap_or_default();
+            settings.apply_args(std::env::args().skip(1));
+        }
+        settings
+    }
+
+    pub fn apply_args(&mut self, args: impl Iterator<Item = String>) {
+        let args = args.collect::<Vec<_>>();
+        for pair in args.windows(2) {
+            match pair[0].as_str() {
+                "--flappy-seed" => match pair[1].parse() {
+                    Ok(seed) => self.seed = Some(seed),
+                    Err(_) => warn!("Flappy seed should be a number, got {}", pair[1]),
+                },
+                "--flappy-mode" => match FlappyMode::parse(&pair[1]) {
+                    Some(mode) => self.mode = mode,
+                    None => warn!("Flappy mode should be story or endless, got {}", pair[1]),
+                },
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Clears and high scores, kept between runs.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct FlappySave {
+    pub cleared: bool,
+    pub best: u32,
+    pub best_endless: u32,
+}
+
+impl FlappySave {
+    pub fn load() -> Self {
+        saves::load(FLAPPY_SAVE_PATH)
+    }
+
+    pub fn store(&self) {
+        saves::store(FLAPPY_SAVE_PATH, self);
+    }
+
+    pub fn best(&self, mode: FlappyMode) -> u32 {
+        match mode {
+            FlappyMode::Story => self.best,
+            FlappyMode::Endless => self.best_endless,
+        }
+    }
+
+    /// Returns whether it is a new high score.
+    pub fn record(&mut self, mode: FlappyMode, score: u32) -> bool {
+        let best = match mode {
+            FlappyMode::Story => &mut self.best,
+            FlappyMode::Endless => &mut self.best_endless,
+        };
+        if score <= *best {return false;}
+        *best = score;
+        true
+    }
+}
+
+/// Current run.
+#[derive(Resource, Debug)]
+pub struct FlappyRun {
+    pub mode: FlappyMode,
+    pub seed: u64,
+    pub score: u32,
+    pub pipes: PipeGenerator,
+}
+
+impl FlappyRun {
+    pub fn new(mode: FlappyMode, seed: u64) -> Self {
+        Self {mode, seed, score: 0, pipes: PipeGenerator::new(seed)}
+    }
+}
+
+/// One pipe pair, `y` is the center of the gap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PipeSpec {
+    pub y: f32,
+    pub gap: f32,
+    /// Vertical swing of moving pipes.
+    pub sway: f32,
+    pub sway_phase: f32,
+}
+
+/// Pipes of a run depend only on the seed.
+#[derive(Debug)]
+pub struct PipeGenerator {
+    rng: StdRng,
+    /// Pipes generated so far.
+    pub index: u32,
+}
+
+impl PipeGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {rng: StdRng::seed_from_u64(seed), index: 0}
+    }
+
+    /// 0 at the first pipe, 1 from `FLAPPY_DIFFICULTY_PIPES` on.
+    pub fn difficulty(&self) -> f32 {
+        (self.index as f32 / FLAPPY_DIFFICULTY_PIPES).min(1.0)
+    }
+
+    pub fn speed(&self) -> f32 {
+        FLAPPY_PIPE_SPEED.lerp(FLAPPY_PIPE_SPEED_MAX, self.difficulty())
+    }
+
+    /// Keeps the distance between pipes as they speed up.
+    pub fn delay(&self) -> f32 {
+        FLAPPY_PIPE_SPAWN_DELAY * FLAPPY_PIPE_SPEED / self.speed()
+    }
+
+    pub fn next(&mut self) -> PipeSpec {
+        let d = self.difficulty();
+        let y = self.rng.random_range(-FLAPPY_PIPE_SPREAD..=FLAPPY_PIPE_SPREAD);
+        // the harder it gets, the more pipes move and the further
+        let sway = if self.rng.random::<f32>() < d {FLAPPY_PIPE_SWAY_MAX * d} else {0.0};
+        let sway_phase = self.rng.random_range(0.0..std::f32::consts::TAU);
+        self.index += 1;
+        PipeSpec {
+            y,
+            gap: FLAPPY_PIPE_GAP.lerp(FLAPPY_PIPE_GAP_MIN, d),
+            sway,
+            sway_phase,
+        }
+    }
+}
+
+
+/// Pacman motion, stepped in `FixedUpdate` by `fly` rather than by the physics engine,
+/// so a run depends only on the ticks its jumps land on.
+#[derive(Component, Default, Debug, Clone, Copy)]
+pub struct Flight {
+    pub velocity: f32,
+    /// Seconds left of the flap frame.
+    pub flap: f32,
+}
+
+pub fn fly(
+    input: Res<TickInput>,
+    time: Res<Time>,
+    mut q: Query<(&mut Flight, &mut Position)>,
+) {
+    let dt = time.delta_secs();
+    for (mut flight, mut position) in q.iter_mut() {
+        if input.just_pressed(KeyCode::Space) {
+            flight.velocity = FLAPPY_BIRD_JUMP_STRENGTH;
+            flight.flap = FLAPPY_FLAP_TIME;
+        }
+        flight.velocity -= FLAPPY_GRAVITY * dt;
+        position.y += flight.velocity * dt;
+        position.x += FLAPPY_BIRD_PROGRESS_SPEED * dt;
+        flight.flap = (flight.flap - dt).max(0.0);
+    }
+}
+
+
+const FLAPPY_SAVE_PATH: &str = "saves/flappy.ron";
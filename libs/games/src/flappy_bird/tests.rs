@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use bevy::{state::app::StatesPlugin, time::TimeUpdateStrategy};
+
+use super::run::*;
+use crate::{prelude::*, replay::{GameplayStarted, InputRecording, Recorder, Replay, ReplayPlugin}, tick_input::{TickInput, TickInputPlugin}};
+
+
+/// Ticks the jump key is pressed on, frames for `tap_keys`.
+#[derive(Resource)]
+struct Jumps(Vec<u64>);
+
+/// Pacman position after every tick.
+#[derive(Resource, Default)]
+struct Flown(Vec<Vec2>);
+
+fn replay_jumps(jumps: Res<Jumps>, mut input: ResMut<TickInput>) {
+    if jumps.0.contains(&input.tick) {
+        input.press(KeyCode::Space);
+    }
+}
+
+/// Frames the stand-in intro takes, like a map load it does not care about the frame rate.
+const INTRO_FRAMES: u32 = 20;
+
+#[derive(States, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+enum Phase {
+    #[default]
+    Intro,
+    Game,
+}
+
+fn play_intro(mut frames: Local<u32>, mut phase: ResMut<NextState<Phase>>) {
+    *frames += 1;
+    if *frames == INTRO_FRAMES {
+        phase.set(Phase::Game);
+    }
+}
+
+fn begin_game(mut started: MessageWriter<GameplayStarted>) {
+    started.write(GameplayStarted);
+}
+
+/// Taps space on the frames in `Jumps`, the way the keyboard would.
+fn tap_keys(jumps: Res<Jumps>, mut keys: ResMut<ButtonInput<KeyCode>>, mut frame: Local<u64>) {
+    keys.reset_all();
+    if jumps.0.contains(&*frame) {
+        keys.press(KeyCode::Space);
+    }
+    *frame += 1;
+}
+
+fn record_arc(q: Query<&Position, With<Flight>>, mut arc: ResMut<Flown>) {
+    arc.0.extend(q.iter().map(|p| p.0));
+}
+
+fn flight_app(fps: f64) -> App {
+    let mut app = App::new();
+    app
+        .add_plugins((MinimalPlugins, TickInputPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / fps)))
+        .init_resource::<Flown>();
+    app.world_mut().spawn((Flight::default(), Position::default()));
+    app
+}
+
+fn fly_for(app: &mut App, ticks: usize) -> Vec<Vec2> {
+    while app.world().resource::<Flown>().0.len() < ticks {
+        app.update();
+    }
+    let mut arc = std::mem::take(&mut app.world_mut().resource_mut::<Flown>().0);
+    arc.truncate(ticks);
+    arc
+}
+
+/// Flies headless at `fps` until `ticks` fixed ticks have run.
+fn fly_at(fps: f64, jumps: &[u64], ticks: usize) -> Vec<Vec2> {
+    let mut app = flight_app(fps);
+    app
+        .insert_resource(Jumps(jumps.to_vec()))
+        .add_systems(FixedUpdate, (replay_jumps, fly, record_arc).chain());
+    fly_for(&mut app, ticks)
+}
+
+/// Runs the replay plugin with an intro before `fly`.
+fn intro_app(fps: f64) -> App {
+    let mut app = flight_app(fps);
+    app
+        .add_plugins((StatesPlugin, ReplayPlugin))
+        .insert_state(AppState::FlappyBird)
+        .init_state::<Phase>()
+        .init_resource::<ButtonInput<KeyCode>>()
+        .add_systems(Update, play_intro.run_if(in_state(Phase::Intro)))
+        .add_systems(OnEnter(Phase::Game), begin_game)
+        .add_systems(FixedUpdate, (fly, record_arc).chain().run_if(in_state(Phase::Game)));
+    app
+}
+
+
+#[test]
+fn same_jumps_same_arc_at_any_frame_rate() {
+    let jumps = [5, 40, 52, 90];
+    let reference = fly_at(60.0, &jumps, 150);
+    for fps in [30.0, 144.0, 240.0] {
+        assert_eq!(fly_at(fps, &jumps, 150), reference, "{fps} FPS");
+    }
+}
+
+#[test]
+fn jump_goes_up_then_falls() {
+    let arc = fly_at(60.0, &[0], 100);
+    let peak = arc.iter().map(|p| p.y).fold(f32::MIN, f32::max);
+    assert!(arc[0].y > 0.0);
+    assert!(peak > arc[0].y);
+    assert!(arc.last().unwrap().y < peak);
+}
+
+#[test]
+fn seed_gives_same_pipes() {
+    let mut a = PipeGenerator::new(7);
+    let mut b = PipeGenerator::new(7);
+    for _ in 0..60 {
+        assert_eq!(a.next(), b.next());
+    }
+}
+
+#[test]
+fn recorded_run_replays_the_same_after_an_intro() {
+    let mut app = intro_app(60.0);
+    app
+        .insert_resource(Recorder::new("unused.ron".into()))
+        .insert_resource(Jumps(vec![5, 30, 31, 45, 80]))
+        .add_systems(PreUpdate, tap_keys);
+    let arc = fly_for(&mut app, 120);
+    let recording = app.world().resource::<Recorder>().recording().expect("not recording");
+    let recording: InputRecording = ron::from_str(&ron::to_string(recording).unwrap()).unwrap();
+    assert_eq!(recording.starts.len(), 1);
+
+    // the intro ends many ticks earlier or later than it did while recording
+    for fps in [30.0, 144.0] {
+        let mut app = intro_app(fps);
+        app.insert_resource(Replay::new(recording.clone()));
+        assert_eq!(fly_for(&mut app, 100), arc[..100], "{fps} FPS");
+    }
+}
+
+#[test]
+fn settings_from_flags() {
+    let mut settings = FlappySettings::default();
+    let args = ["--record", "run.ron", "--flappy-seed", "42", "--flappy-mode", "Endless"];
+    settings.apply_args(args.into_iter().map(String::from));
+    assert_eq!(settings.seed, Some(42));
+    assert_eq!(settings.mode, FlappyMode::Endless);
+
+    let mut settings = FlappySettings::default();
+    settings.apply_args(["--flappy-seed", "pipes", "--flappy-mode", "hard"].into_iter().map(String::from));
+    assert_eq!(settings.seed, None);
+    assert_eq!(settings.mode, FlappyMode::Story);
+}
@@ -1 +1,6 @@
 pub mod plugin;
+pub mod objects;
+pub mod progress;
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,157 @@
+use bevy::ecs::entity::EntityHashSet;
+
+use crate::prelude::*;
+
+
+/// Placed on Tiled objects, the object's collider is what the cube has to touch.
+/// Launches the cube as soon as it touches the pad.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct JumpPad {
+    pub strength: f32,
+}
+
+impl Default for JumpPad {
+    fn default() -> Self {
+        Self {strength: 220.}
+    }
+}
+
+/// Launches the cube when jump is pressed mid-air while touching it.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct JumpOrb {
+    pub strength: f32,
+}
+
+impl Default for JumpOrb {
+    fn default() -> Self {
+        Self {strength: 170.}
+    }
+}
+
+/// Makes the cube fall up, or back down.
+#[derive(Component, Default, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct GravityPortal {
+    pub flipped: bool,
+}
+
+/// Multiplies the cube's horizontal speed.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct SpeedPortal {
+    pub speed: f32,
+}
+
+impl Default for SpeedPortal {
+    fn default() -> Self {
+        Self {speed: 1.}
+    }
+}
+
+/// Shrinks the cube to half its size, or back.
+#[derive(Component, Default, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct SizePortal {
+    pub mini: bool,
+}
+
+/// Turns the cube into a ship that flies up while jump is held, or back into a cube.
+#[derive(Component, Default, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct ShipPortal {
+    pub ship: bool,
+}
+
+/// What the portals did to the cube so far.
+#[derive(Component, Clone, Debug)]
+pub struct CubeMode {
+    pub flipped: bool,
+    pub speed: f32,
+    pub mini: bool,
+    pub ship: bool,
+}
+
+impl Default for CubeMode {
+    fn default() -> Self {
+        Self {flipped: false, speed: 1., mini: false, ship: false}
+    }
+}
+
+impl CubeMode {
+    /// 1 falling down, -1 falling up.
+    pub fn gravity_sign(&self) -> f32 {
+        if self.flipped {-1.} else {1.}
+    }
+
+    pub fn size(&self) -> f32 {
+        if self.mini {MINI_SCALE} else {1.}
+    }
+}
+
+/// Pads and orbs the cube still overlaps, they fire once per touch.
+#[derive(Component, Default)]
+pub struct UsedObjects(pub EntityHashSet);
+
+/// Object under a Tiled object collider.
+#[derive(Component, Clone, Copy)]
+pub struct GdObjectCollider(pub Entity);
+
+pub type GdObjects<'w, 's> = Query<'w, 's, (
+    Option<&'static JumpPad>,
+    Option<&'static JumpOrb>,
+    Option<&'static GravityPortal>,
+    Option<&'static SpeedPortal>,
+    Option<&'static SizePortal>,
+    Option<&'static ShipPortal>,
+)>;
+
+/// Whether `entity` carries any of the objects above.
+pub fn is_gd_object(objects: &GdObjects, entity: Entity) -> bool {
+    objects.get(entity).is_ok_and(|(a, b, c, d, e, f)| {
+        a.is_some() || b.is_some() || c.is_some() || d.is_some() || e.is_some() || f.is_some()
+    })
+}
+
+/// Applies what the cube touches this frame, returns the velocity kick of pads and orbs.
+pub fn touch_objects(
+    touching: impl Iterator<Item = Entity>,
+    objects: &GdObjects,
+    mode: &mut CubeMode,
+    used: &mut UsedObjects,
+    jump_pressed: bool,
+    on_ground: bool,
+) -> Option<f32> {
+    let mut kick = None;
+    let mut still = EntityHashSet::default();
+    for object in touching {
+        let Ok((pad, orb, gravity, speed, size, ship)) = objects.get(object) else {continue;};
+        still.insert(object);
+        if let Some(gravity) = gravity {
+            mode.flipped = gravity.flipped;
+        }
+        if let Some(speed) = speed {
+            mode.speed = speed.speed;
+        }
+        if let Some(size) = size {
+            mode.mini = size.mini;
+        }
+        if let Some(ship) = ship {
+            mode.ship = ship.ship;
+        }
+        if used.0.contains(&object) {continue;}
+        if let Some(pad) = pad {
+            kick = Some(pad.strength);
+            used.0.insert(object);
+        } else if let Some(orb) = orb && jump_pressed && !on_ground {
+            kick = Some(orb.strength);
+            used.0.insert(object);
+        }
+    }
+    used.0.retain(|e| still.contains(e));
+    kick.map(|k| k * mode.gravity_sign() * mode.size().sqrt())
+}
+
+
+pub const MINI_SCALE: f32 = 0.5;
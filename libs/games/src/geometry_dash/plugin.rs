@@ -4,18 +4,20 @@ use avian2d:: math::{PI, Vector};
 use bevy_asset_loader::asset_collection::AssetCollection;
 use global_music::plugin::NewBgMusic;
 
-use crate::{global_music, hints::{self, HintAssets, KeyHint}, prelude::*};
+use super::progress::{self, GdPractice, GdProgress, GdSave, GdSoundtrack};
+use super::objects::{CubeMode, GdObjectCollider, GdObjects, GravityPortal, JumpOrb, JumpPad, ShipPortal, SizePortal, SpeedPortal, UsedObjects, is_gd_object, touch_objects};
+use crate::{global_music, hints::{self, HintAssets, KeyHint}, phase::{PhaseLayer, Phased, Phases}, platformer_controller::ground::{GroundDetector, Grounded, update_ground}, prelude::*, replay::GameplayStarted, tick_input::TickInput, ghost::{GhostRecorder, GhostRun, spawn_ghost}, transition::{RequestTransition, TransitionStyle}};
 pub struct GeometryDashPlugin;
 
-const STATE: AppState = AppState::Geometry;
+pub(super) const STATE: AppState = AppState::Geometry;
 const NEXT_STATE: AppState = AppState::Platformer;
 
-const GRAVITY_SCALE : f32 = 30.;
+pub(super) const GRAVITY_SCALE : f32 = 30.;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, SubStates)]
 #[source(AppState = STATE)]
 #[states(scoped_entities)]
-enum LocalState {
+pub(super) enum LocalState {
     #[default]
     InitialAnim,
     Game,
@@ -28,15 +30,25 @@ pub struct Cube;
 
 #[derive(Component, Default, Reflect)]
 #[reflect(Component, Default)]
-pub struct SpawnPoint;
+pub struct SpawnPoint {
+    /// x distance the cube covers to the end, 0 hides the percent.
+    pub level_length: f32,
+}
 
 #[derive(Component, Default, Reflect)]
 #[reflect(Component, Default)]
 pub struct CameraCenter;
 
+/// On a Tiled tile layer, touching it sends the cube down a lane, backwards, into `phase`.
+#[derive(Component, Default, Reflect, Clone)]
+#[reflect(Component, Default)]
+pub struct PhasePortal {
+    pub phase: u32,
+}
+
 #[derive(Resource)]
 pub struct PlayerEntity {
-    entity: Entity,
+    pub(super) entity: Entity,
 }
 
 #[derive(Resource)]
@@ -46,7 +58,7 @@ pub struct FollowerEntity {
 
 #[derive(Resource)]
 pub struct IsLeft {
-    is: bool,
+    pub is: bool,
 }
 
 impl Plugin for GeometryDashPlugin {
@@ -54,6 +66,13 @@ impl Plugin for GeometryDashPlugin {
         app
             .register_type::<CameraCenter>()
             .register_type::<SpawnPoint>()
+            .register_type::<PhasePortal>()
+            .register_type::<JumpPad>()
+            .register_type::<JumpOrb>()
+            .register_type::<GravityPortal>()
+            .register_type::<SpeedPortal>()
+            .register_type::<SizePortal>()
+            .register_type::<ShipPortal>()
             .add_sub_state::<LocalState>()
             .add_observer(spawnpoint_handler)
             .add_observer(camera_handler)
@@ -61,8 +80,15 @@ impl Plugin for GeometryDashPlugin {
             .add_systems(OnEnter(STATE), setup)
             .add_systems(Update, tick_transition.run_if(in_state(LocalState::InitialAnim)))
             .add_systems(OnEnter(LocalState::Game), begin_game)
-            .add_systems(Update, (follow, controller).chain().run_if(in_state(LocalState::Game)))
-            .add_systems(Update, defeat.run_if(in_state(LocalState::Defeat)))
+            .add_systems(FixedUpdate, (follow, use_objects, apply_cube_mode, controller, apply_cube_phase, progress::track_progress).chain().after(update_ground).run_if(in_state(LocalState::Game)))
+            .add_systems(Update, progress::practice_keys.run_if(in_state(LocalState::Game)))
+            .add_systems(Update, (progress::update_progress_bar, progress::sync_soundtrack).run_if(in_state(STATE)))
+            .add_systems(OnEnter(LocalState::Defeat), (
+                progress::respawn.run_if(resource_exists::<GdPractice>),
+                progress::record_progress.run_if(not(resource_exists::<GdPractice>)),
+            ))
+            .add_systems(OnEnter(LocalState::Win), (progress::complete_level, progress::record_progress).chain())
+            .add_systems(Update, defeat.run_if(in_state(LocalState::Defeat).and(not(resource_exists::<GdPractice>))))
             .add_systems(Update, win.run_if(in_state(LocalState::Win)))
             .add_systems(OnExit(STATE), cleanup)
         ;
@@ -78,7 +104,9 @@ pub struct GeometryDashAssets {
     #[asset(texture_atlas_layout(tile_size_x = 16, tile_size_y = 16, columns = 1, rows = 2))]
     cube_layout: Handle<TextureAtlasLayout>,
     #[asset(path = "sounds/170144__timgormly__8-bit-explosion2.mp3")]
-    explosion: Handle<AudioSource>,
+    pub explosion: Handle<AudioSource>,
+    #[asset(path = "sounds/novel/Overhaul.mp3")]
+    pub soundtrack: Handle<AudioSource>,
     #[asset(path = "sounds/novel/silence.mp3")]
     bg_music: Handle<AudioSource>,
 }
@@ -89,6 +117,7 @@ fn setup(
     mut state: ResMut<LastState>,
     hint_assets: Res<HintAssets>,
     cam: Query<Entity, With<WorldCamera>>,
+    save: Option<Res<GdSave>>,
 ) {
     state.state = STATE;
     cmd.spawn((
@@ -112,16 +141,32 @@ fn setup(
     cmd.insert_resource(FollowerEntity {entity: Entity::PLACEHOLDER});
     cmd.insert_resource(IsLeft {is: false});
     cmd.insert_resource(FunnyTimer(None));
+    cmd.insert_resource(Phases::new(FIRST_PHASE_BIT, CollisionLayer::Default, false));
+    cmd.insert_resource(GdProgress::default());
+    if save.is_none() {
+        cmd.insert_resource(GdSave::load());
+    }
+    // follows the level time in `sync_soundtrack`
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        GdSoundtrack,
+        AudioPlayer(assets.soundtrack.clone()),
+        PlaybackSettings::LOOP.paused(),
+    ));
+    progress::spawn_progress_bar(&mut cmd, STATE);
 }
 fn begin_game (
-    mut state: ResMut<NextState<LocalState>>
+    mut started: MessageWriter<GameplayStarted>,
 ) {
-    state.set(LocalState::Game);
+    started.write(GameplayStarted);
 }
    
 fn tick_transition(
+    player: Res<PlayerEntity>,
     mut state: ResMut<NextState<LocalState>>
 ){
+    // the map spawns the cube once it is loaded, however many ticks that takes
+    if player.entity == Entity::PLACEHOLDER {return;}
     state.set(LocalState::Game);
 }
 
@@ -130,7 +175,6 @@ pub enum CastDir {
     X,
     Y,
     NEGX,
-    NEGY,
 }
 
 #[derive(Component)]
@@ -139,11 +183,13 @@ pub struct Follower;
 fn spawnpoint_handler(
     event: On<Add, SpawnPoint>,
     mut cmd: Commands,
-    spawnpoint_transform_q: Query<&Transform, With<SpawnPoint>>,
+    spawnpoint_transform_q: Query<(&Transform, &SpawnPoint)>,
     assets: Res<GeometryDashAssets>,
     mut player_entity: ResMut<PlayerEntity>,
     mut follower_entity: ResMut<FollowerEntity>,
-    mut proj: Query<&mut Projection, With<WorldCamera>>
+    mut proj: Query<&mut Projection, With<WorldCamera>>,
+    phases: Res<Phases>,
+    mut progress: ResMut<GdProgress>,
 ) {
     match &mut *proj.single_mut().expect("nocam") {
         Projection::Orthographic(proj) => {
@@ -153,13 +199,17 @@ fn spawnpoint_handler(
     }
     proj.single_mut().expect("no cam");
     let e = event.entity;
-    let transform = spawnpoint_transform_q.get(e).expect("no spawnpoint").clone();
-
-    let collider = Collider::rectangle(16.0, 16.0);
-    let layers = CollisionLayers::new(
-        CollisionLayer::Default,
-        [CollisionLayer::Yellow, CollisionLayer::Aboba, CollisionLayer::End],
-    );
+    let (transform, spawnpoint) = spawnpoint_transform_q.get(e).expect("no spawnpoint");
+    let transform = transform.clone();
+    progress.length = spawnpoint.level_length;
+
+    let (collider, caster_shape) = cube_shapes(1.);
+    let phased = Phased {
+        phase: 0,
+        base: CollisionLayers::new(CollisionLayer::Default, [CollisionLayer::Aboba, CollisionLayer::End]),
+        blocker: None,
+    };
+    let ground = phases.mask(phased.phase);
 
     player_entity.entity = cmd.spawn((
         DespawnOnExit(STATE),
@@ -177,13 +227,34 @@ fn spawnpoint_handler(
         Cube,
         collider.clone(),
         RigidBody::Dynamic,
+        // gameplay runs in `FixedUpdate`
+        TransformInterpolation,
         GravityScale(GRAVITY_SCALE),
-        layers,
+        phased.layers(&phases),
+        phased,
+        CubeMode::default(),
+        UsedObjects::default(),
+        GhostRecorder::default(),
         CollisionEventsEnabled,
+        // the cube spins in the air, its ground check should not
+        GroundDetector {
+            shape: Some(caster_shape.clone()),
+            filter: Some(SpatialQueryFilter::from_mask(ground)),
+            distance: 2.,
+            max_slope: PI / 2.,
+            up: Dir2::Y,
+        },
     )).id();
-    
-    let mut caster_shape = collider.clone();
-    caster_shape.set_scale(Vector::ONE * Vector::new(0.9, 0.9), 10);
+    if let Some(ghost) = GhostRun::load(progress::GD_GHOST_PATH) {
+        spawn_ghost(&mut cmd, ghost, Sprite {
+            image: assets.cube.clone(),
+            texture_atlas: Some(TextureAtlas {
+                layout: assets.cube_layout.clone(),
+                index: 0,
+            }),
+            ..default()
+        }, STATE);
+    }
 
     follower_entity.entity = cmd.spawn((
         DespawnOnExit(STATE),
@@ -194,7 +265,7 @@ fn spawnpoint_handler(
                 ShapeCaster::new(caster_shape.clone(), Vector::ZERO, 0.0, Dir2::X)
                     .with_max_distance(10.)
                     .with_ignore_self(true)
-                    .with_query_filter(SpatialQueryFilter::from_mask([CollisionLayer::Yellow]))
+                    .with_query_filter(SpatialQueryFilter::from_mask(ground))
                     .with_max_hits(1),
                 CastDir::X,
             ),
@@ -202,7 +273,7 @@ fn spawnpoint_handler(
                 ShapeCaster::new(caster_shape.clone(), Vector::ZERO, 0.0, Dir2::NEG_X)
                     .with_max_distance(10.)
                     .with_ignore_self(true)
-                    .with_query_filter(SpatialQueryFilter::from_mask([CollisionLayer::Yellow]))
+                    .with_query_filter(SpatialQueryFilter::from_mask(ground))
                     .with_max_hits(1),
                 CastDir::NEGX,
             ),
@@ -210,30 +281,21 @@ fn spawnpoint_handler(
                 ShapeCaster::new(caster_shape.clone(), Vector::ZERO, 0.0, Dir2::Y)
                     .with_max_distance(10.)
                     .with_ignore_self(true)
-                    .with_query_filter(SpatialQueryFilter::from_mask([CollisionLayer::Yellow]))
+                    .with_query_filter(SpatialQueryFilter::from_mask(ground))
                     .with_max_hits(1),
                 CastDir::Y,
             ),
-            (
-                ShapeCaster::new(caster_shape.clone(), Vector::ZERO, 0.0, Dir2::NEG_Y)
-                    .with_max_distance(10.)
-                    .with_ignore_self(true)
-                    .with_query_filter(SpatialQueryFilter::from_mask([CollisionLayer::Yellow]))
-                    .with_max_hits(1),
-                CastDir::NEGY,
-            ),
         ],
     )).id();
 }
 
-#[derive(Component)]
-struct Yellow;
-
-#[derive(Component)]
-struct White;
-
-#[derive(Component)]
-struct Aboba;
+/// Body and wall check shapes of a cube scaled by `size`.
+fn cube_shapes(size: f32) -> (Collider, Collider) {
+    let collider = Collider::rectangle(16.0 * size, 16.0 * size);
+    let mut caster_shape = collider.clone();
+    caster_shape.set_scale(Vector::ONE * Vector::new(0.9, 0.9), 10);
+    (collider, caster_shape)
+}
 
 #[derive(Component)]
 struct End;
@@ -242,72 +304,61 @@ struct End;
 enum CollisionLayer {
     #[default]
     Default,
-    Yellow,
-    White,
     Aboba,
     End,
+    Object,
 }
 
+/// Phases go right after `CollisionLayer`.
+const FIRST_PHASE_BIT: u32 = 4;
+/// Cube sprites, one per phase, later phases reuse the last one tinted.
+const CUBE_FRAMES: usize = 2;
+
 fn on_collider_spawned(
     collider_created: On<TiledEvent<ColliderCreated>>,
     assets: Res<Assets<TiledMapAsset>>,
+    parents: Query<&ChildOf>,
+    phase_layers: Query<(), With<PhaseLayer>>,
+    portals: Query<&PhasePortal>,
+    objects: GdObjects,
     mut commands: Commands,
     state: Res<State<AppState>>,
 ) {
     if state.get() != &STATE {
         return;
     }
-    let Some(layer) = collider_created.event().get_layer(&assets) else {return;};
-    if layer.name == "yellow_front" || layer.name == "yellow_back" {
-        let layers = CollisionLayers::new(
-        CollisionLayer::Yellow,
-        [CollisionLayer::Yellow, CollisionLayer::Default],
-        );
-        commands.entity(collider_created.event().origin).insert((
-            Yellow,
-            RigidBody::Static,
-            layers,
-        ));
-    }
-    if layer.name == "white_front" || layer.name == "white_back" {
-        let layers = CollisionLayers::new(
-        CollisionLayer::White,
-        [CollisionLayer::White, CollisionLayer::Default],
-        );
-        commands.entity(collider_created.event().origin).insert((
-            White,
-            RigidBody::Static,
-            layers,
-        ));
+    let origin = collider_created.event().origin;
+    let Ok(parent) = parents.get(origin).map(|p| p.parent()) else {return;};
+    // layers come from `Phases`
+    if phase_layers.contains(parent) {
+        commands.entity(origin).insert(RigidBody::Static);
     }
-    if layer.name == "white_aboba" {
-        let layers = CollisionLayers::new(
-        CollisionLayer::Aboba,
-        [CollisionLayer::Default],
-        );
-        commands.entity(collider_created.event().origin).insert((
-            Aboba,
-            RigidBody::Static,
-            layers,
+    // only found with spatial queries in `use_objects`
+    if is_gd_object(&objects, parent) {
+        commands.entity(origin).insert((
+            GdObjectCollider(parent),
+            Sensor,
+            CollisionLayers::new(CollisionLayer::Object, LayerMask::NONE),
         ));
     }
-    if layer.name == "yellow_aboba" {
+    if let Ok(portal) = portals.get(parent) {
         let layers = CollisionLayers::new(
         CollisionLayer::Aboba,
         [CollisionLayer::Default],
         );
-        commands.entity(collider_created.event().origin).insert((
-            Aboba,
+        commands.entity(origin).insert((
+            portal.clone(),
             RigidBody::Static,
             layers,
         ));
     }
+    let Some(layer) = collider_created.event().get_layer(&assets) else {return;};
     if layer.name == "white_end" {
         let layers = CollisionLayers::new(
         CollisionLayer::End,
         [CollisionLayer::Default],
         );
-        commands.entity(collider_created.event().origin).insert((
+        commands.entity(origin).insert((
             End,
             RigidBody::Static,
             layers,
@@ -327,6 +378,9 @@ fn camera_handler(
 }
 
 const MS : f32 = 90.0;
+/// Upward acceleration of the ship while jump is held, on top of gravity.
+const SHIP_LIFT : f32 = 900.0;
+const SHIP_MAX_SPEED : f32 = 150.0;
 
 fn follow(
     mut follower_transform_q: Query<&mut Transform, (With<Follower>, Without<Cube>)>,
@@ -337,92 +391,138 @@ fn follow(
     follower_transform.translation = player_transform.translation;
 }
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct FunnyTimer(Option<f32>);
 
-fn controller(
-    mut cube_vel_q: Query<&mut LinearVelocity, With<Cube>>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+fn use_objects(
+    spatial: SpatialQuery,
+    input: Res<TickInput>,
+    object_colliders: Query<&GdObjectCollider>,
+    objects: GdObjects,
+    mut cube_q: Query<(&mut CubeMode, &mut UsedObjects, &mut LinearVelocity, &Grounded, &Position, &Rotation, &Collider), With<Cube>>,
+) {
+    let Ok((mut mode, mut used, mut vel, grounded, pos, rot, collider)) = cube_q.single_mut() else {return;};
+    let filter = SpatialQueryFilter::from_mask(CollisionLayer::Object);
+    let touching = spatial.shape_intersections(collider, pos.0, rot.as_radians(), &filter)
+        .into_iter()
+        .filter_map(|e| object_colliders.get(e).ok().map(|o| o.0));
+    let kick = touch_objects(touching, &objects, &mut mode, &mut used, input.just_pressed(KeyCode::Space), grounded.on_ground());
+    if let Some(kick) = kick {
+        vel.y = kick;
+    }
+}
+
+/// Gravity, size and wall checks of the cube follow its `CubeMode`.
+fn apply_cube_mode(
+    mut cmd: Commands,
+    mut cube_q: Query<(Entity, &CubeMode, &mut GravityScale, &mut GroundDetector, &mut Sprite), (With<Cube>, Changed<CubeMode>)>,
+    mut casters: Query<(&mut ShapeCaster, &CastDir)>,
+) {
+    let Ok((entity, mode, mut gravity, mut detector, mut sprite)) = cube_q.single_mut() else {return;};
+    gravity.0 = GRAVITY_SCALE * mode.gravity_sign();
+    detector.up = if mode.flipped {Dir2::NEG_Y} else {Dir2::Y};
+    sprite.flip_y = mode.flipped;
+    // the ceiling check looks away from the ground
+    for (mut caster, dir) in casters.iter_mut() {
+        if let CastDir::Y = dir {
+            caster.direction = detector.up;
+        }
+    }
+    let size = Vec2::splat(16. * mode.size());
+    if sprite.custom_size.unwrap_or(Vec2::splat(16.)) != size {
+        let (body, caster_shape) = cube_shapes(mode.size());
+        cmd.entity(entity).insert(body);
+        detector.shape = Some(caster_shape.clone());
+        sprite.custom_size = Some(size);
+        for (mut caster, _) in casters.iter_mut() {
+            caster.shape = caster_shape.clone();
+        }
+    }
+}
+
+/// Ground and wall checks and the sprite of the cube follow its phase.
+fn apply_cube_phase(
+    phases: Res<Phases>,
+    mut cube_q: Query<(&Phased, &mut GroundDetector, &mut Sprite), (With<Cube>, Changed<Phased>)>,
+    mut casters: Query<&mut ShapeCaster>,
+) {
+    let Ok((phased, mut detector, mut sprite)) = cube_q.single_mut() else {return;};
+    let phase = phased.phase;
+    let frame = (phase as usize).min(CUBE_FRAMES - 1);
+    sprite.texture_atlas.as_mut().unwrap().index = frame;
+    sprite.color = if phase as usize >= CUBE_FRAMES {phases.color(phase)} else {Color::WHITE};
+    let ground = SpatialQueryFilter::from_mask(phases.mask(phase));
+    detector.filter = Some(ground.clone());
+    for mut caster in casters.iter_mut() {
+        caster.query_filter = ground.clone();
+    }
+}
+
+pub(super) fn controller(
+    mut cube_vel_q: Query<(&mut LinearVelocity, &CubeMode), With<Cube>>,
+    input: Res<TickInput>,
     shapecast_q: Query<(&mut ShapeCaster, &ShapeHits, &CastDir)>,
     end_q: Query<&End>,
     mut state: ResMut<NextState<LocalState>>,
     player_entity: Res<PlayerEntity>,
-    mut cube_pos_q: Query<(&mut Position, &mut CollisionLayers), With<Cube>>,
+    mut cube_pos_q: Query<(&mut Position, &mut Phased, &Grounded), With<Cube>>,
     mut is_left: ResMut<IsLeft>,
-    (time, mut t, mut inair):(Res<Time>, Local<Duration>, Local<bool>,),
+    (time, mut t, mut inair, practice):(Res<Time>, Local<Duration>, Local<bool>, Option<Res<GdPractice>>),
     mut just_jumped: Local<bool>,
     mut cube_transform_q: Query<&mut Transform, With<Cube>>,
-    aboba: Query<&Aboba>,
+    portals: Query<&PhasePortal>,
     mut collision_reader: MessageReader<CollisionStart>,
     mut funny_timer: ResMut<FunnyTimer>,
-    mut sprite: Query<&mut Sprite, With<Cube>>
 ) {
-    let mut on_ground = false;
-    let mut hit_aboba = false;
-    for (_casters, hits, castdir) in shapecast_q.iter() {
+    let mut portal = None;
+    let on_ground = cube_pos_q.single().is_ok_and(|(.., grounded)| grounded.on_ground());
+    let ship = cube_vel_q.single().is_ok_and(|(_, mode)| mode.ship);
+    if on_ground && !ship {
+        if *inair {
+            // println!("{:?}", (time.elapsed() - *t).as_millis());
+            *inair = false;
+        }
+        let mut cube_t = cube_transform_q.single_mut().expect("no cube(");
+        let rotation_angle = cube_t.rotation.to_euler(EulerRot::XYZ).2;
+        let rot = (rotation_angle / PI * 2.0).round() * PI / 2.0;
+        cube_t.rotation = Quat::from_rotation_z(rot);
+    }
+    let grace = practice.is_some_and(|p| p.grace > 0.);
+    for (_casters, hits, _castdir) in shapecast_q.iter() {
+        // the ship slides along ceilings
+        if grace || (ship && matches!(_castdir, CastDir::Y)) {continue;}
         for hit in hits.iter() {
-            match castdir {
-                &CastDir::NEGY => {
-                    if hit.entity != player_entity.entity && hit.distance < 2. {
-                        // println!("FLOOR {} {} {:?} {:?} {:?}", hit.distance, hit.entity, castdir, hit.normal1, hit.normal2);
-                        on_ground = true;
-                        if *inair {
-                            // println!("{:?}", (time.elapsed() - *t).as_millis());
-                            *inair = false;
-                        }
-                        let mut cube_t = cube_transform_q.single_mut().expect("no cube(");
-                        let rotation_angle = cube_t.rotation.to_euler(EulerRot::XYZ).2;
-                        let rot = (rotation_angle / PI * 2.0).round() * PI / 2.0;
-                        cube_t.rotation = Quat::from_rotation_z(rot);
-                    }
-                },
-                _ => {
-                    if hit.entity != player_entity.entity && hit.distance < 0.9 && hit.distance > 0.1 {
-                        // println!("WALLS {} {} {:?} {:?} {:?}", hit.distance, hit.entity, castdir, hit.normal1, hit.normal2);
-                        state.set(LocalState::Defeat);
-                    }
-                }
+            if hit.entity != player_entity.entity && hit.distance < 0.9 && hit.distance > 0.1 {
+                // println!("WALLS {} {} {:?} {:?} {:?}", hit.distance, hit.entity, _castdir, hit.normal1, hit.normal2);
+                state.set(LocalState::Defeat);
             }
         }
     }
+    let current = cube_pos_q.single().map(|(_, phased, ..)| phased.phase).ok();
     for event in collision_reader.read() {
-        if let Ok(_) = aboba.get(event.collider2) {
-            hit_aboba = true;
+        // a portal into the phase the cube is already in does nothing, portals of different phases can overlap
+        if let Ok(p) = portals.get(event.collider2) && Some(p.phase) != current {
+            portal = Some(p.phase);
             break;
         } else if let Ok(_) = end_q.get(event.collider2) {
             state.set(LocalState::Win);
             return;
         }
-        if let Ok(_) = aboba.get(event.collider1) {
-            hit_aboba = true;
+        if let Ok(p) = portals.get(event.collider1) && Some(p.phase) != current {
+            portal = Some(p.phase);
             break;
         } else if let Ok(_) = end_q.get(event.collider1) {
             state.set(LocalState::Win);
             return;
         }
     }
-    if hit_aboba && funny_timer.0.is_none() {
+    if let Some(phase) = portal && funny_timer.0.is_none() {
         funny_timer.0 = Some(0.);
-        let (mut pos, mut layers) = cube_pos_q.single_mut().expect("no cube");
-        let mut sprite = sprite.single_mut().expect("no cube");
-        let mut to_white = true;
+        let (mut pos, mut phased, _) = cube_pos_q.single_mut().expect("no cube");
         is_left.is = !is_left.is;
         *pos = Position::from_xy(pos.x, pos.y + -1. * 6. * 16.);
-        if layers.filters == LayerMask::from([CollisionLayer::Yellow, CollisionLayer::Aboba, CollisionLayer::End]) {
-            sprite.texture_atlas.as_mut().unwrap().index = 1;
-            layers.filters = LayerMask::from([CollisionLayer::White, CollisionLayer::Aboba, CollisionLayer::End]);
-        } else {
-            sprite.texture_atlas.as_mut().unwrap().index = 0;
-            layers.filters = LayerMask::from([CollisionLayer::Yellow, CollisionLayer::End]);
-            to_white = false;
-        }
-        for (mut caster, _hits, _) in shapecast_q {
-            if to_white {
-                *caster = caster.clone().with_query_filter(SpatialQueryFilter::from_mask([CollisionLayer::White]));
-            } else {
-                *caster = caster.clone().with_query_filter(SpatialQueryFilter::from_mask([CollisionLayer::Yellow]));
-            }
-        }
+        // the rest follows in `apply_phase` and `apply_cube_phase`
+        phased.phase = phase;
     }
 
     if funny_timer.0.is_some() {
@@ -433,14 +533,24 @@ fn controller(
         }
     }
 
-    let mut vel = cube_vel_q.single_mut().expect("no cube(");
+    let (mut vel, mode) = cube_vel_q.single_mut().expect("no cube(");
+    let sign = mode.gravity_sign();
     if !is_left.is {
-        vel.x = MS;
+        vel.x = MS * mode.speed;
     } else {
-        vel.x = -MS;
+        vel.x = -MS * mode.speed;
     }
-    if keyboard_input.pressed(KeyCode::Space) && on_ground {
-        vel.y = 160.;
+    if mode.ship {
+        if input.pressed(KeyCode::Space) {
+            vel.y += SHIP_LIFT * sign * time.delta_secs();
+        }
+        vel.y = vel.y.clamp(-SHIP_MAX_SPEED, SHIP_MAX_SPEED);
+        let mut cube_t = cube_transform_q.single_mut().expect("no cube(");
+        cube_t.rotation = Quat::from_rotation_z((vel.y / vel.x.abs().max(1.)).atan() * vel.x.signum());
+        return;
+    }
+    if input.pressed(KeyCode::Space) && on_ground {
+        vel.y = 160. * sign * mode.size().sqrt();
         *just_jumped = true;
         *t = time.elapsed();
     }
@@ -448,16 +558,16 @@ fn controller(
         *just_jumped = false;
         *inair = true;
     }
-    const ROT_PER_MS: f32 = PI / 1090.;
+    const ROT_PER_SEC: f32 = PI / 1.09;
     if !on_ground {
         let mut cube_t = cube_transform_q.single_mut().expect("no cube(");
-        cube_t.rotate_z(ROT_PER_MS * time.delta().as_millis() as f32);
+        cube_t.rotate_z(ROT_PER_SEC * sign * time.delta_secs());
     }
 }
 
 fn defeat(
     mut cmd: Commands,
-    mut screenshot: ResMut<LastScreenshot>,
+    mut transitions: MessageWriter<RequestTransition>,
     assets: Res<GeometryDashAssets>,
 ){
     cmd.spawn((
@@ -468,17 +578,13 @@ fn defeat(
             ..default()
         },
     ));
-    if screenshot.awaiting == false {
-        cmd.spawn(bevy::render::view::screenshot::Screenshot::primary_window())
-            .observe(await_screenshot_and_translate(AppState::Defeat));
-        screenshot.awaiting = true;
-    }
+    transitions.write(RequestTransition::capture(AppState::Defeat));
 }
 
 fn win(
-    mut state: ResMut<NextState<AppState>>,
+    mut transitions: MessageWriter<RequestTransition>,
 ) {
-    state.set(NEXT_STATE);
+    transitions.write(RequestTransition::new(NEXT_STATE, TransitionStyle::Pixelate));
 }
 
 fn cleanup(
@@ -496,4 +602,7 @@ fn cleanup(
     cmd.remove_resource::<PlayerEntity>();
     cmd.remove_resource::<IsLeft>();
     cmd.remove_resource::<FunnyTimer>();
+    cmd.remove_resource::<Phases>();
+    cmd.remove_resource::<GdProgress>();
+    cmd.remove_resource::<GdPractice>();
 }
\ No newline at end of file
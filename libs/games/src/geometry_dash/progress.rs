@@ -0,0 +1,329 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::{objects::CubeMode, plugin::{Cube, GeometryDashAssets, IsLeft, LocalState, STATE}};
+use crate::{ghost::GhostRecorder, phase::Phased, prelude::*, saves};
+
+
+/// Fixed ticks played and x distance covered, the soundtrack follows the ticks.
+#[derive(Resource, Default)]
+pub struct GdProgress {
+    pub ticks: u32,
+    pub distance: f32,
+    /// x distance from the spawn point to the end, from `SpawnPoint::level_length`.
+    pub length: f32,
+    /// Where the level starts, practice respawns here without checkpoints.
+    pub start: Option<Checkpoint>,
+    /// Practice was on at some point, the run does not count.
+    pub practiced: bool,
+}
+
+impl GdProgress {
+    pub fn percent(&self) -> f32 {
+        if self.length <= 0. {return 0.;}
+        (self.distance / self.length * 100.).min(100.)
+    }
+
+    /// Where the soundtrack should be, level time stays on the beat whatever speed portals did to the distance.
+    pub fn music_time(&self, timestep: Duration) -> f32 {
+        self.ticks as f32 * timestep.as_secs_f32()
+    }
+}
+
+/// Best progress, kept between runs. Practice runs do not count.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct GdSave {
+    pub best_percent: f32,
+}
+
+impl GdSave {
+    pub fn load() -> Self {
+        saves::load(GD_SAVE_PATH)
+    }
+
+    pub fn store(&self) {
+        saves::store(GD_SAVE_PATH, self);
+    }
+}
+
+/// Everything needed to put the cube back where it was.
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub rotation: Quat,
+    pub is_left: bool,
+    pub layers: CollisionLayers,
+    pub phase: u32,
+    pub mode: CubeMode,
+    pub ticks: u32,
+    pub distance: f32,
+    marker: Option<Entity>,
+}
+
+/// Exists while practicing: deaths respawn at the last checkpoint instead of losing.
+#[derive(Resource, Default)]
+pub struct GdPractice {
+    pub checkpoints: Vec<Checkpoint>,
+    /// Seconds after a respawn in which walls do not kill, the wall casts are still stale.
+    pub grace: f32,
+}
+
+#[derive(Component)]
+pub struct GdSoundtrack;
+
+#[derive(Component)]
+pub(super) struct ProgressFill;
+
+#[derive(Component)]
+pub(super) struct ProgressText;
+
+type CubeState = (
+    &'static Position,
+    &'static Transform,
+    &'static LinearVelocity,
+    &'static CollisionLayers,
+    &'static Phased,
+    &'static CubeMode,
+);
+
+fn capture(
+    (position, transform, velocity, layers, phased, mode): (&Position, &Transform, &LinearVelocity, &CollisionLayers, &Phased, &CubeMode),
+    is_left: &IsLeft,
+    progress: &GdProgress,
+) -> Checkpoint {
+    Checkpoint {
+        position: position.0,
+        velocity: velocity.0,
+        rotation: transform.rotation,
+        is_left: is_left.is,
+        layers: *layers,
+        phase: phased.phase,
+        mode: mode.clone(),
+        ticks: progress.ticks,
+        distance: progress.distance,
+        marker: None,
+    }
+}
+
+pub(super) fn spawn_progress_bar(cmd: &mut Commands, state: AppState) {
+    cmd.spawn((
+        DespawnOnExit(state),
+        Node {
+            top: Val::Px(8.0),
+            width: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        children![
+            (
+                Node {
+                    width: Val::Percent(40.0),
+                    height: Val::Px(8.0),
+                    ..default()
+                },
+                BackgroundColor(Color::srgba(0., 0., 0., 0.5)),
+                children![(
+                    ProgressFill,
+                    Node {
+                        width: Val::Percent(0.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.3, 1., 0.3)),
+                )],
+            ),
+            (
+                ProgressText,
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor::WHITE,
+            ),
+        ],
+    ));
+}
+
+pub(super) fn track_progress(
+    time: Res<Time>,
+    mut progress: ResMut<GdProgress>,
+    is_left: Res<IsLeft>,
+    cube_q: Query<CubeState, With<Cube>>,
+    mut ghost: Query<&mut GhostRecorder, With<Cube>>,
+) {
+    let Ok(cube) = cube_q.single() else {return;};
+    if progress.start.is_none() {
+        progress.start = Some(capture(cube, &is_left, &progress));
+    }
+    let before = progress.percent();
+    progress.ticks += 1;
+    progress.distance += cube.2.x.abs() * time.delta_secs();
+    // the ghost is compared every few percent
+    if (progress.percent() / GHOST_CHECKPOINT_PERCENT).floor() > (before / GHOST_CHECKPOINT_PERCENT).floor()
+        && let Ok(mut ghost) = ghost.single_mut() {
+        ghost.checkpoint();
+    }
+}
+
+pub(super) fn update_progress_bar(
+    progress: Res<GdProgress>,
+    save: Res<GdSave>,
+    practice: Option<Res<GdPractice>>,
+    mut fill: Query<&mut Node, With<ProgressFill>>,
+    mut text: Query<&mut Text, With<ProgressText>>,
+) {
+    let percent = progress.percent();
+    if let Ok(mut fill) = fill.single_mut() {
+        fill.width = Val::Percent(percent);
+    }
+    if let Ok(mut text) = text.single_mut() {
+        text.0 = match practice {
+            Some(p) => format!("{percent:.0}%  practice, {} checkpoints", p.checkpoints.len()),
+            None => format!("{percent:.0}%  best {:.0}%", save.best_percent),
+        };
+    }
+}
+
+/// The soundtrack plays only in game and seeks when it drifts from `GdProgress::music_time`.
+pub(super) fn sync_soundtrack(
+    progress: Res<GdProgress>,
+    fixed: Res<Time<Fixed>>,
+    in_game: Res<State<LocalState>>,
+    sinks: Query<&AudioSink, With<GdSoundtrack>>,
+    mut warned: Local<bool>,
+) {
+    let Ok(sink) = sinks.single() else {return;};
+    if in_game.get() != &LocalState::Game {
+        sink.pause();
+        return;
+    }
+    if sink.is_paused() {
+        sink.play();
+    }
+    let time = progress.music_time(fixed.timestep());
+    if (sink.position().as_secs_f32() - time).abs() <= SOUNDTRACK_DRIFT {return;}
+    if let Err(e) = sink.try_seek(Duration::from_secs_f32(time)) && !*warned {
+        warn!("Could not seek the GD soundtrack: {e:?}");
+        *warned = true;
+    }
+}
+
+/// P toggles practice, Z drops a checkpoint, X takes the last one back.
+pub(super) fn practice_keys(
+    mut cmd: Commands,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    practice: Option<ResMut<GdPractice>>,
+    mut progress: ResMut<GdProgress>,
+    is_left: Res<IsLeft>,
+    cube_q: Query<CubeState, With<Cube>>,
+) {
+    if keys.just_pressed(KeyCode::KeyP) {
+        match &practice {
+            Some(practice) => {
+                for marker in practice.checkpoints.iter().filter_map(|c| c.marker) {
+                    cmd.entity(marker).despawn();
+                }
+                cmd.remove_resource::<GdPractice>();
+            }
+            None => {
+                progress.practiced = true;
+                cmd.insert_resource(GdPractice::default());
+            }
+        }
+        return;
+    }
+    let Some(mut practice) = practice else {return;};
+    practice.grace = (practice.grace - time.delta_secs()).max(0.);
+    if keys.just_pressed(KeyCode::KeyZ) {
+        let Ok(cube) = cube_q.single() else {return;};
+        let mut checkpoint = capture(cube, &is_left, &progress);
+        checkpoint.marker = Some(cmd.spawn((
+            DespawnOnExit(STATE),
+            Name::new("Checkpoint"),
+            Sprite::from_color(Color::srgb(0.3, 1., 0.3), Vec2::splat(6.)),
+            Transform::from_translation(checkpoint.position.extend(5.))
+                .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+        )).id());
+        practice.checkpoints.push(checkpoint);
+    }
+    if keys.just_pressed(KeyCode::KeyX) && let Some(checkpoint) = practice.checkpoints.pop()
+        && let Some(marker) = checkpoint.marker {
+        cmd.entity(marker).despawn();
+    }
+}
+
+/// Practice deaths put the cube back at the last checkpoint.
+pub(super) fn respawn(
+    mut cmd: Commands,
+    mut practice: ResMut<GdPractice>,
+    mut progress: ResMut<GdProgress>,
+    mut is_left: ResMut<IsLeft>,
+    mut cube_q: Query<(
+        &mut Position,
+        &mut Transform,
+        &mut LinearVelocity,
+        &mut CollisionLayers,
+        &mut Phased,
+        &mut CubeMode,
+    ), With<Cube>>,
+    mut state: ResMut<NextState<LocalState>>,
+    assets: Res<GeometryDashAssets>,
+) {
+    let Some(checkpoint) = practice.checkpoints.last().or(progress.start.as_ref()).cloned() else {return;};
+    let Ok((mut position, mut transform, mut velocity, mut layers, mut phased, mut mode)) = cube_q.single_mut() else {return;};
+    position.0 = checkpoint.position;
+    transform.translation = checkpoint.position.extend(transform.translation.z);
+    transform.rotation = checkpoint.rotation;
+    velocity.0 = checkpoint.velocity;
+    *layers = checkpoint.layers;
+    // the rest of the cube follows its phase and mode
+    phased.phase = checkpoint.phase;
+    *mode = checkpoint.mode;
+    is_left.is = checkpoint.is_left;
+    progress.ticks = checkpoint.ticks;
+    progress.distance = checkpoint.distance;
+    practice.grace = PRACTICE_GRACE;
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        AudioPlayer(assets.explosion.clone()),
+        PlaybackSettings::DESPAWN,
+    ));
+    state.set(LocalState::Game);
+}
+
+/// Reaching the end is 100% whatever `level_length` says.
+pub(super) fn complete_level(
+    mut progress: ResMut<GdProgress>,
+) {
+    progress.distance = progress.distance.max(progress.length);
+}
+
+/// Keeps the best percent of a non-practice run.
+pub(super) fn record_progress(
+    progress: Res<GdProgress>,
+    mut save: ResMut<GdSave>,
+    ghost: Query<&GhostRecorder, With<Cube>>,
+) {
+    if progress.practiced {return;}
+    let percent = progress.percent();
+    if let Ok(ghost) = ghost.single() {
+        ghost.store_if_best(GD_GHOST_PATH, percent);
+    }
+    if percent <= save.best_percent {return;}
+    save.best_percent = percent;
+    save.store();
+}
+
+
+const GD_SAVE_PATH: &str = "saves/gd.ron";
+pub(super) const GD_GHOST_PATH: &str = "saves/gd_ghost.ron";
+const GHOST_CHECKPOINT_PERCENT: f32 = 10.;
+const SOUNDTRACK_DRIFT: f32 = 0.15;
+const PRACTICE_GRACE: f32 = 0.2;
@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use bevy::ecs::system::SystemState;
+use bevy::time::TimeUpdateStrategy;
+
+use super::objects::*;
+use super::plugin::*;
+use super::progress::GdProgress;
+use crate::{phase::Phased, platformer_controller::{ground::{GroundDetector, update_ground}, plugin::PlatformerControllerPlugin}, prelude::*, tick_input::{TickInput, TickInputPlugin}};
+
+
+/// The cube touching `touching` for one frame.
+fn touch(world: &mut World, touching: &[Entity], mode: &mut CubeMode, used: &mut UsedObjects, jump_pressed: bool, on_ground: bool) -> Option<f32> {
+    let mut state = SystemState::<GdObjects<'static, 'static>>::new(world);
+    let objects = state.get(world);
+    touch_objects(touching.iter().copied(), &objects, mode, used, jump_pressed, on_ground)
+}
+
+fn close(a: Option<f32>, b: f32) -> bool {
+    a.is_some_and(|a| (a - b).abs() < 1e-4)
+}
+
+
+#[test]
+fn pad_fires_once_per_touch() {
+    let mut world = World::new();
+    let pad = world.spawn(JumpPad {strength: 200.}).id();
+    let (mut mode, mut used) = (CubeMode::default(), UsedObjects::default());
+    assert_eq!(touch(&mut world, &[pad], &mut mode, &mut used, false, true), Some(200.));
+    assert_eq!(touch(&mut world, &[pad], &mut mode, &mut used, true, false), None);
+    // left and came back
+    assert_eq!(touch(&mut world, &[], &mut mode, &mut used, false, false), None);
+    assert_eq!(touch(&mut world, &[pad], &mut mode, &mut used, false, false), Some(200.));
+}
+
+#[test]
+fn orb_waits_for_a_press_in_the_air() {
+    let mut world = World::new();
+    let orb = world.spawn(JumpOrb {strength: 150.}).id();
+    let (mut mode, mut used) = (CubeMode::default(), UsedObjects::default());
+    // touching without a press keeps the orb for later in the same touch
+    assert_eq!(touch(&mut world, &[orb], &mut mode, &mut used, false, false), None);
+    assert_eq!(touch(&mut world, &[orb], &mut mode, &mut used, true, true), None);
+    assert_eq!(touch(&mut world, &[orb], &mut mode, &mut used, true, false), Some(150.));
+    // used up until the cube leaves it
+    assert_eq!(touch(&mut world, &[orb], &mut mode, &mut used, true, false), None);
+    touch(&mut world, &[], &mut mode, &mut used, false, false);
+    assert_eq!(touch(&mut world, &[orb], &mut mode, &mut used, true, false), Some(150.));
+}
+
+#[test]
+fn kick_follows_gravity_and_size() {
+    let mut world = World::new();
+    let pad = world.spawn(JumpPad {strength: 200.}).id();
+    let mut mode = CubeMode {flipped: true, mini: true, ..default()};
+    let kick = touch(&mut world, &[pad], &mut mode, &mut UsedObjects::default(), false, true);
+    assert!(close(kick, -200. * MINI_SCALE.sqrt()));
+}
+
+#[test]
+fn portals_apply_on_every_touch() {
+    let mut world = World::new();
+    let flip = world.spawn(GravityPortal {flipped: true}).id();
+    let unflip = world.spawn(GravityPortal {flipped: false}).id();
+    let fast = world.spawn((SpeedPortal {speed: 1.5}, SizePortal {mini: true}, ShipPortal {ship: true})).id();
+    let (mut mode, mut used) = (CubeMode::default(), UsedObjects::default());
+
+    for _ in 0..3 {
+        assert_eq!(touch(&mut world, &[flip], &mut mode, &mut used, true, false), None);
+        assert!(mode.flipped);
+    }
+    touch(&mut world, &[unflip], &mut mode, &mut used, false, false);
+    assert!(!mode.flipped);
+    // the same portal again, without leaving anything in between
+    touch(&mut world, &[flip], &mut mode, &mut used, false, false);
+    assert!(mode.flipped);
+    assert!(used.0.is_empty(), "portals are never used up");
+
+    touch(&mut world, &[fast], &mut mode, &mut used, false, false);
+    touch(&mut world, &[fast], &mut mode, &mut used, false, false);
+    assert_eq!((mode.speed, mode.mini, mode.ship), (1.5, true, true));
+}
+
+#[test]
+fn pad_and_portal_in_one_frame_kick_the_new_way() {
+    let mut world = World::new();
+    let pad = world.spawn(JumpPad {strength: 200.}).id();
+    let flip = world.spawn(GravityPortal {flipped: true}).id();
+    let kick = touch(&mut world, &[pad, flip], &mut CubeMode::default(), &mut UsedObjects::default(), false, true);
+    assert_eq!(kick, Some(-200.));
+}
+
+#[test]
+fn soundtrack_follows_ticks_not_distance() {
+    // a 1.4x speed portal covers more distance in the same time
+    let progress = GdProgress {ticks: 128, distance: 90.0 * 2.0 * 1.4, ..default()};
+    assert_eq!(progress.music_time(Duration::from_secs_f64(1.0 / 64.0)), 2.0);
+}
+
+/// Ticks the jump key is down on.
+#[derive(Resource)]
+struct Jumps(Vec<u64>);
+
+/// Cube position after every tick.
+#[derive(Resource, Default)]
+struct Trajectory(Vec<Vec2>);
+
+fn replay_jumps(jumps: Res<Jumps>, mut input: ResMut<TickInput>) {
+    if jumps.0.contains(&input.tick) {
+        input.press(KeyCode::Space);
+    } else {
+        input.release(KeyCode::Space);
+    }
+}
+
+fn record_cube(q: Query<&Position, With<Cube>>, mut trajectory: ResMut<Trajectory>) {
+    trajectory.0.extend(q.iter().map(|p| p.0));
+}
+
+/// Runs the cube on flat ground headless at `fps` until `ticks` fixed ticks have run.
+fn run_cube_at(fps: f64, jumps: &[u64], ticks: usize) -> Vec<Vec2> {
+    let mut app = App::new();
+    app
+        .add_plugins((MinimalPlugins, TransformPlugin, AssetPlugin::default(), PhysicsPlugins::default(), PlatformerControllerPlugin, TickInputPlugin))
+        .init_resource::<Assets<Mesh>>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(1.0 / fps)))
+        .insert_resource(NextState::<LocalState>::default())
+        .insert_resource(IsLeft {is: false})
+        .init_resource::<FunnyTimer>()
+        .insert_resource(Jumps(jumps.to_vec()))
+        .init_resource::<Trajectory>()
+        .add_systems(FixedUpdate, (replay_jumps, controller).chain().after(update_ground))
+        .add_systems(FixedLast, record_cube);
+    app.world_mut().spawn((RigidBody::Static, Collider::rectangle(4000.0, 16.0), Transform::from_xyz(1900.0, -8.0, 0.0)));
+    let cube = app.world_mut().spawn((
+        Cube,
+        RigidBody::Dynamic,
+        Collider::rectangle(16.0, 16.0),
+        LinearVelocity(Vec2::ZERO),
+        GravityScale(GRAVITY_SCALE),
+        Transform::from_xyz(0.0, 8.5, 0.0),
+        CubeMode::default(),
+        Phased {phase: 0, base: CollisionLayers::default(), blocker: None},
+        GroundDetector::default(),
+    )).id();
+    app.insert_resource(PlayerEntity {entity: cube});
+    while app.world().resource::<Trajectory>().0.len() < ticks {
+        app.update();
+    }
+    let mut trajectory = std::mem::take(&mut app.world_mut().resource_mut::<Trajectory>().0);
+    trajectory.truncate(ticks);
+    trajectory
+}
+
+#[test]
+fn same_jumps_same_cube_arc_at_any_frame_rate() {
+    let jumps = [10, 80, 81, 150];
+    let reference = run_cube_at(60.0, &jumps, 220);
+    let ground = reference[9].y;
+    assert!(reference.iter().any(|p| p.y > ground + 16.0), "the cube jumped");
+    for fps in [30.0, 240.0] {
+        assert_eq!(run_cube_at(fps, &jumps, 220), reference, "{fps} FPS");
+    }
+}
@@ -0,0 +1,320 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, saves};
+
+
+/// Translucent replays of the best previous attempt, G hides them.
+///
+/// A game puts a `GhostRecorder` on its player, spawns the stored run with `spawn_ghost`,
+/// calls `GhostRecorder::checkpoint` as the player passes checkpoints and `GhostRecorder::store_if_best` at the end.
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<GhostSettings>()
+            .add_systems(Update, (toggle_ghosts, show_deltas, fade_deltas))
+            .add_systems(PostUpdate, (record_ghosts, play_ghosts).before(TransformSystems::Propagate))
+            ;
+    }
+}
+
+#[derive(Resource)]
+pub struct GhostSettings {
+    pub enabled: bool,
+}
+
+impl Default for GhostSettings {
+    fn default() -> Self {
+        Self {enabled: true}
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct GhostFrame {
+    pub time: f32,
+    pub position: Vec2,
+    pub rotation: f32,
+    pub index: usize,
+    pub color: Color,
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub size: Option<Vec2>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct GhostRun {
+    /// Higher is better, what it means is up to the game.
+    pub score: f32,
+    pub z: f32,
+    pub scale: Vec3,
+    pub frames: Vec<GhostFrame>,
+    /// Times the checkpoints were passed at.
+    pub checkpoints: Vec<f32>,
+}
+
+impl GhostRun {
+    /// `None` when nothing was stored yet.
+    pub fn load(path: &str) -> Option<Self> {
+        let run: Self = saves::load(path);
+        (!run.frames.is_empty()).then_some(run)
+    }
+
+    /// Frames around `time` and how far between them it is.
+    fn at(&self, time: f32) -> Option<(&GhostFrame, &GhostFrame, f32)> {
+        let i = self.frames.partition_point(|f| f.time <= time);
+        let a = self.frames.get(i.checked_sub(1)?)?;
+        let b = self.frames.get(i).unwrap_or(a);
+        let t = if b.time > a.time {(time - a.time) / (b.time - a.time)} else {0.};
+        Some((a, b, t))
+    }
+}
+
+/// Records the sprite of its entity.
+#[derive(Component, Default)]
+pub struct GhostRecorder {
+    pub run: GhostRun,
+    pub time: f32,
+    since_frame: f32,
+    /// Checkpoints compared to the ghost so far.
+    shown: usize,
+}
+
+impl GhostRecorder {
+    /// The player passed a checkpoint, shows how far ahead of the ghost it is.
+    pub fn checkpoint(&mut self) {
+        self.run.checkpoints.push(self.time);
+    }
+
+    /// Keeps the run at `path` if `score` beats the stored one.
+    pub fn store_if_best(&self, path: &str, score: f32) -> bool {
+        if self.run.frames.is_empty() {return false;}
+        if GhostRun::load(path).is_some_and(|best| best.score >= score) {return false;}
+        saves::store(path, &GhostRun {score, ..self.run.clone()});
+        true
+    }
+}
+
+/// Plays `run` back from the moment it is spawned.
+#[derive(Component)]
+pub struct Ghost {
+    pub run: GhostRun,
+    pub time: f32,
+}
+
+#[derive(Component)]
+struct GhostDelta {
+    timer: f32,
+}
+
+/// Spawns a ghost drawing `sprite` as the recorded player did, `sprite` gives image and atlas.
+pub fn spawn_ghost(cmd: &mut Commands, run: GhostRun, sprite: Sprite, state: AppState) -> Entity {
+    let transform = Transform::from_xyz(0., 0., run.z - 0.1).with_scale(run.scale);
+    cmd.spawn((
+        DespawnOnExit(state),
+        Name::new("Ghost"),
+        sprite,
+        transform,
+        Visibility::Hidden,
+        Ghost {run, time: 0.},
+    )).id()
+}
+
+fn record_ghosts(
+    time: Res<Time>,
+    mut q: Query<(&mut GhostRecorder, &Transform, &Sprite)>,
+) {
+    for (mut recorder, transform, sprite) in q.iter_mut() {
+        recorder.time += time.delta_secs();
+        recorder.since_frame += time.delta_secs();
+        if recorder.since_frame < GHOST_FRAME_TIME && !recorder.run.frames.is_empty() {continue;}
+        recorder.since_frame = 0.;
+        let time = recorder.time;
+        recorder.run.z = transform.translation.z;
+        recorder.run.scale = transform.scale;
+        recorder.run.frames.push(GhostFrame {
+            time,
+            position: transform.translation.truncate(),
+            rotation: transform.rotation.to_euler(EulerRot::XYZ).2,
+            index: sprite.texture_atlas.as_ref().map_or(0, |a| a.index),
+            color: sprite.color,
+            flip_x: sprite.flip_x,
+            flip_y: sprite.flip_y,
+            size: sprite.custom_size,
+        });
+    }
+}
+
+fn play_ghosts(
+    time: Res<Time>,
+    settings: Res<GhostSettings>,
+    mut q: Query<(&mut Ghost, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    for (mut ghost, mut transform, mut sprite, mut visibility) in q.iter_mut() {
+        ghost.time += time.delta_secs();
+        let Some((a, b, t)) = ghost.run.at(ghost.time) else {continue;};
+        // a finished ghost disappears
+        let visible = settings.enabled && ghost.time <= b.time;
+        visibility.set_if_neq(if visible {Visibility::Inherited} else {Visibility::Hidden});
+        transform.translation = a.position.lerp(b.position, t).extend(transform.translation.z);
+        transform.rotation = Quat::from_rotation_z(a.rotation).slerp(Quat::from_rotation_z(b.rotation), t);
+        if let Some(atlas) = &mut sprite.texture_atlas {
+            atlas.index = a.index;
+        }
+        sprite.color = a.color.with_alpha(a.color.alpha() * GHOST_ALPHA);
+        sprite.flip_x = a.flip_x;
+        sprite.flip_y = a.flip_y;
+        sprite.custom_size = a.size;
+    }
+}
+
+fn toggle_ghosts(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<GhostSettings>,
+) {
+    if keys.just_pressed(KeyCode::KeyG) {
+        settings.enabled = !settings.enabled;
+    }
+}
+
+fn show_deltas(
+    mut cmd: Commands,
+    settings: Res<GhostSettings>,
+    mut recorders: Query<&mut GhostRecorder>,
+    ghosts: Query<&Ghost>,
+    deltas: Query<Entity, With<GhostDelta>>,
+) {
+    let Ok(mut recorder) = recorders.single_mut() else {return;};
+    if recorder.shown == recorder.run.checkpoints.len() {return;}
+    let i = recorder.run.checkpoints.len() - 1;
+    recorder.shown = i + 1;
+    if !settings.enabled {return;}
+    let time = recorder.run.checkpoints[i];
+    let Some(ghost_time) = ghosts.single().ok().and_then(|g| g.run.checkpoints.get(i)) else {return;};
+    let delta = time - ghost_time;
+    for e in deltas.iter() {
+        cmd.entity(e).despawn();
+    }
+    cmd.spawn((
+        GhostDelta {timer: GHOST_DELTA_TIME},
+        Node {
+            top: Val::Px(28.0),
+            width: Val::Percent(100.0),
+            position_type: PositionType::Absolute,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        GlobalZIndex(10),
+        children![(
+            Text::new(format!("{delta:+.2}")),
+            TextFont {
+                font_size: 18.0,
+                ..default()
+            },
+            // behind the ghost is red
+            TextColor(if delta > 0. {Color::srgb(1., 0.3, 0.3)} else {Color::srgb(0.3, 1., 0.3)}),
+        )],
+    ));
+}
+
+fn fade_deltas(
+    mut cmd: Commands,
+    time: Res<Time>,
+    mut q: Query<(Entity, &mut GhostDelta)>,
+) {
+    for (e, mut delta) in q.iter_mut() {
+        delta.timer -= time.delta_secs();
+        if delta.timer <= 0. {
+            cmd.entity(e).despawn();
+        }
+    }
+}
+
+
+const GHOST_FRAME_TIME: f32 = 1. / 30.;
+const GHOST_ALPHA: f32 = 0.4;
+const GHOST_DELTA_TIME: f32 = 1.5;
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::time::TimeUpdateStrategy;
+
+    use super::*;
+
+    fn frame(time: f32, x: f32) -> GhostFrame {
+        GhostFrame {time, position: Vec2::new(x, 0.), ..default()}
+    }
+
+    fn run(checkpoints: Vec<f32>) -> GhostRun {
+        GhostRun {frames: vec![frame(0., 0.), frame(1., 10.), frame(2., 30.)], checkpoints, ..default()}
+    }
+
+    fn delta_app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins(MinimalPlugins)
+            .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(0.1)))
+            .init_resource::<GhostSettings>()
+            .add_systems(Update, show_deltas);
+        app
+    }
+
+    fn delta_texts(app: &mut App) -> Vec<String> {
+        app.world_mut().query::<&Text>().iter(app.world()).map(|t| t.0.clone()).collect()
+    }
+
+    #[test]
+    fn run_interpolates_between_frames() {
+        let run = run(vec![]);
+        assert!(run.at(-0.5).is_none());
+        let (a, b, t) = run.at(1.5).unwrap();
+        assert_eq!((a.time, b.time), (1., 2.));
+        assert!((t - 0.5).abs() < 1e-6);
+        assert!((a.position.lerp(b.position, t).x - 20.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn run_holds_last_frame_after_the_end() {
+        let run = run(vec![]);
+        let (a, b, t) = run.at(5.).unwrap();
+        assert_eq!((a.time, b.time, t), (2., 2., 0.));
+    }
+
+    #[test]
+    fn checkpoint_shows_signed_delta_to_ghost() {
+        let mut app = delta_app();
+        app.world_mut().spawn(Ghost {run: run(vec![1., 2.]), time: 0.});
+        let player = app.world_mut().spawn(GhostRecorder::default()).id();
+
+        app.world_mut().get_mut::<GhostRecorder>(player).unwrap().time = 1.5;
+        app.world_mut().get_mut::<GhostRecorder>(player).unwrap().checkpoint();
+        app.update();
+        assert_eq!(delta_texts(&mut app), vec!["+0.50"]);
+
+        // the next checkpoint replaces the shown delta
+        app.world_mut().get_mut::<GhostRecorder>(player).unwrap().time = 1.75;
+        app.world_mut().get_mut::<GhostRecorder>(player).unwrap().checkpoint();
+        app.update();
+        assert_eq!(delta_texts(&mut app), vec!["-0.25"]);
+
+        // nothing new without a checkpoint
+        app.update();
+        assert_eq!(delta_texts(&mut app), vec!["-0.25"]);
+    }
+
+    #[test]
+    fn checkpoint_past_the_ghost_shows_nothing() {
+        let mut app = delta_app();
+        app.world_mut().spawn(Ghost {run: run(vec![]), time: 0.});
+        let mut recorder = GhostRecorder::default();
+        recorder.checkpoint();
+        app.world_mut().spawn(recorder);
+        app.update();
+        assert!(delta_texts(&mut app).is_empty());
+    }
+}
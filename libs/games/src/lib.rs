@@ -9,6 +9,15 @@ pub mod core;
 pub mod shaders;
 pub mod global_music;
 pub mod hints;
+pub mod clickable;
+pub mod platformer_controller;
+pub mod phase;
+pub mod saves;
+pub mod tick_input;
+pub mod replay;
+pub mod ghost;
+pub mod cutscene;
+pub mod transition;
 pub mod fake_end;
 pub mod geometry_dash;
 pub mod pathfinder;
@@ -1,4 +1,4 @@
-use camera::CameraController;
+use camera::{CameraController, CameraTarget};
 use rand::Rng;
 
 use crate::prelude::*;
@@ -33,6 +33,10 @@ pub struct FreddyScreen;
 #[derive(Component, Default)]
 pub struct FreddyScreenTop;
 
+/// Never removed, unlike `FreddyScreen`, so both halves can be found later.
+#[derive(Component, Default)]
+pub struct FreddyScreenPart;
+
 #[derive(Component, Default)]
 pub struct BossFightWait;
 
@@ -194,7 +198,8 @@ pub fn setup_freddy_fight2(
     mut bot: Query<(Entity, &mut Transform, &mut Sprite), With<FreddyScreen> >
 ){
     for e in q {
-        cmd.entity(e).insert(RigidBody::Dynamic).remove::<(InvincibleCharacter, BossFightWait)>();
+        // the camera keeps him in view with the player
+        cmd.entity(e).insert((RigidBody::Dynamic, CameraTarget::new(FREDDY_CAMERA_WEIGHT))).remove::<(InvincibleCharacter, BossFightWait)>();
     }
     for (e, mut t, mut s) in bot.iter_mut() {
         t.translation.z = -14.0;
@@ -280,6 +285,7 @@ const FREDDY_CHASE_THRESHOLD: f32 = 600.0;
 const BONNIE_CHICKA_ENDOSKELETON_SPAWN_DELAY: f32 = 10.0;
 const FREDDY1_ENDOSKELETON_SPAWN_DELAY: f32 = 5.0;
 const FREDDY2_ENDOSKELETON_SPAWN_DELAY: f32 = 3.0;
+const FREDDY_CAMERA_WEIGHT: f32 = 0.5;
 
 const WEIGHT_COPPER: u32 = 50;
 const WEIGHT_GOLDEN: u32 = 10;
@@ -0,0 +1,229 @@
+use camera::CameraController;
+
+use crate::pathfinder::plugin::PathfinderObstacle;
+
+use super::bossfight::*;
+use super::decals::*;
+use super::dialog::*;
+use super::entity::*;
+use super::map::*;
+use super::plugin::*;
+use super::weapon::*;
+use crate::prelude::*;
+
+
+#[derive(Component, Reflect, Default)]
+#[reflect(Component, Default)]
+pub struct MiamiCheckpoint;
+
+#[derive(Component)]
+pub struct CheckpointReached;
+
+/// Restores the bossfight after a quick restart, once `setup_freddy_fight` has tagged the fighters again.
+#[derive(Resource)]
+pub struct ResumeBossfight;
+
+#[derive(Resource)]
+pub struct MiamiCheckpointSnapshot {
+    pub player: Transform,
+    pub player_look_dir: Vec2,
+    pub player_weapon: Option<(WeaponType, u32)>,
+    pub enemies: Vec<MiamiSpawnOrigin>,
+    pub weapons: Vec<(WeaponType, u32, Vec3)>,
+    pub doors: Vec<(Entity, Transform, DoorState)>,
+    pub stage: FreddyFightStage,
+    pub entrypoint_dialog: bool,
+    pub boss_dialog: bool,
+}
+
+
+pub fn on_checkpoint_spawned(
+    collider_created: On<TiledEvent<ColliderCreated>>,
+    spawn_query: Query<&MiamiCheckpoint>,
+    parents: Query<&ChildOf>,
+    mut cmd: Commands,
+    state: Res<State<AppState>>,
+) {
+    if state.get() != &STATE {return;}
+    let spawn_entity = collider_created.event().origin;
+    let Ok(p) = parents.get(spawn_entity) else {return;};
+    let Ok(_c) = spawn_query.get(p.parent()) else {return;};
+    cmd.entity(spawn_entity).insert((
+        DespawnOnExit(STATE),
+        Name::new("Checkpoint"),
+        Sensor,
+        dialog_sensor_layer(),
+        RigidBody::Static,
+        CollisionEventsEnabled,
+    )).observe(on_checkpoint_collision);
+}
+
+pub fn on_checkpoint_collision(
+    e: On<CollisionStart>,
+    mut cmd: Commands,
+    state: Res<State<AppState>>,
+    reached: Query<(), With<CheckpointReached>>,
+    player: Query<(&Transform, &CharacterController, Option<&ArmedCharacter>), With<Player>>,
+    weapons: Query<(&Weapon, &GlobalTransform, Option<&WeaponOf>)>,
+    enemies: Query<&MiamiSpawnOrigin, (With<CharacterController>, Without<Player>)>,
+    doors: Query<(Entity, &Transform, &MiamiDoor)>,
+    stage: Option<Res<State<FreddyFightStage>>>,
+    shooted: Res<ShootedDialogs>,
+    zero_health: Option<Res<PlayerZeroHealthTicker>>,
+) {
+    if state.get() != &STATE {return;}
+    if zero_health.is_some() {return;}
+    let checkpoint = e.event().collider1;
+    if reached.contains(checkpoint) {return;}
+    let Ok((pt, pc, armed)) = player.get(e.event().collider2) else {return;};
+    let Some(stage) = stage else {return;};
+
+    let player_weapon = armed
+        .and_then(|a| weapons.get(a.0).ok())
+        .map(|(w, _, _)| (w.weapon_type.clone(), w.ammo));
+    let ground_weapons = weapons.iter()
+        .filter(|(_, _, of)| of.is_none())
+        .map(|(w, gt, _)| (w.weapon_type.clone(), w.ammo, gt.translation()))
+        .collect();
+
+    cmd.entity(checkpoint).insert(CheckpointReached);
+    cmd.insert_resource(MiamiCheckpointSnapshot {
+        player: pt.clone(),
+        player_look_dir: pc.look_dir,
+        player_weapon,
+        enemies: enemies.iter().cloned().collect(),
+        weapons: ground_weapons,
+        doors: doors.iter().map(|(e, t, d)| (e, t.clone(), d.state)).collect(),
+        stage: *stage.get(),
+        entrypoint_dialog: shooted.entrypoint,
+        boss_dialog: shooted.boss,
+    });
+    info!("Checkpoint reached");
+}
+
+
+pub fn quick_restart(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    snapshot: Option<Res<MiamiCheckpointSnapshot>>,
+    mut cmd: Commands,
+    assets: Res<MiamiAssets>,
+    mut camera_controller: ResMut<CameraController>,
+    characters: Query<Entity, With<CharacterController>>,
+    weapons: Query<Entity, (With<Weapon>, Without<WeaponOf>)>,
+    leftovers: Query<Entity, Or<(With<Projectile>, With<SlidingBody>, With<BakingDecal>)>>,
+    mut decals: ResMut<MiamiDecalChunks>,
+    screens: Query<Entity, With<FreddyScreenPart>>,
+    mut doors: Query<(&mut MiamiDoor, &mut Transform, &mut LinearVelocity, &mut AngularVelocity)>,
+    mut navmeshes: Query<&mut NavMeshUpdateMode>,
+    entrypoints: Query<Entity, With<BossEntrypointCollider>>,
+    mut shooted: ResMut<ShootedDialogs>,
+    stage: Res<State<FreddyFightStage>>,
+    mut next_stage: ResMut<NextState<FreddyFightStage>>,
+) {
+    if !keyboard_input.just_pressed(MIAMI_QUICK_RESTART_KEY) {return;}
+    let Some(snapshot) = snapshot else {return;};
+
+    for e in characters.iter() {
+        cmd.entity(e).despawn();
+    }
+    for e in weapons.iter() {
+        cmd.entity(e).despawn();
+    }
+    for e in leftovers.iter() {
+        cmd.entity(e).try_despawn();
+    }
+    // bodies and blood from after the checkpoint would lie next to the respawned enemies
+    decals.clear(&mut cmd);
+    if snapshot.enemies.iter().any(|o| o.entity_type == MiamiEntity::Freddy) {
+        for e in screens.iter() {
+            cmd.entity(e).despawn();
+        }
+    }
+    cmd.remove_resource::<PlayerZeroHealthTicker>();
+    cmd.remove_resource::<BossfightDialog>();
+    cmd.remove_resource::<PreFreddyDialog>();
+
+    spawn_entity(
+        &mut cmd,
+        MiamiEntity::Player,
+        &assets,
+        &mut camera_controller,
+        snapshot.player.clone(),
+        snapshot.player_look_dir,
+    );
+    if let Some((weapon_type, ammo)) = &snapshot.player_weapon {
+        // Dropped right under the player, the regular pickup path equips it on the next contact.
+        let mut wpn = weapon_type.to_weapon();
+        wpn.ammo = *ammo;
+        spawn_pickup_weapon(&mut cmd, &assets, wpn, snapshot.player.translation);
+    }
+
+    for origin in snapshot.enemies.iter() {
+        spawn_entity(
+            &mut cmd,
+            origin.entity_type.clone(),
+            &assets,
+            &mut camera_controller,
+            origin.transform.clone(),
+            origin.look_dir,
+        );
+    }
+    for (weapon_type, ammo, translation) in snapshot.weapons.iter() {
+        let mut wpn = weapon_type.to_weapon();
+        wpn.ammo = *ammo;
+        spawn_pickup_weapon(&mut cmd, &assets, wpn, *translation);
+    }
+    let mut rebuild = false;
+    for (e, t, state) in snapshot.doors.iter() {
+        let Ok((mut door, mut transform, mut lv, mut av)) = doors.get_mut(*e) else {continue;};
+        *transform = t.clone();
+        lv.0 = Vec2::ZERO;
+        av.0 = 0.0;
+        if door.state == *state {continue;}
+        // `update_doors` only tracks changes from here on, so the obstacle follows the restored state
+        if *state == DoorState::Open {
+            cmd.entity(*e).insert(PathfinderObstacle);
+            rebuild = true;
+        } else if door.state == DoorState::Open {
+            cmd.entity(*e).remove::<PathfinderObstacle>();
+            rebuild = true;
+        }
+        door.state = *state;
+    }
+    if rebuild {
+        for mut mode in navmeshes.iter_mut() {
+            *mode = NavMeshUpdateMode::OnDemand(true);
+        }
+    }
+
+    shooted.entrypoint = snapshot.entrypoint_dialog;
+    shooted.boss = snapshot.boss_dialog;
+
+    if snapshot.stage == FreddyFightStage::Idle {
+        for e in entrypoints.iter() {
+            cmd.entity(e).insert(Sensor);
+        }
+        camera_controller.target_zoom = MIAMI_CAMERA_ZOOM;
+    } else {
+        cmd.insert_resource(ResumeBossfight);
+    }
+    if stage.get() != &FreddyFightStage::Idle {
+        next_stage.set(FreddyFightStage::Idle);
+    }
+}
+
+
+pub fn resume_bossfight(
+    mut cmd: Commands,
+    waiting: Query<Entity, (With<BossFightWait>, Without<FighterFreddy>)>,
+    fighters: Query<(), Or<(With<FighterBonnie>, With<FighterChicka>, With<FighterFreddy>)>>,
+    q: Query<Entity, With<BossEntrypointCollider>>,
+    mut controller: ResMut<CameraController>,
+    mut next_stage: ResMut<NextState<FreddyFightStage>>,
+) {
+    if fighters.is_empty() {return;}
+    cmd.remove_resource::<ResumeBossfight>();
+    begin_bossfight(&mut cmd, &waiting, &q, &mut controller);
+    // Dead fighters stay dead, so the stage ticks forward by itself from here.
+    next_stage.set(FreddyFightStage::BonnieChicka);
+}
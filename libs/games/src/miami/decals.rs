@@ -0,0 +1,336 @@
+use std::collections::{HashMap, VecDeque};
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::camera::{ImageRenderTarget, RenderTarget, ScalingMode};
+use bevy::camera::visibility::RenderLayers;
+use bevy::diagnostic::FrameCount;
+use bevy::render::render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages};
+use rand::Rng;
+
+use super::plugin::*;
+use crate::prelude::*;
+
+
+pub const MIAMI_DECAL_LAYERS: RenderLayers = RenderLayers::layer(2);
+/// Same depth the loose blood sprites used to sit at: character z + `BLOOD_Z_TRANSLATION`.
+pub const MIAMI_DECAL_Z: f32 = -2.0 + BLOOD_Z_TRANSLATION;
+
+#[derive(Resource)]
+pub struct MiamiDecalSettings {
+    /// World units (and texels) covered by one baked texture.
+    pub chunk_size: u32,
+    /// Least recently painted chunks are dropped past this.
+    pub max_chunks: usize,
+    /// Decals drawn into chunks per frame, the rest waits in the queue.
+    pub bakes_per_frame: usize,
+    pub trail_spacing: f32,
+}
+
+impl Default for MiamiDecalSettings {
+    fn default() -> Self {
+        Self {
+            chunk_size: 256,
+            max_chunks: 64,
+            bakes_per_frame: 64,
+            trail_spacing: 6.0,
+        }
+    }
+}
+
+/// Sprite to be painted into the floor once and forgotten.
+#[derive(Event, Clone)]
+pub struct MiamiDecal {
+    pub image: Handle<Image>,
+    pub rect: Rect,
+    pub color: Color,
+    /// Translation `z` only orders decals baked in the same frame.
+    pub transform: Transform,
+}
+
+pub struct DecalChunk {
+    camera: Entity,
+    sprite: Entity,
+    last_used: u32,
+    ready_at: u32,
+}
+
+#[derive(Resource, Default)]
+pub struct MiamiDecalChunks {
+    chunks: HashMap<IVec2, DecalChunk>,
+    queue: VecDeque<MiamiDecal>,
+}
+
+impl MiamiDecalChunks {
+    /// Drops every baked chunk and the decals still queued.
+    pub fn clear(&mut self, cmd: &mut Commands) {
+        self.queue.clear();
+        evict_chunks(cmd, self, 0);
+    }
+}
+
+#[derive(Component)]
+pub struct DecalChunkCamera;
+
+#[derive(Component)]
+pub struct BakingDecal;
+
+#[derive(Component)]
+pub struct SlidingBody {
+    pub velocity: Vec2,
+    pub image: Handle<Image>,
+    pub rect: Rect,
+}
+
+#[derive(Component)]
+pub struct BloodTrail {
+    pub color: Color,
+    pub last: Vec2,
+}
+
+
+pub fn on_decal(
+    decal: On<MiamiDecal>,
+    chunks: Option<ResMut<MiamiDecalChunks>>,
+) {
+    let Some(mut chunks) = chunks else {return;};
+    chunks.queue.push_back(decal.event().clone());
+}
+
+pub fn bake_decals(
+    mut cmd: Commands,
+    settings: Res<MiamiDecalSettings>,
+    mut chunks: ResMut<MiamiDecalChunks>,
+    mut images: ResMut<Assets<Image>>,
+    mut cameras: Query<&mut Camera, With<DecalChunkCamera>>,
+    baked: Query<Entity, With<BakingDecal>>,
+    frame: Res<FrameCount>,
+) {
+    // last frame's decals are in the textures already
+    for e in baked.iter() {
+        cmd.entity(e).despawn();
+    }
+    for mut c in cameras.iter_mut() {
+        c.is_active = false;
+    }
+    let frame = frame.0;
+    let size = settings.chunk_size as f32;
+    let mut postponed = vec![];
+    for _ in 0..settings.bakes_per_frame {
+        let Some(decal) = chunks.queue.pop_front() else {break;};
+        let half = decal.rect.size() * decal.transform.scale.truncate().abs() * 0.5;
+        let radius = half.length();
+        let center = decal.transform.translation.truncate();
+        let min = ((center - radius) / size).floor().as_ivec2();
+        let max = ((center + radius) / size).floor().as_ivec2();
+        let mut ready = true;
+        for x in min.x..=max.x {
+            for y in min.y..=max.y {
+                let key = ivec2(x, y);
+                if !chunks.chunks.contains_key(&key) {
+                    evict_chunks(&mut cmd, &mut chunks, settings.max_chunks.saturating_sub(1));
+                    let chunk = spawn_chunk(&mut cmd, &mut images, key, settings.chunk_size, frame);
+                    chunks.chunks.insert(key, chunk);
+                }
+                let Some(chunk) = chunks.chunks.get_mut(&key) else {continue;};
+                chunk.last_used = frame;
+                // a fresh render target is not prepared yet, drawing now would lose the decal
+                if chunk.ready_at > frame {ready = false; continue;}
+                let Ok(mut c) = cameras.get_mut(chunk.camera) else {continue;};
+                c.is_active = true;
+            }
+        }
+        if !ready {
+            postponed.push(decal);
+            continue;
+        }
+        cmd.spawn((
+            Name::new("BakingDecal"),
+            BakingDecal,
+            MIAMI_DECAL_LAYERS,
+            decal.transform,
+            Sprite {
+                image: decal.image,
+                rect: Some(decal.rect),
+                color: decal.color,
+                ..Default::default()
+            },
+        ));
+    }
+    for decal in postponed.into_iter().rev() {
+        chunks.queue.push_front(decal);
+    }
+}
+
+fn evict_chunks(cmd: &mut Commands, chunks: &mut MiamiDecalChunks, keep: usize) {
+    while chunks.chunks.len() > keep {
+        let Some(key) = chunks.chunks.iter()
+            .min_by_key(|(_, c)| c.last_used)
+            .map(|(k, _)| *k) else {return;};
+        let Some(chunk) = chunks.chunks.remove(&key) else {return;};
+        cmd.entity(chunk.camera).despawn();
+        cmd.entity(chunk.sprite).despawn();
+    }
+}
+
+fn spawn_chunk(
+    cmd: &mut Commands,
+    images: &mut ResMut<Assets<Image>>,
+    key: IVec2,
+    chunk_size: u32,
+    frame: u32,
+) -> DecalChunk {
+    let extent = Extent3d {
+        width: chunk_size,
+        height: chunk_size,
+        ..default()
+    };
+    let mut texture = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size: extent,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    texture.resize(extent);
+    let handle = images.add(texture);
+    let size = chunk_size as f32;
+    let center = (key.as_vec2() + 0.5) * size;
+    let camera = cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("DecalChunkCamera"),
+        DecalChunkCamera,
+        Camera2d,
+        Msaa::Off,
+        Camera {
+            order: -1,
+            is_active: false,
+            clear_color: ClearColorConfig::None,
+            ..Default::default()
+        },
+        RenderTarget::Image(ImageRenderTarget{ handle: handle.clone(), scale_factor: 1.0 }),
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed { width: size, height: size },
+            near: -1000.0,
+            far: 1000.0,
+            ..OrthographicProjection::default_2d()
+        }),
+        Transform::from_translation(center.extend(0.0)),
+        MIAMI_DECAL_LAYERS,
+    )).id();
+    let sprite = cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("DecalChunk"),
+        Sprite {
+            image: handle,
+            custom_size: Some(Vec2::splat(size)),
+            ..Default::default()
+        },
+        Transform::from_translation(center.extend(MIAMI_DECAL_Z)),
+    )).id();
+    DecalChunk { camera, sprite, last_used: frame, ready_at: frame + 2 }
+}
+
+
+/// Main stain plus droplets thrown along the hit, `dir` is where the blood flies.
+pub fn blood_splatter(
+    cmd: &mut Commands,
+    assets: &Res<MiamiAssets>,
+    blood_rects: &[Rect; 3],
+    color: Color,
+    pos: Vec2,
+    dir: Vec2,
+    idx: usize,
+) {
+    let mut rng = rand::rng();
+    let dir = if dir == Vec2::ZERO {Vec2::from_angle(rng.random_range(0.0..TAU))} else {dir.normalize()};
+    cmd.trigger(MiamiDecal {
+        image: assets.decals.clone(),
+        rect: blood_rects[idx].clone(),
+        color,
+        transform: Transform::from_translation((pos + dir * 4.0).extend(0.0))
+            .with_rotation(Quat::from_rotation_z(dir.to_angle() - FRAC_PI_2)),
+    });
+    for _ in 0..(idx + 1) * BLOOD_DROPLETS_PER_SIZE {
+        let d = Vec2::from_angle(rng.random_range(-BLOOD_SPLATTER_SPREAD..BLOOD_SPLATTER_SPREAD)).rotate(dir);
+        let offset = d * rng.random_range(6.0..BLOOD_SPLATTER_DISTANCE);
+        cmd.trigger(MiamiDecal {
+            image: assets.decals.clone(),
+            rect: blood_rects[0].clone(),
+            color,
+            transform: Transform::from_translation((pos + offset).extend(0.0))
+                .with_rotation(Quat::from_rotation_z(rng.random_range(0.0..TAU)))
+                .with_scale(Vec3::splat(rng.random_range(0.2..0.5))),
+        });
+    }
+}
+
+pub fn eject_shell_casing(
+    cmd: &mut Commands,
+    assets: &Res<MiamiAssets>,
+    pos: Vec3,
+    look_dir: Vec2,
+) {
+    let mut rng = rand::rng();
+    let right = Vec2::new(look_dir.y, -look_dir.x);
+    let p = pos.truncate() + right * rng.random_range(8.0..16.0) - look_dir * rng.random_range(0.0..6.0);
+    cmd.trigger(MiamiDecal {
+        image: assets.projectiles.clone(),
+        rect: shell_casing_rect(),
+        color: shell_casing_color(),
+        transform: Transform::from_translation(p.extend(1.0))
+            .with_rotation(Quat::from_rotation_z(rng.random_range(0.0..TAU))),
+    });
+}
+
+pub fn slide_bodies(
+    mut cmd: Commands,
+    mut bodies: Query<(Entity, &mut SlidingBody, &mut Transform, &Sprite, Option<&mut BloodTrail>)>,
+    settings: Res<MiamiDecalSettings>,
+    assets: Res<MiamiAssets>,
+    time: Res<Time>,
+) {
+    let dt = time.dt();
+    for (e, mut body, mut t, sprite, trail) in bodies.iter_mut() {
+        t.translation += (body.velocity * dt).extend(0.0);
+        body.velocity = body.velocity.exp_decay(Vec2::ZERO, BODY_SLIDE_DECAY, dt);
+        let pos = t.translation.truncate();
+        if let Some(mut trail) = trail && trail.last.distance(pos) > settings.trail_spacing {
+            let dir = (pos - trail.last).normalize_or_zero();
+            trail.last = pos;
+            cmd.trigger(MiamiDecal {
+                image: assets.decals.clone(),
+                rect: blood_rects()[0].clone(),
+                color: trail.color,
+                transform: Transform::from_translation(pos.extend(0.0))
+                    .with_rotation(Quat::from_rotation_z(dir.to_angle()))
+                    .with_scale(vec3(0.6, 0.3, 1.0)),
+            });
+        }
+        if body.velocity.length_squared() > BODY_SLIDE_STOP * BODY_SLIDE_STOP {continue;}
+        let mut transform = t.clone();
+        transform.translation.z = 2.0;
+        cmd.trigger(MiamiDecal {
+            image: body.image.clone(),
+            rect: body.rect.clone(),
+            color: sprite.color,
+            transform,
+        });
+        cmd.entity(e).despawn();
+    }
+}
+
+
+pub const BODY_SLIDE_SPEED: f32 = 120.0;
+const BLOOD_DROPLETS_PER_SIZE: usize = 2;
+const BLOOD_SPLATTER_SPREAD: f32 = 0.4;
+const BLOOD_SPLATTER_DISTANCE: f32 = 24.0;
+const BODY_SLIDE_DECAY: f32 = 6.0;
+const BODY_SLIDE_STOP: f32 = 4.0;
@@ -2,12 +2,12 @@ use std::time::Duration;
 
 use bevy::{audio::{PlaybackMode, Volume}, text::{FontSmoothing, LineHeight}};
 use bevy_tweening::Tween;
-use camera::CameraController;
+use camera::{CameraController, PanCamera};
 
 use super::bossfight::*;
 use super::entity::*;
 use super::player::*;
-use crate::{miami::plugin::STATE, prelude::*};
+use crate::{miami::plugin::STATE, prelude::*, transition::RequestTransition};
 
 
 
@@ -60,13 +60,19 @@ pub fn start_freddy_enter_dialog(
     mut cmd: Commands,
     assets: Res<super::plugin::MiamiAssets>,
     cam: Query<Entity, With<WorldCamera>>,
-    player: Query<Entity, With<Player>>
+    player: Query<Entity, With<Player>>,
+    freddy: Query<Entity, With<FighterFreddy>>,
+    mut pans: MessageWriter<PanCamera>,
 ) {
     let player = player.iter().next().expect("No player!");
     let cam = cam.iter().next().expect("No cam!");
 
     cmd.entity(player).insert(PlayerDisabled);
     info!("start_freddy_enter_dialog");
+    // a look at him while he talks
+    if let Some(freddy) = freddy.iter().next() {
+        pans.write(PanCamera::to_and_back(freddy, None, FREDDY_PAN_TIME, FREDDY_PAN_HOLD));
+    }
     cmd.init_resource::<PreFreddyDialog>();
     cmd.spawn((
         DespawnOnExit(STATE),
@@ -406,8 +412,8 @@ pub fn tick_dialog(
     mut texts: Query<&mut Text, With<DialogLabel>>,
     mut cmd: Commands,
 
-    (time, keys, assets, last_screenshot): 
-    (Res<Time>, Res<ButtonInput<KeyCode>>,  Res<super::plugin::MiamiAssets>,  Res<LastScreenshot>,),
+    (time, keys, assets, mut transitions): 
+    (Res<Time>, Res<ButtonInput<KeyCode>>,  Res<super::plugin::MiamiAssets>,  MessageWriter<RequestTransition>,),
     
     (disabled_q,
     main_q,
@@ -451,11 +457,8 @@ pub fn tick_dialog(
             if final_dialog.is_some() {
                 cmd.remove_resource::<FinalDialog>();
                 // local_state.set(FreddyFightStage::Freddy);
-                if !last_screenshot.awaiting {
-                        cmd.spawn(bevy::render::view::screenshot::Screenshot::primary_window())
-                        .observe(await_screenshot_and_translate(super::plugin::NEXT_STATE));
-                    return;
-                }
+                transitions.write(RequestTransition::capture(super::plugin::NEXT_STATE));
+                return;
             }
             
             
@@ -618,4 +621,8 @@ pub fn tick_dialog(
             }
         }
     }
-}
\ No newline at end of file
+}
+
+
+const FREDDY_PAN_TIME: f32 = 1.0;
+const FREDDY_PAN_HOLD: f32 = 1.5;
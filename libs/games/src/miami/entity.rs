@@ -8,8 +8,9 @@ use room::Focusable;
 
 
 use super::{plugin::{MiamiAssets, STATE, back_body_rect, blood_rects, front_body_rect, miami_character_layers, miami_player_layers, miami_seeker_shapecast_layer, oil_blood, red_blood}, shadows::ShadowInit, weapon::{ArmedCharacter, WeaponComponents, WeaponOf, WeaponSprite, WeaponType}};
-use super::{bossfight::{BossFightStandAi, BossFightWait}, plugin::CHASER_RANDOM_RADIUS};
+use super::{bossfight::{BossFightStandAi, BossFightWait}, plugin::{CHASER_RANDOM_RADIUS, KNOCKBACK_DECAY}};
 use super::bossfight::*;
+use super::stun::KnockedDown;
 use crate::prelude::*;
 
 #[derive(Component)]
@@ -222,7 +223,12 @@ pub fn spawn_entity(
     camera_controller: &mut ResMut<CameraController>,
     mut transform : Transform,
     look_dir : Vec2
-) {
+) -> Entity {
+    let origin = MiamiSpawnOrigin {
+        entity_type: entity_type.clone(),
+        transform: transform.clone(),
+        look_dir,
+    };
     transform.translation.z = -2.0;
     let char = entity_type.to_character();
     let foot1 = cmd.spawn((
@@ -284,6 +290,7 @@ pub fn spawn_entity(
         Friction::ZERO,
         CollisionEventsEnabled,
         CharacterComponents{sprite, pivot},
+        origin,
     ));
     let id;
     if let MiamiEntity::Player = entity_type {
@@ -366,6 +373,7 @@ pub fn spawn_entity(
             transform.clone(),
             Name::new("Bottom"),
             FreddyScreen,
+            FreddyScreenPart,
             RigidBody::Static,
             Collider::rectangle(80.0, 76.0),
         ));
@@ -377,13 +385,15 @@ pub fn spawn_entity(
                 ..Default::default()
             },
             transform.clone(),
-            FreddyScreenTop
+            FreddyScreenTop,
+            FreddyScreenPart,
         ));
         cmd.spawn((
             
         ));
     }
     cmd.entity(id).add_child(pivot);
+    id
 }
 
 pub fn on_entity_spawnpoint(
@@ -400,6 +410,15 @@ pub fn on_entity_spawnpoint(
 }
 
 
+/// What a character was spawned from, so a checkpoint can bring it back.
+#[derive(Component, Clone)]
+pub struct MiamiSpawnOrigin {
+    pub entity_type: MiamiEntity,
+    pub transform: Transform,
+    pub look_dir: Vec2,
+}
+
+
 #[derive(Component)]
 pub struct CharacterSprite {
     pub default_rect: Rect,
@@ -435,6 +454,9 @@ pub struct CharacterController {
 
     pub last_impact_back: bool,
     pub last_impact_dir: Vec2,
+
+    /// Added to the input velocity and fading out, set by door hits and shoves.
+    pub knockback: Vec2,
 }
 
 impl Default for CharacterController {
@@ -458,6 +480,7 @@ impl Default for CharacterController {
             last_impact_back: false,
             last_impact_dir: Vec2::NEG_Y,
             body_offset: vec3(0.0, 22.0, 0.0),
+            knockback: Vec2::ZERO,
         }
     }
 }
@@ -476,6 +499,37 @@ pub struct ChaserAi {
 
     pub stay_time: f32,
     pub max_stay_time: f32,
+
+    pub heard: Option<Vec2>,
+    pub awareness: ChaserAwareness,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChaserAwareness {
+    #[default]
+    Calm,
+    /// Heard something and went to look.
+    Suspicious,
+    /// Saw the player.
+    Alerted,
+}
+
+/// Something loud enough for chasers in `radius` to come and look.
+#[derive(Event)]
+pub struct MiamiNoise {
+    pub position: Vec2,
+    pub radius: f32,
+}
+
+pub fn on_noise(
+    noise: On<MiamiNoise>,
+    mut chasers: Query<(&mut ChaserAi, &GlobalTransform), (Without<BossFightWait>, Without<BossFightStandAi>)>,
+) {
+    let noise = noise.event();
+    for (mut chaser, gt) in chasers.iter_mut() {
+        if gt.translation().truncate().distance(noise.position) > noise.radius {continue;}
+        chaser.heard = Some(noise.position);
+    }
 }
 
 
@@ -484,16 +538,17 @@ pub struct DummyEntity;
 
 
 pub fn update_controllers(
-    mut entities: Query<(&CharacterController, &mut LinearVelocity, &CharacterComponents)>,
+    mut entities: Query<(&mut CharacterController, &mut LinearVelocity, &CharacterComponents)>,
     mut pivots: Query<&mut Transform, With<CharacterPivotPoint>>,
     mut foots: Query<(&ChildOf, &mut Transform, &mut CharacterFoot), Without<CharacterPivotPoint>>,
     time: Res<Time>,
 ) {
     let dt = time.dt();
-    for (controller, mut velocity, c) in entities.iter_mut() {
+    for (mut controller, mut velocity, c) in entities.iter_mut() {
         let i = controller.input_dir.normalize_or_zero();
-        velocity.x = i.x * controller.speed;
-        velocity.y = i.y * controller.speed;
+        velocity.x = i.x * controller.speed + controller.knockback.x;
+        velocity.y = i.y * controller.speed + controller.knockback.y;
+        controller.knockback = controller.knockback.exp_decay(Vec2::ZERO, KNOCKBACK_DECAY, dt);
         let Ok(mut t) = pivots.get_mut(c.pivot) else {continue;};
         if controller.look_dir != Vec2::ZERO {
             t.rotation = Quat::from_rotation_z(controller.look_dir.to_angle() + std::f32::consts::FRAC_PI_2);      
@@ -524,7 +579,7 @@ pub fn update_chasers(
             &GlobalTransform, &ShapeHits, &mut ShapeCaster
         ),
         (
-            Without<DummyEntity>, Without<BossFightWait>, Without<BossFightStandAi>
+            Without<DummyEntity>, Without<BossFightWait>, Without<BossFightStandAi>, Without<KnockedDown>
         )>,
     player: Query<(Entity, &GlobalTransform), With<Player>>,
     navmeshes: Res<Assets<NavMesh>>,
@@ -556,6 +611,7 @@ pub fn update_chasers(
             }
         }
         if let Some(last_seen) = last_seen {
+            chaser.awareness = ChaserAwareness::Alerted;
             if chaser.last_seen != Some(last_seen) {
                 cmd.entity(e).remove::<CharacterInPlace>();
                 t.z = 0.0;
@@ -574,9 +630,32 @@ pub fn update_chasers(
                     }
                 );
             }
+        } else if let Some(heard) = chaser.heard.take() {
+            cmd.entity(e).remove::<CharacterInPlace>();
+            t.z = 0.0;
+            let Some(path) = navmesh.transformed_path(t, heard.extend(0.0)) else {
+                continue;
+            };
+            let Some((f, r)) = path.path.split_first() else {continue;};
+            let mut remaining = r.to_vec();
+            remaining.reverse();
+            // investigating, not attacking
+            chaser.last_seen = None;
+            if chaser.awareness == ChaserAwareness::Calm {
+                chaser.awareness = ChaserAwareness::Suspicious;
+            }
+            chaser.seek_time = 0.0;
+            controller.speed = controller.run_speed;
+            cmd.entity(e).insert(
+                Path {
+                    current: *f,
+                    next: remaining
+                }
+            );
         } else if chaser.seek_time > chaser.max_seek_time {
             chaser.seek_time = 0.0;
             chaser.last_seen = None;
+            chaser.awareness = ChaserAwareness::Calm;
             controller.look_dir = Vec2::ZERO;
             let Some(path) = navmesh.transformed_path(t, chaser.origin_point.extend(0.0)) else {
                 continue;
@@ -627,7 +706,7 @@ pub fn chase(
         &mut ChaserAi,
         Entity,
         &mut CharacterController
-    ), (Without<Player>, Without<CharacterInPlace>)>,
+    ), (Without<Player>, Without<CharacterInPlace>, Without<KnockedDown>)>,
     time: Res<Time>,
 ){
     let dt = time.dt();
@@ -0,0 +1,365 @@
+use std::f32::consts::TAU;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::color::ColorToComponents;
+use bevy::camera::{ImageRenderTarget, RenderTarget};
+use bevy::camera::visibility::{NoFrustumCulling, RenderLayers};
+use bevy::image::ImageSampler;
+use bevy::mesh::{Indices, MeshVertexBufferLayoutRef, PrimitiveTopology};
+use bevy::render::render_resource::{
+    AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState, Extent3d, RenderPipelineDescriptor,
+    SpecializedMeshPipelineError, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::shader::ShaderRef;
+use bevy::sprite_render::{AlphaMode2d, Material2d, Material2dKey};
+use camera::ViewportCanvas;
+
+use super::plugin::*;
+use super::vision::*;
+use crate::prelude::*;
+
+
+const LIGHT_SHADER_ASSET_PATH: &str = "shaders/light.wgsl";
+const LIGHT_MAP_SHADER_ASSET_PATH: &str = "shaders/light_map.wgsl";
+
+pub const MIAMI_LIGHT_LAYERS: RenderLayers = RenderLayers::layer(3);
+
+
+/// Point light, or a cone light when `cone` is under a full turn. Placed as a Tiled object.
+#[derive(Component, Reflect)]
+#[reflect(Component, Default)]
+pub struct MiamiLight {
+    pub color: Color,
+    pub intensity: f32,
+    pub radius: f32,
+    /// Opening angle in degrees, `0` or `360` for a point light.
+    pub cone: f32,
+    /// Cone direction in degrees, counter-clockwise from +x, on top of the object rotation.
+    pub direction: f32,
+    /// How far the shadow samples are spread, wider is softer.
+    pub softness: f32,
+}
+
+impl Default for MiamiLight {
+    fn default() -> Self {
+        Self {
+            color: Color::WHITE,
+            intensity: 1.0,
+            radius: 120.0,
+            cone: 0.0,
+            direction: 0.0,
+            softness: 4.0,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct MiamiLightMesh {
+    pub light: Entity,
+}
+
+#[derive(Component)]
+pub struct MuzzleFlash {
+    pub t: f32,
+}
+
+#[derive(Component)]
+pub struct LightMapCamera;
+
+#[derive(Component)]
+pub struct LightMapOverlay;
+
+/// Lights rendered by `LightMapCamera`, then multiplied over the world by `LightMapOverlay`.
+#[derive(Resource)]
+pub struct MiamiLightMap {
+    pub image: Handle<Image>,
+    pub material: Handle<LightMapMaterial>,
+    pub light_material: Handle<LightMaterial>,
+}
+
+
+/// Adds up everything drawn with it, colors come from the vertices.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct LightMaterial {}
+
+impl Material2d for LightMaterial {
+    fn fragment_shader() -> ShaderRef {
+        LIGHT_SHADER_ASSET_PATH.into()
+    }
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        set_blend(descriptor, BlendComponent {
+            src_factor: BlendFactor::One,
+            dst_factor: BlendFactor::One,
+            operation: BlendOperation::Add,
+        });
+        Ok(())
+    }
+}
+
+/// Multiplies what is already drawn by the light map.
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct LightMapMaterial {
+    #[texture(0)]
+    #[sampler(1)]
+    pub light_map: Handle<Image>,
+}
+
+impl Material2d for LightMapMaterial {
+    fn fragment_shader() -> ShaderRef {
+        LIGHT_MAP_SHADER_ASSET_PATH.into()
+    }
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        set_blend(descriptor, BlendComponent {
+            src_factor: BlendFactor::Dst,
+            dst_factor: BlendFactor::Zero,
+            operation: BlendOperation::Add,
+        });
+        Ok(())
+    }
+}
+
+fn set_blend(descriptor: &mut RenderPipelineDescriptor, color: BlendComponent) {
+    let Some(fragment) = &mut descriptor.fragment else {return;};
+    for target in fragment.targets.iter_mut().flatten() {
+        target.blend = Some(BlendState {
+            color,
+            // keep the alpha the world was drawn with
+            alpha: BlendComponent {
+                src_factor: BlendFactor::Zero,
+                dst_factor: BlendFactor::One,
+                operation: BlendOperation::Add,
+            },
+        });
+    }
+}
+
+
+pub fn setup_lighting(
+    mut cmd: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut light_map_materials: ResMut<Assets<LightMapMaterial>>,
+    mut light_materials: ResMut<Assets<LightMaterial>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    canvas: Res<ViewportCanvas>,
+    world_camera: Query<(Entity, &Projection), With<WorldCamera>>,
+) {
+    let Some((world_camera, projection)) = world_camera.iter().next() else {return;};
+    let size = light_map_size(canvas.size);
+    let mut texture = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        // half resolution and filtered, blurs the shadow edges a bit more
+        sampler: ImageSampler::linear(),
+        ..default()
+    };
+    texture.resize(size);
+    let image = images.add(texture);
+    let material = light_map_materials.add(LightMapMaterial { light_map: image.clone() });
+
+    let camera = cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("LightMapCamera"),
+        LightMapCamera,
+        Camera2d,
+        Msaa::Off,
+        Camera {
+            order: -1,
+            clear_color: ClearColorConfig::Custom(miami_ambient_light()),
+            ..Default::default()
+        },
+        RenderTarget::Image(ImageRenderTarget{ handle: image.clone(), scale_factor: 1.0 }),
+        projection.clone(),
+        Transform::default(),
+        MIAMI_LIGHT_LAYERS,
+    )).id();
+    let overlay = cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("LightMapOverlay"),
+        LightMapOverlay,
+        Mesh2d(meshes.add(Rectangle::default())),
+        MeshMaterial2d(material.clone()),
+        NoFrustumCulling,
+        Transform::from_xyz(0.0, 0.0, MIAMI_LIGHT_MAP_Z),
+        WORLD_LAYERS,
+    )).id();
+    cmd.entity(world_camera).add_children(&[camera, overlay]);
+
+    cmd.insert_resource(MiamiLightMap {
+        image,
+        material,
+        light_material: light_materials.add(LightMaterial {}),
+    });
+}
+
+fn light_map_size(canvas: Vec2) -> Extent3d {
+    Extent3d {
+        width: (canvas.x as u32 / 2).max(1),
+        height: (canvas.y as u32 / 2).max(1),
+        ..default()
+    }
+}
+
+/// Follows the world camera zoom and the canvas size.
+pub fn sync_light_map(
+    light_map: Res<MiamiLightMap>,
+    canvas: Res<ViewportCanvas>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<LightMapMaterial>>,
+    world_camera: Query<&Projection, (With<WorldCamera>, Without<LightMapCamera>)>,
+    mut light_camera: Query<&mut Projection, With<LightMapCamera>>,
+    mut overlay: Query<&mut Transform, With<LightMapOverlay>>,
+) {
+    let Some(projection) = world_camera.iter().next() else {return;};
+    let size = light_map_size(canvas.size);
+    if let Some(image) = images.get(&light_map.image) && image.texture_descriptor.size != size {
+        if let Some(image) = images.get_mut(&light_map.image) {
+            image.resize(size);
+        }
+        // the bind group still holds the old texture, setting the handle again rebuilds it
+        if let Some(material) = materials.get_mut(&light_map.material) {
+            material.light_map = light_map.image.clone();
+        }
+    }
+    for mut p in light_camera.iter_mut() {
+        *p = projection.clone();
+    }
+    let Projection::Orthographic(ortho) = projection else {return;};
+    for mut t in overlay.iter_mut() {
+        t.translation = ortho.area.center().extend(MIAMI_LIGHT_MAP_Z);
+        t.scale = ortho.area.size().extend(1.0);
+    }
+}
+
+
+pub fn on_light_added(
+    ev: On<Add, MiamiLight>,
+    mut cmd: Commands,
+    light_map: Option<Res<MiamiLightMap>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    state: Res<State<AppState>>,
+) {
+    if state.get() != &STATE {return;}
+    let Some(light_map) = light_map else {return;};
+    let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("LightMesh"),
+        MiamiLightMesh { light: ev.entity },
+        Mesh2d(meshes.add(mesh)),
+        MeshMaterial2d(light_map.light_material.clone()),
+        NoFrustumCulling,
+        Transform::default(),
+        MIAMI_LIGHT_LAYERS,
+    ));
+}
+
+/// Every light is drawn `MIAMI_LIGHT_SAMPLES` times from around its center, the overlap makes soft shadows.
+pub fn update_lights(
+    mut cmd: Commands,
+    lights: Query<(&MiamiLight, &GlobalTransform)>,
+    light_meshes: Query<(Entity, &MiamiLightMesh, &Mesh2d)>,
+    walls: Res<MiamiWalls>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (e, light_mesh, mesh) in light_meshes.iter() {
+        let Ok((light, gt)) = lights.get(light_mesh.light) else {
+            cmd.entity(e).despawn();
+            continue;
+        };
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {continue;};
+        let center = gt.translation().truncate();
+        let rotation = gt.rotation().to_euler(EulerRot::ZYX).0;
+        let arc = if light.cone <= 0.0 || light.cone >= 360.0 {TAU} else {light.cone.to_radians()};
+        let from = rotation + light.direction.to_radians() - arc * 0.5;
+        let color = light.color.to_linear() * (light.intensity / MIAMI_LIGHT_SAMPLES as f32);
+
+        let mut positions = vec![];
+        let mut colors = vec![];
+        let mut indices = vec![];
+        for k in 0..MIAMI_LIGHT_SAMPLES {
+            let offset = if k == 0 {
+                Vec2::ZERO
+            } else {
+                Vec2::from_angle(k as f32 / (MIAMI_LIGHT_SAMPLES - 1) as f32 * TAU) * light.softness
+            };
+            let origin = center + offset;
+            let points = visibility_fan(origin, from, arc, light.radius, &walls.segments, &[], |_| light.radius);
+            let first = positions.len() as u32;
+            positions.push([origin.x, origin.y, 0.0]);
+            colors.push(color.to_f32_array());
+            for p in points.iter() {
+                let falloff = (1.0 - p.distance(origin) / light.radius).max(0.0);
+                positions.push([p.x, p.y, 0.0]);
+                colors.push((color * falloff).to_f32_array());
+            }
+            let n = points.len() as u32;
+            // a cone is open between its last and first ray
+            let triangles = if arc >= TAU {n} else {n.saturating_sub(1)};
+            for i in 0..triangles {
+                indices.extend_from_slice(&[first, first + i + 1, first + (i + 1) % n + 1]);
+            }
+        }
+        let uvs = vec![[0.0, 0.0]; positions.len()];
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+        mesh.insert_indices(Indices::U32(indices));
+    }
+}
+
+
+pub fn muzzle_flash(cmd: &mut Commands, pos: Vec3, look_dir: Vec2) {
+    cmd.spawn((
+        DespawnOnExit(STATE),
+        Name::new("MuzzleFlash"),
+        MiamiLight {
+            color: muzzle_flash_color(),
+            intensity: MUZZLE_FLASH_INTENSITY,
+            radius: MUZZLE_FLASH_RADIUS,
+            softness: 0.0,
+            ..Default::default()
+        },
+        MuzzleFlash { t: MUZZLE_FLASH_TIME },
+        Transform::from_translation(pos + (look_dir * 10.0).extend(0.0)),
+    ));
+}
+
+pub fn tick_muzzle_flashes(
+    mut cmd: Commands,
+    mut flashes: Query<(Entity, &mut MuzzleFlash, &mut MiamiLight)>,
+    time: Res<Time>,
+) {
+    let dt = time.dt();
+    for (e, mut flash, mut light) in flashes.iter_mut() {
+        flash.t -= dt;
+        if flash.t <= 0.0 {
+            cmd.entity(e).despawn();
+            continue;
+        }
+        light.intensity = MUZZLE_FLASH_INTENSITY * flash.t / MUZZLE_FLASH_TIME;
+    }
+}
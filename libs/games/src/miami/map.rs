@@ -7,6 +7,7 @@ use super::entity::*;
 use super::player::*;
 use super::dialog::*;
 use super::plugin::*;
+use super::stun::*;
 use crate::prelude::*;
 
 
@@ -37,6 +38,28 @@ pub struct HorizontalDoor;
 #[reflect(Component, Default)]
 pub struct VerticalDoor;
 
+/// Hinged door body spawned from `VerticalDoor` / `HorizontalDoor`.
+#[derive(Component)]
+pub struct MiamiDoor {
+    pub hinge: Vec2,
+    pub closed_angle: f32,
+    pub state: DoorState,
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DoorState {
+    #[default]
+    Closed,
+    Open,
+    Swinging,
+}
+
+impl MiamiDoor {
+    pub fn new(hinge: Vec2, closed_angle: f32) -> Self {
+        Self { hinge, closed_angle, state: DoorState::Closed }
+    }
+}
+
 
 pub fn setup_tilemap_shadows(
     layer_created: On<TiledEvent<LayerCreated>>,
@@ -145,16 +168,18 @@ pub fn on_v_door(
     )).id();
     let door = cmd.spawn((
         Name::new("Door"),
+        MiamiDoor::new(t.translation.truncate(), 0.0),
         Transform::from_translation(t.translation - vec3(0., 16., 0.0)),
         DespawnOnExit(STATE),
         RigidBody::Dynamic,
         LinearDamping(1.0),
-        AngularDamping(1.0),
+        AngularDamping(DOOR_ANGULAR_DAMPING),
         GravityScale(0.0),
-        Mass(100.0),
+        Mass(DOOR_MASS),
         Visibility::Inherited,
         CollisionLayers::from_bits(0b101010111, 0b000010110),
         Collider::capsule(4.0, 32.0),
+        CollisionEventsEnabled,
         children![(
             Sprite {
                 image: assets.door.clone(),
@@ -162,7 +187,7 @@ pub fn on_v_door(
             },
             Transform::from_rotation(Quat::from_rotation_z(-PI / 2.)),
         )]
-    )).id();
+    )).observe(on_door_hit).id();
     cmd.spawn((
         DespawnOnExit(STATE),
         Name::new("DoorJoint"),
@@ -188,15 +213,17 @@ pub fn on_h_door(
     )).id();
     let door = cmd.spawn((
         Name::new("Door"),
+        MiamiDoor::new(t.translation.truncate(), PI / 2.),
         Transform::from_translation(t.translation - vec3(-16., 0., 0.0)).with_rotation(Quat::from_rotation_z(PI / 2.)),
         DespawnOnExit(STATE),
         RigidBody::Dynamic,
         LinearDamping(1.0),
-        AngularDamping(1.0),
+        AngularDamping(DOOR_ANGULAR_DAMPING),
         GravityScale(0.0),
-        Mass(100.0),
+        Mass(DOOR_MASS),
         CollisionLayers::from_bits(0b101010111, 0b000010110),
         Collider::capsule(4.0, 32.0),
+        CollisionEventsEnabled,
         Visibility::Inherited,
         children![(
             Sprite {
@@ -205,7 +232,7 @@ pub fn on_h_door(
             },
             Transform::from_rotation(Quat::from_rotation_z(-PI / 2.)),
         )]
-    )).id();
+    )).observe(on_door_hit).id();
     cmd.spawn((
         Name::new("DoorJoint"),
         RevoluteJoint::new(origin, door)
@@ -215,6 +242,66 @@ pub fn on_h_door(
     ));
 }
 
+/// Door swung fast enough into someone knocks them over.
+pub fn on_door_hit(
+    e: On<CollisionStart>,
+    doors: Query<(&MiamiDoor, &AngularVelocity)>,
+    mut characters: Query<(&mut CharacterController, &GlobalTransform), (Without<Player>, Without<InvincibleCharacter>)>,
+    mut cmd: Commands,
+    state: Res<State<AppState>>,
+) {
+    if state.get() != &STATE {return;}
+    let Ok((door, av)) = doors.get(e.event().collider1) else {return;};
+    let Ok((mut c, gt)) = characters.get_mut(e.event().collider2) else {return;};
+    let pos = gt.translation().truncate();
+    let r = pos - door.hinge;
+    // speed of the door leaf at the point it met the character
+    if av.0.abs() * r.length() < DOOR_KNOCKDOWN_SPEED {return;}
+    let dir = r.perp().normalize_or_zero() * av.0.signum();
+    let ld = if c.look_dir == Vec2::ZERO {Vec2::NEG_Y} else {c.look_dir};
+    c.knockback = dir * DOOR_KNOCKBACK_SPEED;
+    c.last_impact_dir = -dir;
+    c.last_impact_back = (-dir).dot(ld) < 0.0;
+    cmd.entity(e.event().collider2).insert(KnockedDown::new(DOOR_KNOCKDOWN_TIME));
+    cmd.trigger(MiamiNoise { position: pos, radius: DOOR_HIT_NOISE_RADIUS });
+}
+
+/// Closed and swinging doors are pushed through, a door resting open is an obstacle for the navmesh.
+pub fn update_doors(
+    mut doors: Query<(Entity, &mut MiamiDoor, &Transform, &AngularVelocity)>,
+    mut navmeshes: Query<&mut NavMeshUpdateMode>,
+    mut cmd: Commands,
+) {
+    let mut rebuild = false;
+    for (e, mut door, t, av) in doors.iter_mut() {
+        let angle = t.rotation.to_euler(EulerRot::ZYX).0;
+        let open = Rot2::radians(angle - door.closed_angle).as_radians().abs();
+        let state = if av.0.abs() > DOOR_SWING_SPEED {
+            DoorState::Swinging
+        } else if open < DOOR_CLOSED_ANGLE {
+            DoorState::Closed
+        } else {
+            DoorState::Open
+        };
+        if state == door.state {continue;}
+        if state == DoorState::Swinging {
+            cmd.trigger(MiamiNoise { position: t.translation.truncate(), radius: DOOR_NOISE_RADIUS });
+        }
+        if state == DoorState::Open {
+            cmd.entity(e).insert(PathfinderObstacle);
+            rebuild = true;
+        } else if door.state == DoorState::Open {
+            cmd.entity(e).remove::<PathfinderObstacle>();
+            rebuild = true;
+        }
+        door.state = state;
+    }
+    if !rebuild {return;}
+    for mut mode in navmeshes.iter_mut() {
+        *mode = NavMeshUpdateMode::OnDemand(true);
+    }
+}
+
 
 pub fn on_entrypoint_dialog_spawned(
     collider_created: On<TiledEvent<ColliderCreated>>,
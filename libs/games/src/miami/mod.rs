@@ -5,4 +5,9 @@ pub mod shadows;
 pub mod plugin;
 pub mod player;
 pub mod dialog;
-pub mod bossfight;
\ No newline at end of file
+pub mod bossfight;
+pub mod checkpoint;
+pub mod decals;
+pub mod stun;
+pub mod vision;
+pub mod lighting;
\ No newline at end of file
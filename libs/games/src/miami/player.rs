@@ -45,6 +45,7 @@ pub fn player_look_at_cursor(
     window: Single<&Window>,
     camera_q: Query<(&Camera, &GlobalTransform), With<WorldCamera>>,
     canvas: Res<camera::ViewportCanvas>,
+    mut controller: ResMut<camera::CameraController>,
 ) {
     let window = *window;
     let Some(cursor_win) = window.cursor_position() else { return; }; // top-left origin (Bevy >= 0.11)
@@ -67,6 +68,7 @@ pub fn player_look_at_cursor(
     match camera.viewport_to_world_2d(cam_transform, viewport_pos) {
         Ok(world_pos) => {
             c.look_dir = (world_pos - gt.translation().truncate()).normalize_or_zero();
+            controller.aim = c.look_dir;
         }
         _ => {}
     }
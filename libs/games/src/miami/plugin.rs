@@ -1,13 +1,10 @@
-use std::time::Duration;
-
-use avian2d::math::FRAC_PI_2;
 use bevy::audio::{PlaybackMode, Volume};
 use bevy_asset_loader::asset_collection::AssetCollection;
+use bevy::sprite_render::Material2dPlugin;
 use camera::CameraController;
 use crate::global_music::plugin::NewBgMusic;
 use crate::hints::HintAssets;
 use crate::hints::KeyHint;
-use rand::Rng;
 
 use super::weapon::*;
 use super::map::*;
@@ -16,7 +13,12 @@ use super::entity::*;
 use super::shadows::*;
 use super::player::*;
 use super::dialog::*;
-use crate::prelude::*;
+use super::checkpoint::*;
+use super::decals::*;
+use super::stun::*;
+use super::vision::*;
+use super::lighting::*;
+use crate::{prelude::*, transition::{RequestTransition, Transition, TransitionStyle}};
 
 pub const STATE: AppState = AppState::Miami;
 pub const NEXT_STATE: AppState = AppState::FakeEnd;
@@ -106,8 +108,17 @@ impl Plugin for MiamiPlugin {
             .register_type::<BossfightSpawner>()
             .register_type::<FreddySpawner>()
             .register_type::<Weapon>()
+            .register_type::<MiamiCheckpoint>()
+            .register_type::<MiamiLight>()
+
+            .add_plugins((
+                Material2dPlugin::<LightMaterial>::default(),
+                Material2dPlugin::<LightMapMaterial>::default(),
+            ))
 
             .add_sub_state::<FreddyFightStage>()
+            .init_resource::<MiamiDecalSettings>()
+            .init_resource::<MiamiVisionCones>()
 
             .add_observer(setup_tilemap_shadows)
             .add_observer(on_weapon_spawnpoint)
@@ -124,8 +135,14 @@ impl Plugin for MiamiPlugin {
             .add_observer(on_entrypoint_dialog_spawned)
             .add_observer(on_boss_entrypoint_spawned)
             .add_observer(on_boss_dialog_spawned)
+            .add_observer(on_checkpoint_spawned)
+            .add_observer(on_decal)
+            .add_observer(on_noise)
+            .add_observer(on_knocked_down)
+            .add_observer(on_get_up)
+            .add_observer(on_light_added)
             
-            .add_systems(OnEnter(STATE), setup)
+            .add_systems(OnEnter(STATE), (setup, setup_lighting))
             .add_systems(PostUpdate, setup_freddy_fight.run_if(in_state(FreddyFightStage::Idle)))
             .add_systems(OnEnter(FreddyFightStage::PreFreddy), start_freddy_enter_dialog)
             .add_systems(OnEnter(FreddyFightStage::PreFreddy), kill_endoskeletons)
@@ -133,6 +150,9 @@ impl Plugin for MiamiPlugin {
             .add_systems(OnEnter(FreddyFightStage::Finished), kill_endoskeletons)
             .add_systems(PostUpdate, tick_bonnie_chicka_fight.run_if(in_state(FreddyFightStage::BonnieChicka)))
             .add_systems(PostUpdate, tick_freddy_fight.run_if(in_state(FreddyFightStage::Freddy)))
+            .add_systems(PostUpdate, resume_bossfight
+                .after(setup_freddy_fight)
+                .run_if(in_state(FreddyFightStage::Idle).and(resource_exists::<ResumeBossfight>)))
             .add_systems(Update, (
                 bonnie_chicka_fight_attack,
                 
@@ -143,13 +163,14 @@ impl Plugin for MiamiPlugin {
                 update_projectile,
                 player_look_at_cursor,
                 update_controllers,
-                (control_player, shoot, throw_weapon).chain(),
+                (control_player, player_melee, shoot, throw_weapon).chain(),
                 tick_thrown,
 
                 (update_chasers, chase, player_health_watcher),
+                tick_knocked_down,
+                update_doors,
                 
                 tick_dialog,
-                update_screenshot,
 
                 display_path,
                 
@@ -175,6 +196,12 @@ impl Plugin for MiamiPlugin {
             // ).after(TransformSystems::Propagate).run_if(in_state(STATE)))
             .add_systems(Update, (
                 tick,
+                quick_restart,
+                (slide_bodies, bake_decals).chain(),
+                toggle_vision_cones,
+                (collect_walls, spawn_vision_cones, update_vision_cones).chain(),
+                (tick_muzzle_flashes, update_lights).chain().after(collect_walls),
+                sync_light_map.run_if(resource_exists::<MiamiLightMap>),
             ).run_if(in_state(STATE)))
             .add_systems(OnExit(STATE), cleanup)
             ;
@@ -182,9 +209,6 @@ impl Plugin for MiamiPlugin {
 }
 
 
-#[derive(Component)]
-pub struct MiamiScreenshot(f32);
-
 #[derive(Resource, Default)]
 pub struct MiamiTransitionShooted;
 
@@ -200,10 +224,12 @@ fn setup(
     cam: Query<Entity, With<WorldCamera>>,
     hint_assets: Res<HintAssets>,
 ){
-    cmd.init_resource::<CameraShake>();
+    cmd.init_resource::<MiamiDecalChunks>();
+    cmd.init_resource::<MiamiWalls>();
     let cam = cam.iter().next().expect("No cam!");
     camera_controller.follow_speed = 0.9;
-    camera_controller.target_zoom = 0.9;
+    camera_controller.target_zoom = MIAMI_CAMERA_ZOOM;
+    camera_controller.look_ahead = MIAMI_LOOK_AHEAD;
     // cmd.init_resource::<SinceObstacle>();
     crate::hints::show_hints(
         &mut cmd,
@@ -226,16 +252,8 @@ fn setup(
     if completed.is_some() {return;}
     cmd.init_resource::<MiamiTransitionShooted>();
     let Some(screenshot) = last.image.clone() else {return;};
-    let tween = Tween::new(
-        EaseFunction::QuinticOut,
-        Duration::from_secs_f32(SCREENSHOT_TRANSITION_TIME),
-        TransformRotationLens {
-            start: Quat::from_rotation_x(0.0),
-            end: Quat::from_rotation_x(FRAC_PI_2),
-        }
-    );
     cmd.spawn((
-        Name::new("Screenshot"),
+        Name::new("Powerup sound"),
         DespawnOnExit(STATE),
         PlaybackSettings{
             mode: PlaybackMode::Once,
@@ -243,42 +261,14 @@ fn setup(
             ..default()
         },
         AudioPlayer::new(assets.powerup_sound.clone()),
-        TweenAnim::new(tween),
-        MiamiScreenshot(0.0),
-        Transform::from_translation(Vec3::new(0.0, 0.0, 500.0)),
-        Sprite {
-            image: screenshot,
-            ..Default::default()
-        },
-        HIGHRES_LAYERS,
     ));
-    
+    cmd.spawn(Transition::new(screenshot, TransitionStyle::Shatter, SCREENSHOT_TRANSITION_TIME));
 }
 
 
 const SCREENSHOT_TRANSITION_TIME: f32 = 1.0;
 
 
-fn update_screenshot(
-    mut screenshot: Query<(Entity, &mut MiamiScreenshot)>,
-    mut cmd: Commands,
-    dt: Res<Time>,
-    p_q: Query<Entity, (With<Player>, With<PlayerDisabled>)>,
-){
-    if screenshot.iter().len() == 0 {return;}
-
-    let dt = dt.dt();
-    for (e, mut s) in screenshot.iter_mut() {
-        s.0 += dt;
-        if s.0 > SCREENSHOT_TRANSITION_TIME {
-            cmd.entity(e).despawn();
-            let Some(player) = p_q.iter().next() else {continue;};
-            cmd.entity(player).remove::<PlayerDisabled>();
-        }
-    }
-}
-
-
 fn on_map_created(
     _event: On<TiledEvent<TilemapCreated>>,
     state: Res<State<AppState>>,
@@ -292,50 +282,12 @@ fn on_map_created(
 
 
 
-pub const SHAKE_STRENGTH: f32 = 6.0;
-pub const SHAKE_DURATION: f32 = 0.25;
-
-#[derive(Resource, Default)]
-pub struct CameraShake {
-    pub timer: f32,
-    pub strength: f32,
-}
-
-
 fn tick(
-    time: Res<Time>,
-    mut camera: Query<&mut Transform, With<WorldCamera>>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut shake: ResMut<CameraShake>,
-    mut cmd: Commands,
-    last_screenshot: Res<LastScreenshot>,
+    mut transitions: MessageWriter<RequestTransition>,
 ) {
     if keyboard_input.just_pressed(KeyCode::Enter) {
-        if !last_screenshot.awaiting {
-            cmd.spawn(bevy::render::view::screenshot::Screenshot::primary_window())
-                .observe(await_screenshot_and_translate(super::plugin::NEXT_STATE));
-            return;
-        }
-    }
-
-    // if keyboard_input.just_pressed(KeyCode::ShiftRight) {
-    //     shake.timer = SHAKE_DURATION;
-    // }
-
-    let Some(mut t) = camera.iter_mut().next() else { return; };
-
-    if shake.timer > 0.0 {
-        shake.timer -= time.delta_secs();
-        let k = shake.timer / SHAKE_DURATION; // затухание 1 → 0
-
-        let mut rng = rand::thread_rng();
-        let offset = Vec3::new(
-            rng.gen_range(-1.0..1.0),
-            rng.gen_range(-1.0..1.0),
-            0.0,
-        ) * shake.strength * k;
-
-        t.translation += offset;
+        transitions.write(RequestTransition::capture(NEXT_STATE));
     }
 }
 
@@ -349,10 +301,17 @@ fn cleanup(
     cmd.remove_resource::<ShootedDialogs>();
     cmd.remove_resource::<BossfightDialog>();
     cmd.remove_resource::<FinalDialog>();
-    cmd.remove_resource::<CameraShake>();
+    cmd.remove_resource::<MiamiCheckpointSnapshot>();
+    cmd.remove_resource::<MiamiDecalChunks>();
+    cmd.remove_resource::<MiamiWalls>();
+    cmd.remove_resource::<MiamiLightMap>();
+    cmd.remove_resource::<ResumeBossfight>();
 
     controller.follow_speed = 0.0;
     controller.target_zoom = 0.8;
+    controller.aim = Vec2::ZERO;
+    controller.look_ahead = 0.0;
+    controller.cancel_pan();
     
     let Ok(mut t) = camera.single_mut() else {return;};
     t.rotation.z = 0.0;
@@ -367,20 +326,18 @@ pub struct PlayerZeroHealthTicker(pub f32);
 pub fn player_health_watcher(
     zh: Option<ResMut<PlayerZeroHealthTicker>>,
     time: Res<Time>,
-    mut screenshot: ResMut<LastScreenshot>,
-    mut cmd: Commands,
+    mut transitions: MessageWriter<RequestTransition>,
+    checkpoint: Option<Res<MiamiCheckpointSnapshot>>,
 ) {
     let Some(mut zh) = zh else {
         return;
     };
     let dt = time.dt();
     zh.0 += dt;
+    // with a checkpoint behind, wait for the quick restart instead of restarting the whole level
+    if checkpoint.is_some() {return;}
     if zh.0 > DEFEAT_TIME {
-        if screenshot.awaiting == false {
-            cmd.spawn(bevy::render::view::screenshot::Screenshot::primary_window())
-                .observe(await_screenshot_and_translate(AppState::Defeat));
-            screenshot.awaiting = true;
-        }
+        transitions.write(RequestTransition::capture(AppState::Defeat));
     }
 }
 
@@ -403,6 +360,13 @@ pub fn blood_rects() -> [Rect; 3] {
         Rect::new(0.0, 0.0, 32.0, 32.0),
     ]
 }
+pub fn vision_cone_calm_color() ->       Color {Color::Srgba(Srgba::rgba_u8(255, 255, 255, 40))}
+pub fn vision_cone_suspicious_color() -> Color {Color::Srgba(Srgba::rgba_u8(255, 200, 40, 60))}
+pub fn vision_cone_alerted_color() ->    Color {Color::Srgba(Srgba::rgba_u8(230, 30, 50, 70))}
+pub fn miami_ambient_light() ->          Color {Color::linear_rgb(0.75, 0.7, 0.85)}
+pub fn muzzle_flash_color() ->          Color {Color::Srgba(Srgba::rgba_u8(255, 210, 120, 255))}
+pub fn shell_casing_rect() -> Rect {Rect::new(0.0, 0.0, 1.0, 3.0)}
+pub fn shell_casing_color() -> Color {Color::Srgba(Srgba::rgba_u8(222, 180, 80, 255))}
 pub fn front_body_rect() -> Rect {Rect::new(48.0, 0.0, 80.0, 64.0)}
 pub fn back_body_rect() -> Rect {Rect::new(80.0, 0.0, 112.0, 64.0)}
 
@@ -412,7 +376,50 @@ pub const THROWN_DAMAGE_MULTIPLIER: f32 = 0.0071428571;
 
 pub const DEFEAT_TIME: f32 = 1.0;
 
+pub const MIAMI_CAMERA_ZOOM: f32 = 0.9;
+/// How far the camera leads the player towards the cursor.
+pub const MIAMI_LOOK_AHEAD: f32 = 24.0;
+pub const MIAMI_QUICK_RESTART_KEY: KeyCode = KeyCode::KeyR;
+pub const MIAMI_VISION_CONES_KEY: KeyCode = KeyCode::KeyV;
+
+/// Between the decals and the characters.
+pub const MIAMI_VISION_CONE_Z: f32 = -3.0;
+pub const VISION_CONE_RAYS: usize = 64;
+pub const VISION_CONE_EPSILON: f32 = 0.0005;
+
+/// In front of the whole world, relative to the world camera.
+pub const MIAMI_LIGHT_MAP_Z: f32 = 900.0;
+pub const MIAMI_LIGHT_SAMPLES: usize = 4;
+pub const MUZZLE_FLASH_INTENSITY: f32 = 1.5;
+pub const MUZZLE_FLASH_RADIUS: f32 = 90.0;
+pub const MUZZLE_FLASH_TIME: f32 = 0.06;
+
 pub const CHASER_RANDOM_RADIUS: f32 = 500.0;
+pub const KNOCKBACK_DECAY: f32 = 8.0;
+
+pub const DOOR_MASS: f32 = 20.0;
+pub const DOOR_ANGULAR_DAMPING: f32 = 3.0;
+/// Door leaf speed at the contact point, units per second.
+pub const DOOR_KNOCKDOWN_SPEED: f32 = 90.0;
+pub const DOOR_KNOCKBACK_SPEED: f32 = 180.0;
+pub const DOOR_KNOCKDOWN_TIME: f32 = 2.0;
+pub const DOOR_SWING_SPEED: f32 = 1.5;
+pub const DOOR_CLOSED_ANGLE: f32 = 0.15;
+pub const DOOR_NOISE_RADIUS: f32 = 150.0;
+pub const DOOR_HIT_NOISE_RADIUS: f32 = 250.0;
+
+pub const THROWN_KNOCKDOWN_SPEED: f32 = 200.0;
+pub const THROWN_KNOCKDOWN_TIME: f32 = 2.5;
+pub const SHOVE_RANGE: f32 = 24.0;
+/// Minimal dot product between the player's look and the direction to the shoved enemy.
+pub const SHOVE_CONE: f32 = 0.5;
+pub const SHOVE_KNOCKBACK_SPEED: f32 = 150.0;
+pub const SHOVE_KNOCKDOWN_TIME: f32 = 1.5;
+pub const KNOCKDOWN_ALERT_INTERVAL: f32 = 1.0;
+pub const KNOCKDOWN_NOISE_RADIUS: f32 = 200.0;
+pub const EXECUTION_RANGE: f32 = 24.0;
+pub const EXECUTION_TIME: f32 = 1.2;
+pub const EXECUTION_UNARMED_HITS: u32 = 3;
 
 pub const SHOTGUN_BULLET_RADIUS: f32 = 0.4;
 pub const SHOTGUN_BULLET_COUNT: usize = 6;
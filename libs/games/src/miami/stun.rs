@@ -0,0 +1,184 @@
+use super::decals::*;
+use super::entity::*;
+use super::player::PlayerDisabled;
+use super::plugin::*;
+use super::weapon::*;
+use crate::prelude::*;
+
+
+/// Enemy lying on the floor, AI and input are ignored until it gets up after `t`.
+#[derive(Component)]
+pub struct KnockedDown {
+    pub t: f32,
+    /// Time left until the next noise calling the allies around.
+    pub alert: f32,
+}
+
+impl KnockedDown {
+    pub fn new(t: f32) -> Self {
+        Self { t, alert: 0.0 }
+    }
+}
+
+/// Player finishing off a knocked down enemy for as long as Fire is held.
+#[derive(Component)]
+pub struct Execution {
+    pub target: Entity,
+    pub t: f32,
+    pub hits: u32,
+}
+
+/// Keeps a knocked down enemy on the floor while it is being executed.
+#[derive(Component)]
+pub struct BeingExecuted;
+
+
+pub fn on_knocked_down(
+    ev: On<Add, KnockedDown>,
+    characters: Query<(&CharacterController, &CharacterComponents)>,
+    mut sprites: Query<(&mut Sprite, &mut Transform), With<CharacterSprite>>,
+) {
+    let Ok((controller, components)) = characters.get(ev.entity) else {return;};
+    let Ok((mut sprite, mut t)) = sprites.get_mut(components.sprite) else {return;};
+    let rect = if controller.last_impact_back {controller.back_body_rect} else {controller.front_body_rect};
+    sprite.rect = Some(rect);
+    // pivot space looks down -y, so the body lies behind the character like a dead one would
+    t.translation = vec3(controller.body_offset.x, -controller.body_offset.y, 0.0);
+}
+
+pub fn on_get_up(
+    ev: On<Remove, KnockedDown>,
+    mut characters: Query<(&CharacterComponents, Option<&mut ChaserAi>)>,
+    mut sprites: Query<(&mut Sprite, &mut Transform, &CharacterSprite)>,
+    mut cmd: Commands,
+) {
+    let Ok((components, chaser)) = characters.get_mut(ev.entity) else {return;};
+    // its own alert noises are not worth investigating
+    if let Some(mut chaser) = chaser {
+        chaser.heard = None;
+    }
+    cmd.entity(ev.entity).try_remove::<BeingExecuted>();
+    let Ok((mut sprite, mut t, character)) = sprites.get_mut(components.sprite) else {return;};
+    sprite.rect = Some(character.default_rect.clone());
+    t.translation = character.default_offset.clone();
+}
+
+pub fn tick_knocked_down(
+    mut cmd: Commands,
+    mut q: Query<(Entity, &mut KnockedDown, &mut CharacterController, &GlobalTransform, Has<BeingExecuted>)>,
+    time: Res<Time>,
+) {
+    let dt = time.dt();
+    for (e, mut down, mut controller, gt, executed) in q.iter_mut() {
+        controller.input_dir = Vec2::ZERO;
+        controller.shoot = false;
+        controller.throw = false;
+        down.alert -= dt;
+        if down.alert <= 0.0 {
+            down.alert = KNOCKDOWN_ALERT_INTERVAL;
+            cmd.trigger(MiamiNoise { position: gt.translation().truncate(), radius: KNOCKDOWN_NOISE_RADIUS });
+        }
+        if executed {continue;}
+        down.t -= dt;
+        if down.t <= 0.0 {
+            cmd.entity(e).remove::<KnockedDown>();
+        }
+    }
+}
+
+
+/// Fire next to a knocked down enemy starts an execution, with bare hands it shoves the enemy in front.
+pub fn player_melee(
+    mut cmd: Commands,
+    player: Single<
+        (Entity, &mut CharacterController, &GlobalTransform, Option<&ArmedCharacter>, Option<&mut Execution>),
+        (With<Player>, Without<PlayerDisabled>)
+    >,
+    mut enemies: Query<
+        (Entity, &mut CharacterController, &GlobalTransform, Has<KnockedDown>),
+        (Without<Player>, Without<InvincibleCharacter>)
+    >,
+    weapons: Query<(&Weapon, &WeaponComponents)>,
+    mut weapon_sprites: Query<(&mut Sprite, &mut Transform), With<WeaponSprite>>,
+    assets: Res<MiamiAssets>,
+    time: Res<Time>,
+) {
+    let (pe, mut pc, pt, armed, execution) = player.into_inner();
+    let pos = pt.translation().truncate();
+
+    if let Some(mut ex) = execution {
+        let target = ex.target;
+        let Ok((_, mut tc, tt, _)) = enemies.get_mut(target) else {
+            cmd.entity(pe).remove::<Execution>();
+            return;
+        };
+        let tpos = tt.translation().truncate();
+        if !pc.holding_shoot || tpos.distance(pos) > EXECUTION_RANGE * 1.5 {
+            cmd.entity(pe).remove::<Execution>();
+            cmd.entity(target).remove::<BeingExecuted>();
+            return;
+        }
+        pc.input_dir = Vec2::ZERO;
+        pc.shoot = false;
+        pc.holding_shoot = false;
+        pc.throw = false;
+        pc.look_dir = (tpos - pos).normalize_or_zero();
+        ex.t += time.dt();
+
+        let weapon = armed.and_then(|a| weapons.get(a.0).ok());
+        let frames = weapon.map(|(w, _)| w.execution_frames).unwrap_or(EXECUTION_UNARMED_HITS).max(1);
+        let frame = (((ex.t / EXECUTION_TIME) * frames as f32) as u32).min(frames - 1);
+        if let Some((w, wc)) = weapon && w.execution_frames > 0
+        && let Ok((mut sprite, mut t)) = weapon_sprites.get_mut(wc.sprite) {
+            let mut rect = w.execution_rect.clone();
+            let offset = rect.width() * frame as f32;
+            rect.min.x += offset;
+            rect.max.x += offset;
+            sprite.rect = Some(rect);
+            t.translation = w.held_offset.clone();
+        }
+        // every new frame lands a hit
+        if frame + 1 > ex.hits {
+            ex.hits = frame + 1;
+            tc.last_impact_dir = -pc.look_dir;
+            blood_splatter(&mut cmd, &assets, &tc.blood_rects, tc.blood_color, tpos, pc.look_dir, 1);
+        }
+        if ex.t >= EXECUTION_TIME {
+            // health_watcher turns it into a body
+            tc.hp = 0.0;
+            cmd.entity(pe).remove::<Execution>();
+            info!("Executed {:?}", tc.character);
+        }
+        return;
+    }
+
+    if !pc.shoot {return;}
+    let down = enemies.iter()
+        .filter(|(_, _, t, down)| *down && t.translation().truncate().distance(pos) < EXECUTION_RANGE)
+        .min_by(|(_, _, a, _), (_, _, b, _)| {
+            a.translation().truncate().distance(pos).total_cmp(&b.translation().truncate().distance(pos))
+        })
+        .map(|(e, _, _, _)| e);
+    if let Some(target) = down {
+        pc.shoot = false;
+        pc.holding_shoot = false;
+        cmd.entity(pe).insert(Execution { target, t: 0.0, hits: 0 });
+        cmd.entity(target).insert(BeingExecuted);
+        return;
+    }
+
+    if armed.is_some() {return;}
+    for (e, mut c, t, down) in enemies.iter_mut() {
+        if down {continue;}
+        let d = t.translation().truncate() - pos;
+        if d.length() > SHOVE_RANGE {continue;}
+        let dir = d.normalize_or_zero();
+        if dir.dot(pc.look_dir) < SHOVE_CONE {continue;}
+        let ld = if c.look_dir == Vec2::ZERO {Vec2::NEG_Y} else {c.look_dir};
+        c.knockback = dir * SHOVE_KNOCKBACK_SPEED;
+        c.last_impact_dir = -dir;
+        c.last_impact_back = (-dir).dot(ld) < 0.0;
+        cmd.entity(e).insert(KnockedDown::new(SHOVE_KNOCKDOWN_TIME));
+        break;
+    }
+}
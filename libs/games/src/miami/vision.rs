@@ -0,0 +1,243 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::asset::RenderAssetUsages;
+use bevy::color::ColorToComponents;
+use bevy::camera::visibility::NoFrustumCulling;
+use bevy::mesh::{Indices, PrimitiveTopology};
+
+use super::bossfight::{BossFightStandAi, BossFightWait};
+use super::entity::*;
+use super::plugin::*;
+use super::stun::KnockedDown;
+use crate::prelude::*;
+
+
+/// Stealth readability mode, draws what every chaser can see on the floor.
+#[derive(Resource, Default)]
+pub struct MiamiVisionCones {
+    pub enabled: bool,
+}
+
+/// World space wall edges taken from the tilemap colliders, what vision cones are clipped by.
+#[derive(Resource, Default)]
+pub struct MiamiWalls {
+    pub segments: Vec<(Vec2, Vec2)>,
+}
+
+#[derive(Component)]
+pub struct VisionCone {
+    pub owner: Entity,
+}
+
+#[derive(Component)]
+pub struct HasVisionCone(pub Entity);
+
+
+pub fn toggle_vision_cones(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut cones: ResMut<MiamiVisionCones>,
+) {
+    if !keyboard_input.just_pressed(MIAMI_VISION_CONES_KEY) {return;}
+    cones.enabled = !cones.enabled;
+    info!("Vision cones: {}", cones.enabled);
+}
+
+pub fn collect_walls(
+    changed: Query<(), (With<TiledColliderPolygons>, Changed<GlobalTransform>)>,
+    colliders: Query<(&TiledColliderPolygons, &GlobalTransform), Without<Sensor>>,
+    mut walls: ResMut<MiamiWalls>,
+) {
+    if changed.is_empty() {return;}
+    walls.segments.clear();
+    for (polygons, gt) in colliders.iter() {
+        for polygon in polygons.0.iter() {
+            for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+                for line in ring.lines() {
+                    let a = gt.transform_point(vec3(line.start.x, line.start.y, 0.0)).truncate();
+                    let b = gt.transform_point(vec3(line.end.x, line.end.y, 0.0)).truncate();
+                    walls.segments.push((a, b));
+                }
+            }
+        }
+    }
+}
+
+
+pub fn spawn_vision_cones(
+    mut cmd: Commands,
+    settings: Res<MiamiVisionCones>,
+    chasers: Query<Entity, (With<ChaserAi>, Without<HasVisionCone>)>,
+    cones: Query<(Entity, &VisionCone)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut material: Local<Option<Handle<ColorMaterial>>>,
+) {
+    if !settings.enabled {
+        for (e, cone) in cones.iter() {
+            cmd.entity(e).despawn();
+            cmd.entity(cone.owner).try_remove::<HasVisionCone>();
+        }
+        return;
+    }
+    // colors come from the vertices
+    let material = material.get_or_insert_with(|| materials.add(ColorMaterial::from_color(Color::WHITE))).clone();
+    for owner in chasers.iter() {
+        let mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default());
+        let cone = cmd.spawn((
+            DespawnOnExit(STATE),
+            Name::new("VisionCone"),
+            VisionCone { owner },
+            Mesh2d(meshes.add(mesh)),
+            MeshMaterial2d(material.clone()),
+            NoFrustumCulling,
+            Transform::from_xyz(0.0, 0.0, MIAMI_VISION_CONE_Z),
+        )).id();
+        cmd.entity(owner).insert(HasVisionCone(cone));
+    }
+}
+
+pub fn update_vision_cones(
+    mut cmd: Commands,
+    settings: Res<MiamiVisionCones>,
+    walls: Res<MiamiWalls>,
+    cones: Query<(Entity, &VisionCone, &Mesh2d)>,
+    chasers: Query<
+        (&ChaserAi, &CharacterController, &GlobalTransform, Has<KnockedDown>),
+        (Without<DummyEntity>, Without<BossFightWait>, Without<BossFightStandAi>)
+    >,
+    alive: Query<(), With<ChaserAi>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if !settings.enabled {return;}
+    for (e, cone, mesh) in cones.iter() {
+        if !alive.contains(cone.owner) {
+            cmd.entity(e).despawn();
+            continue;
+        }
+        let Some(mesh) = meshes.get_mut(&mesh.0) else {continue;};
+        // waiting bosses and knocked down enemies are not watching
+        let Ok((chaser, controller, gt, false)) = chasers.get(cone.owner) else {
+            set_fan(mesh, Vec2::ZERO, &[], Color::NONE);
+            continue;
+        };
+        let origin = gt.translation().truncate();
+        let look = if controller.look_dir == Vec2::ZERO {Vec2::NEG_Y} else {controller.look_dir.normalize()};
+        let points = visibility_polygon(origin, look, chaser.seek_range, chaser.attention_range, &walls.segments);
+        let color = match chaser.awareness {
+            ChaserAwareness::Calm => vision_cone_calm_color(),
+            ChaserAwareness::Suspicious => vision_cone_suspicious_color(),
+            ChaserAwareness::Alerted => vision_cone_alerted_color(),
+        };
+        set_fan(mesh, origin, &points, color);
+    }
+}
+
+
+/// Same shape `update_chasers` detects the player in: `seek_range` in front, `attention_range` behind,
+/// cut by every wall segment in between.
+pub fn visibility_polygon(
+    origin: Vec2,
+    look: Vec2,
+    seek_range: f32,
+    attention_range: f32,
+    segments: &[(Vec2, Vec2)],
+) -> Vec<Vec2> {
+    let base = look.to_angle();
+    // the step between the two ranges
+    let sides = [base - FRAC_PI_2, base + FRAC_PI_2];
+    visibility_fan(origin, 0.0, TAU, seek_range.max(attention_range), segments, &sides, |dir| {
+        if dir.dot(look) >= 0.0 {seek_range} else {attention_range}
+    })
+}
+
+/// Ray ends from `origin` over `[from, from + arc]`, a full turn when `arc >= TAU`, stopped by the walls.
+/// `range` gives the length of a ray by its direction and must stay under `max_range`.
+pub fn visibility_fan(
+    origin: Vec2,
+    from: f32,
+    arc: f32,
+    max_range: f32,
+    segments: &[(Vec2, Vec2)],
+    extra_angles: &[f32],
+    range: impl Fn(Vec2) -> f32,
+) -> Vec<Vec2> {
+    let walls: Vec<(Vec2, Vec2)> = segments.iter()
+        .filter(|(a, b)| segment_distance(origin, *a, *b) < max_range)
+        .cloned()
+        .collect();
+    let arc = arc.min(TAU);
+
+    let mut angles: Vec<f32> = (0..=VISION_CONE_RAYS)
+        .map(|i| i as f32 / VISION_CONE_RAYS as f32 * arc)
+        .collect();
+    for side in extra_angles {
+        angles.push(side - from - VISION_CONE_EPSILON);
+        angles.push(side - from + VISION_CONE_EPSILON);
+    }
+    // rays just past each corner so the shadow edges land on them
+    for (a, b) in walls.iter() {
+        for p in [a, b] {
+            if p.distance(origin) > max_range {continue;}
+            let angle = (*p - origin).to_angle() - from;
+            angles.push(angle - VISION_CONE_EPSILON);
+            angles.push(angle);
+            angles.push(angle + VISION_CONE_EPSILON);
+        }
+    }
+    let mut angles: Vec<f32> = angles.into_iter()
+        .map(|a| if a == arc {a} else {a.rem_euclid(TAU)})
+        .filter(|a| *a <= arc)
+        .collect();
+    angles.sort_by(f32::total_cmp);
+
+    angles.into_iter().map(|angle| {
+        let dir = Vec2::from_angle(from + angle);
+        origin + dir * ray_distance(origin, dir, range(dir), &walls)
+    }).collect()
+}
+
+fn ray_distance(origin: Vec2, dir: Vec2, max: f32, walls: &[(Vec2, Vec2)]) -> f32 {
+    let mut best = max;
+    for (a, b) in walls {
+        let e = *b - *a;
+        let denom = dir.perp_dot(e);
+        if denom.abs() < f32::EPSILON {continue;}
+        let d = *a - origin;
+        let t = d.perp_dot(e) / denom;
+        let u = d.perp_dot(dir) / denom;
+        if t >= 0.0 && t < best && (0.0..=1.0).contains(&u) {
+            best = t;
+        }
+    }
+    best
+}
+
+fn segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let len = ab.length_squared();
+    if len == 0.0 {return p.distance(a);}
+    let t = ((p - a).dot(ab) / len).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+/// Triangle fan around `origin`, faded towards the edges.
+fn set_fan(mesh: &mut Mesh, origin: Vec2, points: &[Vec2], color: Color) {
+    let center = color.to_linear().to_f32_array();
+    let edge = color.with_alpha(color.alpha() * 0.3).to_linear().to_f32_array();
+    let mut positions = vec![[origin.x, origin.y, 0.0]];
+    let mut colors = vec![center];
+    for p in points {
+        positions.push([p.x, p.y, 0.0]);
+        colors.push(edge);
+    }
+    let n = points.len() as u32;
+    let mut indices = Vec::with_capacity(points.len() * 3);
+    for i in 0..n {
+        indices.extend_from_slice(&[0, i + 1, (i + 1) % n + 1]);
+    }
+    let uvs = vec![[0.0, 0.0]; positions.len()];
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors);
+    mesh.insert_indices(Indices::U32(indices));
+}
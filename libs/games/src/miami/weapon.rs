@@ -9,9 +9,13 @@ use super::entity::*;
 use super::plugin::*;
 use rand::Rng;
 use super::entity::{CharacterComponents, CharacterController, CharacterPivotPoint, CharacterSprite, Player};
-use super::plugin::{BLOOD_Z_TRANSLATION, BODY_Z_TRANSLATION, THROWN_DAMAGE_MULTIPLIER, miami_dropped_weapon_layers, miami_pickup_weapon_layers, miami_projectile_damager_layer, miami_projectile_player_layer};
+use super::plugin::{BODY_Z_TRANSLATION, THROWN_DAMAGE_MULTIPLIER, miami_dropped_weapon_layers, miami_pickup_weapon_layers, miami_projectile_damager_layer, miami_projectile_player_layer};
 use super::shadows::ShadowCaster;
+use super::decals::*;
+use super::stun::*;
+use super::lighting::muzzle_flash;
 use crate::pathfinder::plugin::PathfinderObstacle;
+use camera::ShakeCamera;
 use crate::prelude::*;
 use super::{plugin::{MiamiAssets, STATE}, shadows::ShadowInit};
 
@@ -71,6 +75,10 @@ pub struct Weapon {
 
     pub throw_damage: f32,
 
+    /// First frame of the execution row, the rest follow to the right.
+    pub execution_rect: Rect,
+    pub execution_frames: u32,
+
     pub weapon_type: WeaponType,
 }
 #[derive(Component)]
@@ -186,6 +194,9 @@ impl WeaponType {
                 projectile_speed: 500.0,
                 throw_damage: 100.0,
 
+                execution_rect: Rect::new(0.0, 128.0, 16.0, 144.0),
+                execution_frames: 4,
+
                 ..Default::default()
             },
             WeaponType::GoldenPistol => Weapon {
@@ -216,6 +227,9 @@ impl WeaponType {
                 projectile_speed: 700.0,
                 throw_damage: 150.0,
 
+                execution_rect: Rect::new(0.0, 144.0, 16.0, 160.0),
+                execution_frames: 4,
+
                 ..Default::default()
             },
             WeaponType::Shotgun => Weapon {
@@ -246,6 +260,9 @@ impl WeaponType {
                 projectile_speed: 700.0,
                 throw_damage: 100.0,
 
+                execution_rect: Rect::new(0.0, 176.0, 16.0, 208.0),
+                execution_frames: 4,
+
                 ..Default::default()
             },
             WeaponType::Uzi => Weapon {
@@ -276,6 +293,9 @@ impl WeaponType {
                 projectile_speed: 700.0,
                 throw_damage: 100.0,
 
+                execution_rect: Rect::new(0.0, 160.0, 16.0, 176.0),
+                execution_frames: 4,
+
                 ..Default::default()
             },
             WeaponType::Axe => Weapon {
@@ -304,6 +324,9 @@ impl WeaponType {
                 piercing: 128,
                 throw_damage: 400.0,
 
+                execution_rect: Rect::new(0.0, 208.0, 32.0, 224.0),
+                execution_frames: 2,
+
                 ..Default::default()
             },
             WeaponType::Baguette => Weapon {
@@ -332,6 +355,9 @@ impl WeaponType {
                 
                 throw_damage: 500.0,
 
+                execution_rect: Rect::new(0.0, 224.0, 32.0, 240.0),
+                execution_frames: 2,
+
                 ..Default::default()
             },
             WeaponType::EnemyFists => Weapon {
@@ -434,7 +460,7 @@ impl Weapon {
         pos: Vec3,
         is_enemy: bool,
         assets: &Res<MiamiAssets>,
-        shake: &mut ResMut<CameraShake>,
+        shake: &mut MessageWriter<ShakeCamera>,
     ) -> bool {
         let mut rng = rand::rng();
         let mut p = Projectile {
@@ -468,26 +494,24 @@ impl Weapon {
 
         match weapon.weapon_type {
             WeaponType::Shotgun => {
-                shake.timer = SHAKE_DURATION;
-                shake.strength = 6.0;
+                shake.write(ShakeCamera {trauma: 0.85});
             },
             WeaponType::Pistol | WeaponType::GoldenPistol => {
-                shake.timer = SHAKE_DURATION;
-                shake.strength = 4.0;
+                shake.write(ShakeCamera {trauma: 0.7});
             }
             WeaponType::Uzi => {
-                shake.timer = SHAKE_DURATION;
-                shake.strength = 3.0;
+                shake.write(ShakeCamera {trauma: 0.6});
             }
             WeaponType::Axe | WeaponType::Baguette => {
-                // shake.timer = SHAKE_DURATION;
-                // shake.strength = 0.0;
+                // shake.write(ShakeCamera {trauma: 0.0});
             },
             _ => {}
         }
 
         match self.weapon_type {
             WeaponType::Pistol | WeaponType::GoldenPistol => {
+                eject_shell_casing(cmd, assets, pos, controller.look_dir);
+                muzzle_flash(cmd, pos, controller.look_dir);
                 let mut t = Transform::from_translation(pos - Vec3::new(controller.look_dir.x, controller.look_dir.y, 0.0) * 10.0);
                 t.rotation = Quat::from_rotation_z(controller.look_dir.to_angle() - std::f32::consts::FRAC_PI_2);
                 p.despawn_on_wall = true;
@@ -567,6 +591,8 @@ impl Weapon {
             }
             WeaponType::Shotgun => {
                 p.despawn_on_wall = true;
+                eject_shell_casing(cmd, assets, pos, controller.look_dir);
+                muzzle_flash(cmd, pos, controller.look_dir);
                 for _ in 0..SHOTGUN_BULLET_COUNT {
                     let angle = controller.look_dir.to_angle()
                         - std::f32::consts::FRAC_PI_2
@@ -623,6 +649,8 @@ impl Weapon {
                 true
             },
             WeaponType::Uzi => {
+                eject_shell_casing(cmd, assets, pos, controller.look_dir);
+                muzzle_flash(cmd, pos, controller.look_dir);
                 let mut t = Transform::from_translation(pos - Vec3::new(controller.look_dir.x, controller.look_dir.y, 0.0) * 10.0);
                 t.rotation = Quat::from_rotation_z(controller.look_dir.to_angle() - std::f32::consts::FRAC_PI_2);
                 p.despawn_on_wall = true;
@@ -714,7 +742,15 @@ pub fn on_weapon_spawnpoint(
 ){
     if state.get() != &STATE {return;}
     let Ok((spawner, transform)) = q.get(point.entity) else {return;};
-    let wpn: Weapon = spawner.weapon_type.to_weapon();
+    spawn_pickup_weapon(&mut cmd, &assets, spawner.weapon_type.to_weapon(), transform.translation);
+}
+
+pub fn spawn_pickup_weapon(
+    cmd: &mut Commands,
+    assets: &Res<MiamiAssets>,
+    wpn: Weapon,
+    translation: Vec3,
+) -> Entity {
     let sprite = cmd.spawn((
         DespawnOnExit(STATE),
         Visibility::default(),
@@ -741,12 +777,13 @@ pub fn on_weapon_spawnpoint(
             miami_pickup_weapon_layers(),
             LinearDamping(3.0),
             GravityScale(0.0),
-            Transform::from_translation(transform.translation),
+            Transform::from_translation(translation),
         ),
         WeaponComponents{sprite},
         wpn,
     )).id();
     cmd.entity(w).add_child(sprite);
+    w
 }
 
 
@@ -857,14 +894,17 @@ pub fn tick_thrown(
 }
 
 pub fn shoot(
-    characters: Query<(&Children, &CharacterController, &CharacterComponents, &ArmedCharacter, Option<&Player>)>,
+    characters: Query<
+        (&Children, &CharacterController, &CharacterComponents, &ArmedCharacter, Option<&Player>),
+        (Without<Execution>, Without<KnockedDown>)
+    >,
     mut weapons: Query<(&mut Weapon, &mut Transform, &WeaponComponents), Without<CharacterSprite>>,
     mut weapon_sprite: Query<(&mut Sprite, &GlobalTransform), (With<WeaponSprite>, Without<CharacterSprite>)>,
     mut char_sprite: Query<(&mut Sprite, &mut Transform), (With<CharacterSprite>, Without<WeaponSprite>, Without<WeaponComponents>)>,
     mut cmd: Commands,
     time: Res<Time>,
     assets: Res<MiamiAssets>,
-    mut shake: ResMut<CameraShake>,
+    mut shake: MessageWriter<ShakeCamera>,
 ){
     let dt = time.dt();
     for (_child, controller, cc, a, p) in characters.iter() {
@@ -908,12 +948,12 @@ pub fn on_thrown_weapon_collision(
     event: On<CollisionStart>,
     state: Res<State<AppState>>,
     mut dropped: Query<(&mut LinearVelocity, &GlobalTransform, &Weapon),  With<ThrownWeapon>>,
-    mut character: Query<(Entity, &mut CharacterController, &GlobalTransform, Option<&InvincibleCharacter>)>,
+    mut character: Query<(Entity, &mut CharacterController, &GlobalTransform, Option<&InvincibleCharacter>, Has<Player>)>,
     mut cmd: Commands,
 ) {
     if state.get() != &STATE {return;}
     let Ok((mut lv, dt, w)) = dropped.get_mut(event.collider2) else {return;};
-    let Ok((e, mut c, ct, inv)) = character.get_mut(event.collider1) else {return;};
+    let Ok((e, mut c, ct, inv, player)) = character.get_mut(event.collider1) else {return;};
     let d = dt.translation() - ct.translation();
     let ld = if c.look_dir == Vec2::ZERO {
         Vec2::new(0.0, -1.0)
@@ -925,6 +965,9 @@ pub fn on_thrown_weapon_collision(
     c.last_impact_dir = lv.normalize();
     if inv.is_none(){
         c.hp -= THROWN_DAMAGE_MULTIPLIER * lv.length() * w.throw_damage;
+        if !player && c.hp > 0.0 && lv.length() > THROWN_KNOCKDOWN_SPEED {
+            cmd.entity(e).insert(KnockedDown::new(THROWN_KNOCKDOWN_TIME));
+        }
     }
     lv.x = lv.x * 0.5;
     lv.y = lv.y * 0.5;
@@ -942,14 +985,12 @@ pub fn health_watcher(
     c_q: Query<Entity, With<WorldCamera>>,
     mut local_state: ResMut<NextState<FreddyFightStage>>
 ) {
-    let mut rng = rand::rng();
     for (e, t, components, mut controller, freddy, player) in character.iter_mut() {
         let dmg = controller.prev_hp - controller.hp;
         
         let Ok(_s) = sprite.get(components.sprite) else {continue;};
         if dmg == 0.0 {continue;}
-        let mut b = Transform::from_translation(t.translation());
-        b.translation.z += BLOOD_Z_TRANSLATION;
+        let pos = t.translation().truncate();
         let mut t = Transform::from_translation(t.translation());
         t.translation.z += BODY_Z_TRANSLATION;
 
@@ -972,7 +1013,6 @@ pub fn health_watcher(
         } else {
             idx = 2;
         }
-        b.rotation = Quat::from_rotation_z(rng.random_range(0u32..=3u32) as f32 * FRAC_PI_2);
         let rect;
         if controller.last_impact_back {
             rect = controller.back_body_rect;
@@ -990,42 +1030,32 @@ pub fn health_watcher(
                 cmd.init_resource::<PlayerZeroHealthTicker>();
             }
             info!("Spawning body of {:?}", controller.character);
+            let image = controller.character.to_handle(&assets);
+            // slides off with the hit and gets baked into the floor once it stops
             cmd.spawn((
                 DespawnOnExit(STATE),
                 t,
                 Name::new("Body"),
                 GlobalTransform::IDENTITY,
                 Sprite {
-                    image: controller.character.to_handle(&assets),
+                    image: image.clone(),
                     rect: Some(rect),
                     ..Default::default()
                 },
-            ));
-            cmd.spawn((
-                DespawnOnExit(STATE),
-                b,
-                Name::new("Blood"),
-                GlobalTransform::IDENTITY,
-                Sprite {
-                    image: assets.decals.clone(),
+                SlidingBody {
+                    velocity: -controller.last_impact_dir.normalize_or_zero() * BODY_SLIDE_SPEED,
+                    image,
+                    rect,
+                },
+                BloodTrail {
                     color: controller.blood_color,
-                    rect: Some(controller.blood_rects[2].clone()),
-                    ..Default::default()
-                }
+                    last: t.translation.truncate(),
+                },
             ));
+            blood_splatter(&mut cmd, &assets, &controller.blood_rects, controller.blood_color, pos, -controller.last_impact_dir, 2);
             cmd.entity(e).despawn();
         } else {
-            cmd.spawn((
-                DespawnOnExit(STATE),
-                b,
-                Name::new("Blood"),
-                Sprite {
-                    image: assets.decals.clone(),
-                    color: controller.blood_color,
-                    rect: Some(controller.blood_rects[idx].clone()),
-                    ..Default::default()
-                }
-            ));
+            blood_splatter(&mut cmd, &assets, &controller.blood_rects, controller.blood_color, pos, -controller.last_impact_dir, idx);
         };
     }
 }
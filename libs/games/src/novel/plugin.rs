@@ -1,6 +1,6 @@
 use std::time::Duration;
 
-use crate::{actors, backgrounds, global_music::plugin::NewBgMusic, hints::{HintAssets, KeyHint}, novel::engine::NovelStage, novel_music, prelude::*, properties::{AppState, LastState}, sound_effects, stages};
+use crate::{actors, backgrounds, global_music::plugin::NewBgMusic, hints::{HintAssets, KeyHint}, novel::engine::NovelStage, novel_music, prelude::*, properties::{AppState, LastState}, sound_effects, stages, transition::RequestTransition};
 use bevy::{audio::{PlaybackMode, Volume}, text::{FontSmoothing, LineHeight}};
 use bevy_asset_loader::prelude::AssetCollection;
 use crate::novel::engine::*;
@@ -516,6 +516,7 @@ fn tick(
     current_q: Query<Entity, With<CurrentMusic>>,
     prev_q: Query<Entity, With<PrevMusic>>,
     mut music_q: Query<&mut AudioSink>,
+    mut transitions: MessageWriter<RequestTransition>,
 ){
     let dt = time.dt();
     for e in current_q.iter() {
@@ -541,8 +542,8 @@ fn tick(
     let pressed = keyboard_input.just_pressed(KeyCode::Space);
     if state.is_finished() {
         if pressed {
-            cmd.spawn(bevy::render::view::screenshot::Screenshot::primary_window())
-                .observe(await_screenshot_and_translate(NEXT_STATE));
+            // novel2fnaf zooms into the captured frame itself
+            transitions.write(RequestTransition::capture(NEXT_STATE));
         }
         return;
     }
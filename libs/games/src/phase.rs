@@ -0,0 +1,254 @@
+use crate::prelude::*;
+
+
+/// Color phases: tile layers tagged with `PhaseLayer` in Tiled are solid, and optionally visible,
+/// only for characters in their phase. A game inserts `Phases` to say where phases go in its physics layers.
+pub struct PhasePlugin;
+
+impl Plugin for PhasePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .register_type::<PhaseLayer>()
+            .add_message::<PhaseSwapBlocked>()
+            .add_systems(Update, (read_phase_keys, apply_phase, flash_blocked).chain().run_if(resource_exists::<Phases>))
+            .add_observer(on_phase_layer)
+            .add_observer(on_phase_collider)
+            ;
+    }
+}
+
+/// On a Tiled tile layer, several layers can share a phase.
+#[derive(Component, Reflect, Clone, Debug)]
+#[reflect(Component, Default)]
+pub struct PhaseLayer {
+    /// 0 is the phase a level starts in.
+    pub phase: u32,
+    /// Tint of characters in this phase.
+    pub color: Color,
+}
+
+impl Default for PhaseLayer {
+    fn default() -> Self {
+        Self {phase: 0, color: Color::WHITE}
+    }
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct Phases {
+    /// Physics layer bit of phase 0, phase `i` gets `first_bit + i`.
+    pub first_bit: u32,
+    /// What phase tiles collide with besides their own phase.
+    pub tile_filters: LayerMask,
+    /// Layers of other phases are hidden.
+    pub hide_inactive: bool,
+    /// Phase on screen, follows the last character to swap.
+    pub active: u32,
+    /// Tint of every phase, filled in as `PhaseLayer`s spawn.
+    pub colors: Vec<Color>,
+}
+
+impl Phases {
+    pub fn new(first_bit: u32, tile_filters: impl Into<LayerMask>, hide_inactive: bool) -> Self {
+        Self {
+            first_bit,
+            tile_filters: tile_filters.into(),
+            hide_inactive,
+            active: 0,
+            colors: vec![],
+        }
+    }
+
+    /// Empty for phases past the last physics layer bit.
+    pub fn mask(&self, phase: u32) -> LayerMask {
+        1u32.checked_shl(self.first_bit.saturating_add(phase)).map_or(LayerMask::NONE, LayerMask)
+    }
+
+    /// Whether `phase` has a physics layer bit of its own.
+    pub fn fits(&self, phase: u32) -> bool {
+        self.first_bit.saturating_add(phase) < u32::BITS
+    }
+
+    /// Every phase of the loaded maps.
+    pub fn all(&self) -> LayerMask {
+        (0..self.count()).fold(LayerMask::NONE, |mask, phase| mask | self.mask(phase))
+    }
+
+    pub fn count(&self) -> u32 {
+        self.colors.len().max(1) as u32
+    }
+
+    pub fn next(&self, phase: u32) -> u32 {
+        (phase + 1) % self.count()
+    }
+
+    pub fn color(&self, phase: u32) -> Color {
+        self.colors.get(phase as usize).copied().unwrap_or(Color::WHITE)
+    }
+}
+
+/// Character living in one phase at a time. Change `phase` directly to force a swap,
+/// `PhaseKeys` swap only when the character would not end up inside the new phase's tiles.
+#[derive(Component, Clone, Debug)]
+pub struct Phased {
+    pub phase: u32,
+    /// Layers regardless of phase, the phase is added to both memberships and filters.
+    pub base: CollisionLayers,
+    /// Has to be clear of the target phase for a swap, the character's collider when `None`.
+    pub blocker: Option<Collider>,
+}
+
+impl Phased {
+    pub fn layers(&self, phases: &Phases) -> CollisionLayers {
+        let phase = phases.mask(self.phase);
+        CollisionLayers::new(self.base.memberships | phase, self.base.filters | phase)
+    }
+}
+
+/// `cycle` goes to the next phase, with `select` the digit keys pick one.
+#[derive(Component, Clone, Debug)]
+pub struct PhaseKeys {
+    pub cycle: KeyCode,
+    pub select: bool,
+}
+
+/// Sprite is tinted with the phase color.
+#[derive(Component, Default)]
+pub struct PhaseTint;
+
+/// Sent when a swap is refused because the character overlaps the target phase.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct PhaseSwapBlocked {
+    pub entity: Entity,
+    pub phase: u32,
+}
+
+/// Seconds left of the red flash after a refused swap.
+#[derive(Component)]
+pub struct BlockedFlash(pub f32);
+
+const DIGITS: [KeyCode; 9] = [
+    KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3,
+    KeyCode::Digit4, KeyCode::Digit5, KeyCode::Digit6,
+    KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+];
+
+fn read_phase_keys(
+    mut cmd: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    phases: Res<Phases>,
+    spatial: SpatialQuery,
+    mut characters: Query<(Entity, &mut Phased, &PhaseKeys, &Position, &Rotation, Option<&Collider>)>,
+    mut blocked: MessageWriter<PhaseSwapBlocked>,
+) {
+    for (entity, mut phased, phase_keys, position, rotation, collider) in characters.iter_mut() {
+        let target = if keys.just_pressed(phase_keys.cycle) {
+            phases.next(phased.phase)
+        } else if let Some(i) = DIGITS.iter().position(|k| phase_keys.select && keys.just_pressed(*k)) {
+            i as u32
+        } else {
+            continue;
+        };
+        if target == phased.phase || target >= phases.count() {continue;}
+        let Some(shape) = phased.blocker.as_ref().or(collider) else {continue;};
+        let filter = SpatialQueryFilter::from_mask(phases.mask(target)).with_excluded_entities([entity]);
+        if !spatial.shape_intersections(shape, position.0, rotation.as_radians(), &filter).is_empty() {
+            blocked.write(PhaseSwapBlocked {entity, phase: target});
+            cmd.entity(entity).insert(BlockedFlash(BLOCKED_FLASH_TIME));
+            continue;
+        }
+        phased.phase = target;
+    }
+}
+
+fn apply_phase(
+    mut cmd: Commands,
+    mut phases: ResMut<Phases>,
+    mut characters: Query<(Entity, &Phased, Option<&mut Sprite>, Has<PhaseTint>), Changed<Phased>>,
+    mut layers: Query<(&PhaseLayer, &mut Visibility)>,
+) {
+    let mut active = None;
+    for (entity, phased, sprite, tint) in characters.iter_mut() {
+        cmd.entity(entity).insert(phased.layers(&phases));
+        if tint && let Some(mut sprite) = sprite {
+            sprite.color = phases.color(phased.phase);
+        }
+        active = Some(phased.phase);
+    }
+    let Some(active) = active else {return;};
+    phases.active = active;
+    if !phases.hide_inactive {return;}
+    for (layer, mut visibility) in layers.iter_mut() {
+        *visibility = if layer.phase == active {Visibility::Visible} else {Visibility::Hidden};
+    }
+}
+
+fn flash_blocked(
+    mut cmd: Commands,
+    time: Res<Time>,
+    phases: Res<Phases>,
+    mut characters: Query<(Entity, &Phased, &mut BlockedFlash, Option<&mut Sprite>, Has<PhaseTint>)>,
+) {
+    for (entity, phased, mut flash, sprite, tint) in characters.iter_mut() {
+        flash.0 -= time.delta_secs();
+        let Some(mut sprite) = sprite else {continue;};
+        if flash.0 > 0. {
+            sprite.color = BLOCKED_COLOR;
+            continue;
+        }
+        sprite.color = if tint {phases.color(phased.phase)} else {Color::WHITE};
+        cmd.entity(entity).remove::<BlockedFlash>();
+    }
+}
+
+fn on_phase_layer(
+    layer: On<Add, PhaseLayer>,
+    phases: Option<ResMut<Phases>>,
+    mut layers: Query<(&PhaseLayer, Option<&mut Visibility>)>,
+) {
+    let Some(mut phases) = phases else {return;};
+    let Ok((layer, visibility)) = layers.get_mut(layer.entity) else {return;};
+    if !phases.fits(layer.phase) {
+        warn!("Phase {} is past the last physics layer, phases start at bit {}", layer.phase, phases.first_bit);
+        return;
+    }
+    let i = layer.phase as usize;
+    if phases.colors.len() <= i {
+        phases.colors.resize(i + 1, Color::WHITE);
+    }
+    phases.colors[i] = layer.color;
+    if phases.hide_inactive && layer.phase != phases.active && let Some(mut visibility) = visibility {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn on_phase_collider(
+    collider_created: On<TiledEvent<ColliderCreated>>,
+    phases: Option<Res<Phases>>,
+    parents: Query<&ChildOf>,
+    layers: Query<&PhaseLayer>,
+    mut cmd: Commands,
+) {
+    let Some(phases) = phases else {return;};
+    let origin = collider_created.event().origin;
+    let Ok(layer) = parents.get(origin).and_then(|p| layers.get(p.parent())) else {return;};
+    let mask = phases.mask(layer.phase);
+    cmd.entity(origin).insert(CollisionLayers::new(mask, mask | phases.tile_filters));
+}
+
+
+const BLOCKED_FLASH_TIME: f32 = 0.15;
+const BLOCKED_COLOR: Color = Color::srgb(1., 0.2, 0.2);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_past_the_last_bit_is_empty() {
+        let phases = Phases::new(30, LayerMask::ALL, false);
+        assert_eq!(phases.mask(1), LayerMask(1 << 31));
+        assert!(!phases.fits(2));
+        assert_eq!(phases.mask(2), LayerMask::NONE);
+        assert_eq!(phases.mask(u32::MAX), LayerMask::NONE);
+    }
+}
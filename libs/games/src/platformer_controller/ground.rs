@@ -0,0 +1,127 @@
+use super::plugin::CharacterMotion;
+use crate::prelude::*;
+
+
+/// Casts down (against `up`) from the body every fixed step and keeps `Grounded` up to date.
+#[derive(Component, Clone, Debug)]
+#[require(Grounded)]
+pub struct GroundDetector {
+    /// Cast instead of the body's own collider, handy for bodies that rotate.
+    pub shape: Option<Collider>,
+    /// `None` casts against what the body's `CollisionLayers` collide with.
+    pub filter: Option<SpatialQueryFilter>,
+    /// How far below the body still counts as standing.
+    pub distance: f32,
+    /// Steepest walkable ground, in radians from flat.
+    pub max_slope: f32,
+    /// Away from the ground, the cast goes the other way.
+    pub up: Dir2,
+}
+
+impl Default for GroundDetector {
+    fn default() -> Self {
+        Self {
+            shape: None,
+            filter: None,
+            distance: 2.0,
+            max_slope: std::f32::consts::FRAC_PI_4,
+            up: Dir2::Y,
+        }
+    }
+}
+
+impl GroundDetector {
+    /// Whether a surface with `normal` can be stood on.
+    pub fn walkable(&self, normal: Vec2) -> bool {
+        normal.dot(*self.up) >= self.max_slope.cos() - f32::EPSILON
+    }
+
+    pub fn filter(&self, layers: Option<&CollisionLayers>) -> SpatialQueryFilter {
+        self.filter.clone().unwrap_or_else(|| {
+            SpatialQueryFilter::from_mask(layers.map_or(LayerMask::ALL, |l| l.filters))
+        })
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Grounded {
+    pub entity: Option<Entity>,
+    /// Points away from the ground, `GroundDetector::up` while airborne.
+    pub normal: Vec2,
+    /// Seconds since the body last stood on something.
+    pub airtime: f32,
+}
+
+impl Grounded {
+    pub fn on_ground(&self) -> bool {
+        self.entity.is_some()
+    }
+}
+
+/// Solid only from above, characters jump through it from below and can drop down.
+#[derive(Component, Default, Reflect)]
+#[reflect(Component, Default)]
+pub struct OneWayPlatform;
+
+pub fn update_ground(
+    time: Res<Time>,
+    spatial: SpatialQuery,
+    mut bodies: Query<(Entity, &GroundDetector, &mut Grounded, &Position, Option<&Collider>, Option<&CollisionLayers>, Option<&CharacterMotion>)>,
+    solids: Query<(Has<Sensor>, Option<&ColliderOf>)>,
+    rigid_bodies: Query<&RigidBody>,
+    one_way: Query<(), With<OneWayPlatform>>,
+) {
+    for (entity, detector, mut grounded, position, collider, layers, motion) in bodies.iter_mut() {
+        let Some(shape) = detector.shape.as_ref().or(collider) else {continue;};
+        let mut filter = detector.filter(layers);
+        filter.excluded_entities.insert(entity);
+        // a one-way platform is only stood on from above, not passed on the way up
+        let rising = motion.is_some_and(|m| m.velocity.dot(*detector.up) > 0.0);
+        let hit = cast_solid(&spatial, &solids, &rigid_bodies, shape, position.0, -detector.up, detector.distance, filter, |hit| {
+            !rising || !one_way.contains(hit.entity)
+        })
+            .filter(|hit| detector.walkable(hit.normal1));
+        match hit {
+            Some(hit) => {
+                *grounded = Grounded {entity: Some(hit.entity), normal: hit.normal1, airtime: 0.0};
+            }
+            None => {
+                grounded.entity = None;
+                grounded.normal = *detector.up;
+                grounded.airtime += time.delta_secs();
+            }
+        }
+    }
+}
+
+/// First hit along `dir` that blocks a character: sensors and dynamic bodies are passed through,
+/// `accept` decides on the rest (one-way platforms for example).
+pub fn cast_solid(
+    spatial: &SpatialQuery,
+    solids: &Query<(Has<Sensor>, Option<&ColliderOf>)>,
+    rigid_bodies: &Query<&RigidBody>,
+    shape: &Collider,
+    origin: Vec2,
+    dir: Dir2,
+    distance: f32,
+    mut filter: SpatialQueryFilter,
+    mut accept: impl FnMut(&ShapeHitData) -> bool,
+) -> Option<ShapeHitData> {
+    let config = ShapeCastConfig {
+        max_distance: distance,
+        ignore_origin_penetration: true,
+        ..default()
+    };
+    // every rejected hit is excluded, so this ends
+    loop {
+        let hit = spatial.cast_shape(shape, origin, 0.0, dir, &config, &filter)?;
+        let (sensor, collider_of) = solids.get(hit.entity).unwrap_or((false, None));
+        let dynamic = collider_of
+            .and_then(|c| rigid_bodies.get(c.body).ok())
+            .is_some_and(|rb| rb.is_dynamic());
+        if !sensor && !dynamic && accept(&hit) {
+            return Some(hit);
+        }
+        filter.excluded_entities.insert(hit.entity);
+    }
+}
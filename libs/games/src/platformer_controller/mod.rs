@@ -0,0 +1,5 @@
+pub mod plugin;
+pub mod ground;
+
+#[cfg(test)]
+mod tests;
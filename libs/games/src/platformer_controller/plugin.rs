@@ -0,0 +1,214 @@
+use super::ground::*;
+use crate::prelude::*;
+
+
+/// Kinematic side-view movement: coyote time, jump buffering, variable jump height, apex hang,
+/// fall speed clamp, one-way platforms and slopes.
+pub struct PlatformerControllerPlugin;
+
+impl Plugin for PlatformerControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .register_type::<OneWayPlatform>()
+            .add_systems(FixedUpdate, (update_ground, move_characters).chain())
+            ;
+    }
+}
+
+/// Tuning of a character, velocities in px/s.
+#[derive(Component, Clone, Debug)]
+#[require(RigidBody = RigidBody::Kinematic, GroundDetector, ControllerInput, CharacterMotion, TransformInterpolation)]
+pub struct PlatformerController {
+    pub max_speed: f32,
+    pub ground_accel: f32,
+    pub air_accel: f32,
+    pub jump_speed: f32,
+    pub gravity: f32,
+    /// Gravity multiplier while falling.
+    pub fall_gravity: f32,
+    pub max_fall_speed: f32,
+    /// Upward velocity is multiplied by it when jump is released early.
+    pub jump_cut: f32,
+    /// Below this vertical speed the jump is at its apex and gravity is `apex_gravity` times weaker.
+    pub apex_threshold: f32,
+    pub apex_gravity: f32,
+    /// Seconds after walking off a ledge that still allow a jump.
+    pub coyote_time: f32,
+    /// Seconds a jump press is remembered before landing.
+    pub jump_buffer: f32,
+    /// Keeps the character on the ground going down slopes and steps.
+    pub snap_distance: f32,
+}
+
+impl Default for PlatformerController {
+    fn default() -> Self {
+        Self {
+            max_speed: 200.0,
+            ground_accel: 1500.0,
+            air_accel: 1000.0,
+            jump_speed: 300.0,
+            gravity: 490.0,
+            fall_gravity: 1.5,
+            max_fall_speed: 500.0,
+            jump_cut: 0.5,
+            apex_threshold: 40.0,
+            apex_gravity: 0.5,
+            coyote_time: 0.1,
+            jump_buffer: 0.12,
+            snap_distance: 6.0,
+        }
+    }
+}
+
+/// Written by the game, read on the next fixed step.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct ControllerInput {
+    /// -1 is full left, 1 full right.
+    pub x: f32,
+    /// Set on press, cleared once buffered.
+    pub jump_pressed: bool,
+    pub jump_held: bool,
+    /// Falls through one-way platforms while held.
+    pub drop: bool,
+}
+
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct CharacterMotion {
+    pub velocity: Vec2,
+    pub coyote: f32,
+    pub buffer: f32,
+    /// Rising from a jump that can still be cut.
+    pub jumping: bool,
+}
+
+impl CharacterMotion {
+    /// Timers, jump and gravity of one step with the ground as `update_ground` saw it, true when it jumped.
+    pub fn step(&mut self, c: &PlatformerController, input: &mut ControllerInput, on_ground: bool, dt: f32) -> bool {
+        self.coyote = if on_ground {c.coyote_time} else {(self.coyote - dt).max(0.0)};
+        if input.jump_pressed {
+            self.buffer = c.jump_buffer;
+            input.jump_pressed = false;
+        } else {
+            self.buffer = (self.buffer - dt).max(0.0);
+        }
+
+        let mut v = self.velocity;
+        let target = input.x.clamp(-1.0, 1.0) * c.max_speed;
+        let accel = if on_ground {c.ground_accel} else {c.air_accel};
+        v.x = v.x.move_towards(target, accel * dt);
+
+        let mut jumped = false;
+        if self.buffer > 0.0 && self.coyote > 0.0 {
+            v.y = c.jump_speed;
+            self.buffer = 0.0;
+            self.coyote = 0.0;
+            self.jumping = true;
+            jumped = true;
+        }
+        if self.jumping && !input.jump_held && v.y > 0.0 {
+            v.y *= c.jump_cut;
+            self.jumping = false;
+        }
+        if v.y <= 0.0 {
+            self.jumping = false;
+        }
+
+        if on_ground && !jumped {
+            v.y = 0.0;
+        } else {
+            let gravity = if v.y.abs() < c.apex_threshold && input.jump_held {
+                c.gravity * c.apex_gravity
+            } else if v.y < 0.0 {
+                c.gravity * c.fall_gravity
+            } else {
+                c.gravity
+            };
+            v.y = (v.y - gravity * dt).max(-c.max_fall_speed);
+        }
+        self.velocity = v;
+        jumped
+    }
+}
+
+pub fn move_characters(
+    time: Res<Time>,
+    spatial: SpatialQuery,
+    mut characters: Query<(
+        Entity,
+        &PlatformerController,
+        &GroundDetector,
+        &mut Grounded,
+        &mut ControllerInput,
+        &mut CharacterMotion,
+        &mut Position,
+        &Collider,
+        Option<&CollisionLayers>,
+    )>,
+    solids: Query<(Has<Sensor>, Option<&ColliderOf>)>,
+    rigid_bodies: Query<&RigidBody>,
+    one_way: Query<(), With<OneWayPlatform>>,
+) {
+    let dt = time.delta_secs();
+    if dt <= 0.0 {return;}
+    for (entity, c, detector, mut grounded, mut input, mut motion, mut position, collider, layers) in characters.iter_mut() {
+        let mut filter = detector.filter(layers);
+        filter.excluded_entities.insert(entity);
+        let dropping = input.drop && grounded.entity.is_some_and(|e| one_way.contains(e));
+        let on_ground = grounded.on_ground() && !dropping;
+
+        let jumped = motion.step(c, &mut input, on_ground, dt);
+        let mut v = motion.velocity;
+
+        // walk along the slope instead of into it
+        let mut delta = v * dt;
+        if on_ground && !jumped {
+            let n = grounded.normal;
+            delta = Vec2::new(n.y, -n.x) * delta.x + Vec2::Y * delta.y;
+        }
+
+        let mut pos = position.0;
+        let mut landed = None;
+        for _ in 0..MAX_SLIDES {
+            let Ok(dir) = Dir2::new(delta) else {break;};
+            let length = delta.length();
+            let hit = cast_solid(&spatial, &solids, &rigid_bodies, collider, pos, dir, length + SKIN, filter.clone(), |hit| {
+                !one_way.contains(hit.entity) || (!input.drop && dir.y < 0.0 && hit.normal1.y > 0.0)
+            });
+            let Some(hit) = hit else {
+                pos += delta;
+                break;
+            };
+            let travel = (hit.distance - SKIN).max(0.0).min(length);
+            pos += dir * travel;
+            let n = hit.normal1;
+            delta = (delta - dir * travel).reject_from_normalized(n);
+            if v.dot(n) < 0.0 {
+                v = v.reject_from_normalized(n);
+            }
+            if detector.walkable(n) {
+                landed = Some((hit.entity, n));
+            }
+        }
+
+        if landed.is_none() && on_ground && !jumped {
+            let snap = cast_solid(&spatial, &solids, &rigid_bodies, collider, pos, Dir2::NEG_Y, c.snap_distance + SKIN, filter.clone(), |hit| {
+                !input.drop || !one_way.contains(hit.entity)
+            });
+            if let Some(hit) = snap.filter(|hit| detector.walkable(hit.normal1)) {
+                pos.y -= (hit.distance - SKIN).max(0.0);
+                landed = Some((hit.entity, hit.normal1));
+            }
+        }
+        if let Some((entity, normal)) = landed {
+            *grounded = Grounded {entity: Some(entity), normal, airtime: 0.0};
+        }
+
+        motion.velocity = v;
+        position.0 = pos;
+    }
+}
+
+
+/// Gap kept between the character and what it slides along.
+const SKIN: f32 = 0.5;
+const MAX_SLIDES: usize = 4;
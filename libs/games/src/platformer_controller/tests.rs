@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use bevy::time::TimeUpdateStrategy;
+
+use super::ground::*;
+use super::plugin::*;
+use crate::prelude::*;
+
+
+const DT: f32 = 1.0 / 64.0;
+
+/// Steps `motion` `steps` times and gives whether any of them jumped.
+fn run(motion: &mut CharacterMotion, input: &mut ControllerInput, on_ground: bool, steps: usize) -> bool {
+    let c = PlatformerController::default();
+    (0..steps).fold(false, |jumped, _| motion.step(&c, input, on_ground, DT) | jumped)
+}
+
+fn press(held: bool) -> ControllerInput {
+    ControllerInput {jump_pressed: true, jump_held: held, ..Default::default()}
+}
+
+fn steps(seconds: f32) -> usize {
+    (seconds / DT).round() as usize
+}
+
+
+#[test]
+fn jumps_right_after_leaving_a_ledge() {
+    let c = PlatformerController::default();
+    let mut motion = CharacterMotion::default();
+    run(&mut motion, &mut ControllerInput::default(), true, 4);
+    run(&mut motion, &mut ControllerInput::default(), false, steps(c.coyote_time * 0.5));
+    assert!(run(&mut motion, &mut press(true), false, 1));
+    assert!(motion.velocity.y > 0.0);
+}
+
+#[test]
+fn no_jump_once_coyote_time_is_over() {
+    let c = PlatformerController::default();
+    let mut motion = CharacterMotion::default();
+    run(&mut motion, &mut ControllerInput::default(), true, 4);
+    run(&mut motion, &mut ControllerInput::default(), false, steps(c.coyote_time * 1.5));
+    assert!(!run(&mut motion, &mut press(true), false, 1));
+    assert!(motion.velocity.y < 0.0);
+}
+
+#[test]
+fn early_press_jumps_on_landing() {
+    let c = PlatformerController::default();
+    let mut motion = CharacterMotion::default();
+    let mut input = press(true);
+    assert!(!run(&mut motion, &mut input, false, 1));
+    assert!(!input.jump_pressed, "the press is buffered once");
+    run(&mut motion, &mut input, false, steps(c.jump_buffer * 0.5));
+    assert!(run(&mut motion, &mut input, true, 1));
+    // and only once
+    assert!(!run(&mut motion, &mut input, true, 8));
+}
+
+#[test]
+fn too_early_press_is_forgotten() {
+    let c = PlatformerController::default();
+    let mut motion = CharacterMotion::default();
+    let mut input = press(true);
+    run(&mut motion, &mut input, false, steps(c.jump_buffer * 1.5));
+    assert!(!run(&mut motion, &mut input, true, 1));
+}
+
+#[test]
+fn releasing_jump_cuts_it_once() {
+    let c = PlatformerController::default();
+    let mut motion = CharacterMotion::default();
+    let mut input = press(true);
+    assert!(run(&mut motion, &mut input, true, 1));
+    run(&mut motion, &mut input, false, 2);
+    let rising = motion.velocity.y;
+    assert!(motion.jumping && rising > c.apex_threshold);
+
+    input.jump_held = false;
+    run(&mut motion, &mut input, false, 1);
+    assert!((motion.velocity.y - (rising * c.jump_cut - c.gravity * DT)).abs() < 1e-3);
+    assert!(!motion.jumping);
+    let cut = motion.velocity.y;
+    run(&mut motion, &mut input, false, 1);
+    assert!((motion.velocity.y - (cut - c.gravity * DT)).abs() < 1e-3);
+}
+
+#[test]
+fn held_jump_goes_higher() {
+    let apex = |held_steps: usize| {
+        let mut motion = CharacterMotion::default();
+        let mut input = press(true);
+        run(&mut motion, &mut input, true, 1);
+        let mut y = 0.0_f32;
+        let mut top = 0.0_f32;
+        for i in 0..200 {
+            input.jump_held = i < held_steps;
+            run(&mut motion, &mut input, false, 1);
+            y += motion.velocity.y * DT;
+            top = top.max(y);
+        }
+        top
+    };
+    assert!(apex(4) < apex(200));
+}
+
+
+#[test]
+fn jumps_up_through_a_one_way_platform_and_lands_on_it() {
+    let mut app = App::new();
+    app
+        .add_plugins((MinimalPlugins, TransformPlugin, AssetPlugin::default(), PhysicsPlugins::default(), PlatformerControllerPlugin))
+        .init_resource::<Assets<Mesh>>()
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f32(DT)));
+    app.world_mut().spawn((RigidBody::Static, Collider::rectangle(400.0, 10.0), Transform::from_xyz(0.0, -13.0, 0.0)));
+    let platform = app.world_mut().spawn((
+        RigidBody::Static,
+        OneWayPlatform,
+        Collider::rectangle(64.0, 4.0),
+        Transform::from_xyz(0.0, 40.0, 0.0),
+    )).id();
+    let player = app.world_mut().spawn((
+        PlatformerController::default(),
+        Collider::rectangle(16.0, 16.0),
+        Transform::from_xyz(0.0, 1.0, 0.0),
+    )).id();
+    for _ in 0..16 {
+        app.update();
+    }
+    assert!(app.world().get::<Grounded>(player).unwrap().on_ground());
+
+    *app.world_mut().get_mut::<ControllerInput>(player).unwrap() = press(true);
+    let mut top = f32::MIN;
+    for _ in 0..160 {
+        app.update();
+        let world = app.world();
+        let (motion, grounded) = (world.get::<CharacterMotion>(player).unwrap(), world.get::<Grounded>(player).unwrap());
+        assert!(motion.velocity.y <= 0.0 || grounded.entity != Some(platform), "grounded on the platform on the way up");
+        top = top.max(world.get::<Position>(player).unwrap().y);
+    }
+    assert!(top > 50.0, "got above the platform");
+    let world = app.world();
+    assert_eq!(world.get::<Grounded>(player).unwrap().entity, Some(platform));
+    assert_eq!(world.get::<CharacterMotion>(player).unwrap().velocity.y, 0.0);
+    assert!((world.get::<Position>(player).unwrap().y - 50.5).abs() < 1.0);
+}
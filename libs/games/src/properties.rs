@@ -1,8 +1,8 @@
-use bevy::render::view::screenshot::ScreenshotCaptured;
+use serde::{Deserialize, Serialize};
 
 use crate::prelude::*;
 
-#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States, Serialize, Deserialize)]
 pub enum AppState {
     // setup loading screen
     // Begin, 
@@ -51,29 +51,8 @@ pub struct LastScreenshot {
     pub awaiting: bool
 }
 
-pub fn await_screenshot_and_translate(
-    state: AppState
-) -> impl FnMut(On<ScreenshotCaptured>, ResMut<LastScreenshot>, ResMut<Assets<Image>>, ResMut<NextState<AppState>>) {
-    move |
-            screenshot_captured,
-            mut last_screenshot,
-            mut images_mut,
-            mut next_state
-    | {
-        let img = screenshot_captured.image.clone();
-        let handle = images_mut.add(img);
-        last_screenshot.image = Some(handle.clone());
-        next_state.set(state);
-        last_screenshot.awaiting = false;
-    }
-}
-
 pub const MUSIC_INTERPOLATION : f32 = 0.6;
 
-pub const PACMAN_EAT_PATH_HALF : f32 = 280.0;
-pub const PACMAN_EAT_SCALE : f32 = 4.0;
-pub const PACMAN_EAT_ANIM_DELAY : f32 = 0.06;
-pub const PACMAN_EAT_WALK_SPEED : f32 = 400.0;
 
 
 
@@ -83,6 +62,7 @@ pub const PLATFORMER_MAX_SPEED : f32 = 200.0;
 pub const PLATFORMER_AIR_GAIN : f32 = 1000.0;
 pub const PLATFORMER_GROUND_GAIN : f32 = 1500.0;
 pub const PLATFORMER_ANIM_DELAY : f32 = 0.1;
+pub const PLATFORMER_STOMP_BOUNCE : f32 = 250.0;
 
 
 
@@ -93,7 +73,6 @@ pub const FLAPPY_LEFT_BOUND : f32 = -FLAPPY_WIDTH / 2.0 + 120.0;
 pub const FLAPPY_RIGHT_BOUND : f32 = FLAPPY_WIDTH / 2.0 - 200.0;
 
 pub const FLAPPY_TRANSITION_SPEED : f32 = 500.;
-pub const FLAPPY_BG_TRANSITION_SPEED : f32 = 800.;
 pub const FLAPPY_PARALLAX_SPEED : f32 = 50.0;
 pub const FLAPPY_BIRD_PROGRESS_SPEED : f32 = 25.0;
 pub const FLAPPY_BIRD_OUT_SPEED : f32 = 500.0;
@@ -102,9 +81,17 @@ pub const FLAPPY_PIPE_SPAWN_DELAY : f32 = 1.5;
 pub const FLAPPY_PIPE_GAP : f32 = 85.0;
 pub const FLAPPY_PIPE_SPREAD : f32 = 40.0;
 pub const FLAPPY_GRAVITY_AFFECT : f32 = 50.0;
+pub const FLAPPY_GRAVITY : f32 = FLAPPY_GRAVITY_AFFECT * 9.81;
+pub const FLAPPY_FLAP_TIME : f32 = 0.3;
 pub const FLAPPY_BIRD_JUMP_STRENGTH : f32 = 200.0;
 pub const FLAPPY_DEATH_DELAY : f32 = 1.0;
 pub const FLAPPY_PIPE_SCALE : f32 = 0.25;
+pub const FLAPPY_PIPE_GAP_MIN : f32 = 60.0;
+pub const FLAPPY_PIPE_SPEED_MAX : f32 = 400.0;
+pub const FLAPPY_PIPE_SWAY_MAX : f32 = 30.0;
+pub const FLAPPY_PIPE_SWAY_SPEED : f32 = 2.0;
+// pipes until the difficulty stops growing
+pub const FLAPPY_DIFFICULTY_PIPES : f32 = 40.0;
 
 pub const NOVEL_MUSIC_INTERPOLATION : f32 = 1.0;
 
@@ -0,0 +1,352 @@
+use bevy::{app::RunFixedMainLoopSystems, input::InputSystems, platform::collections::HashSet};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, tick_input::TickInput};
+
+
+/// `--record <file>` saves the keys of every fixed tick, `--replay <file>` plays them back.
+/// Recording starts with the first minigame state and is written on exit.
+///
+/// Intros and map loads take a different number of ticks at every frame rate, so games send
+/// `GameplayStarted` when their fixed tick gameplay begins and the replay lines up on it.
+/// Games stepped in `FixedUpdate` on `TickInput` and seeded from `RunSeed` replay exactly,
+/// the rest get the keys mirrored into `ButtonInput` once per frame and only follow roughly.
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RunSeed::new(rand::rng().random()));
+        match ReplayArgs::parse() {
+            ReplayArgs::Record(path) => {
+                app.insert_resource(Recorder::new(path));
+            }
+            ReplayArgs::Replay(path) => match InputRecording::load(&path) {
+                Ok(recording) => {
+                    info!("Replaying {path}: {} ticks from {:?}, seed {}", recording.length, recording.state, recording.seed);
+                    app.insert_resource(RunSeed::new(recording.seed));
+                    app.insert_resource(Replay::new(recording));
+                }
+                Err(e) => error!("Could not load replay {path}: {e}"),
+            },
+            ReplayArgs::None => {}
+        }
+        app
+            .add_message::<GameplayStarted>()
+            .add_systems(Startup, spawn_indicator)
+            .add_systems(RunFixedMainLoop, ((start_recording, start_replay), mark_gameplay_start).chain()
+                .in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop))
+            .add_systems(FixedFirst, (
+                feed_replay.run_if(resource_exists::<Replay>),
+                record_tick.run_if(resource_exists::<Recorder>),
+            ))
+            .add_systems(PreUpdate, mirror_replay.after(InputSystems).run_if(resource_exists::<Replay>))
+            .add_systems(Update, update_indicator)
+            .add_systems(Last, store_recording.run_if(resource_exists::<Recorder>))
+            ;
+    }
+}
+
+/// State the replay starts in, `None` when not replaying.
+pub fn replay_start_state(app: &App) -> Option<AppState> {
+    app.world().get_resource::<Replay>().map(|r| r.recording.state)
+}
+
+/// Sent when a game's `FixedUpdate` gameplay begins, on entering its playing state.
+#[derive(Message, Clone, Copy, Debug, Default)]
+pub struct GameplayStarted;
+
+/// Seeds of seeded games, drawn in order from the seed saved with the recording.
+#[derive(Resource, Debug)]
+pub struct RunSeed {
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl RunSeed {
+    pub fn new(seed: u64) -> Self {
+        Self {seed, rng: StdRng::seed_from_u64(seed)}
+    }
+
+    pub fn next(&mut self) -> u64 {
+        self.rng.random()
+    }
+}
+
+/// Keys of the ticks where something changed, held keys stay held until the next entry.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct InputRecording {
+    pub state: AppState,
+    pub seed: u64,
+    /// Ticks recorded.
+    pub length: u64,
+    /// Ticks at which a game sent `GameplayStarted`.
+    #[serde(default)]
+    pub starts: Vec<u64>,
+    pub ticks: Vec<RecordedTick>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RecordedTick {
+    /// Counted from the start of the recording.
+    pub tick: u64,
+    pub held: Vec<KeyCode>,
+    pub pressed: Vec<KeyCode>,
+}
+
+impl InputRecording {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let s = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&s).map_err(|e| e.to_string())
+    }
+
+    /// Adds `tick` unless it only repeats the held keys of the last entry.
+    pub fn push(&mut self, tick: u64, held: Vec<KeyCode>, pressed: Vec<KeyCode>) {
+        self.length = self.length.max(tick + 1);
+        let last_held = self.ticks.last().map(|t| &t.held);
+        if pressed.is_empty() && last_held.map_or(held.is_empty(), |h| *h == held) {return;}
+        self.ticks.push(RecordedTick {tick, held, pressed});
+    }
+
+    /// Held and just pressed keys of `tick`.
+    pub fn keys(&self, tick: u64) -> (HashSet<KeyCode>, HashSet<KeyCode>) {
+        let i = self.ticks.partition_point(|t| t.tick <= tick);
+        let Some(entry) = i.checked_sub(1).map(|i| &self.ticks[i]) else {return default();};
+        let pressed = if entry.tick == tick {entry.pressed.iter().copied().collect()} else {default()};
+        (entry.held.iter().copied().collect(), pressed)
+    }
+}
+
+enum ReplayArgs {
+    None,
+    Record(String),
+    Replay(String),
+}
+
+impl ReplayArgs {
+    fn parse() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let mut args = std::env::args().skip(1);
+            while let Some(arg) = args.next() {
+                match (arg.as_str(), args.next()) {
+                    ("--record", Some(path)) => return Self::Record(path),
+                    ("--replay", Some(path)) => return Self::Replay(path),
+                    _ => {}
+                }
+            }
+        }
+        Self::None
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct Recorder {
+    path: String,
+    /// With the tick it started at.
+    recording: Option<(u64, InputRecording)>,
+}
+
+impl Recorder {
+    /// Records from the next run state on and saves to `path` on exit, as `--record` does.
+    pub(crate) fn new(path: String) -> Self {
+        Self {path, recording: None}
+    }
+
+    pub(crate) fn recording(&self) -> Option<&InputRecording> {
+        self.recording.as_ref().map(|(_, r)| r)
+    }
+}
+
+#[derive(Resource)]
+pub(crate) struct Replay {
+    recording: InputRecording,
+    /// Tick the current segment started at, segment `n` follows the `n`th gameplay start.
+    start: Option<u64>,
+    segment: usize,
+    /// Of the last replayed tick.
+    held: HashSet<KeyCode>,
+}
+
+impl Replay {
+    pub(crate) fn new(recording: InputRecording) -> Self {
+        Self {recording, start: None, segment: 0, held: HashSet::default()}
+    }
+
+    /// Recorded tick replayed on `tick`, `None` before the start or past the recorded gameplay starts.
+    fn recorded_tick(&self, tick: u64) -> Option<u64> {
+        let from = match self.segment {
+            0 => 0,
+            n => *self.recording.starts.get(n - 1)?,
+        };
+        Some(from + tick - self.start?)
+    }
+}
+
+#[derive(Component)]
+struct ReplayIndicator;
+
+/// Loading states are not part of a run.
+fn in_run(state: &AppState) -> bool {
+    !matches!(state, AppState::LoadingScreen | AppState::LoadingAssets)
+}
+
+fn start_recording(
+    recorder: Option<ResMut<Recorder>>,
+    state: Option<Res<State<AppState>>>,
+    input: Res<TickInput>,
+    mut seed: ResMut<RunSeed>,
+) {
+    let (Some(mut recorder), Some(state)) = (recorder, state) else {return;};
+    if recorder.recording.is_some() || !in_run(state.get()) {return;}
+    // reseeding here keeps the seeds drawn during loading out of the run
+    *seed = RunSeed::new(seed.seed);
+    info!("Recording input from {:?} to {}", state.get(), recorder.path);
+    recorder.recording = Some((input.tick, InputRecording {state: *state.get(), seed: seed.seed, ..default()}));
+}
+
+fn start_replay(
+    replay: Option<ResMut<Replay>>,
+    state: Option<Res<State<AppState>>>,
+    input: Res<TickInput>,
+    mut seed: ResMut<RunSeed>,
+) {
+    let (Some(mut replay), Some(state)) = (replay, state) else {return;};
+    if replay.start.is_some() || !in_run(state.get()) {return;}
+    *seed = RunSeed::new(replay.recording.seed);
+    replay.start = Some(input.tick);
+}
+
+/// Runs after the state transitions of the frame, so the first gameplay tick is `input.tick`.
+fn mark_gameplay_start(
+    mut started: MessageReader<GameplayStarted>,
+    recorder: Option<ResMut<Recorder>>,
+    replay: Option<ResMut<Replay>>,
+    input: Res<TickInput>,
+) {
+    if started.read().count() == 0 {return;}
+    if let Some(mut recorder) = recorder && let Some((start, recording)) = &mut recorder.recording {
+        recording.starts.push(input.tick - *start);
+    }
+    if let Some(mut replay) = replay && replay.start.is_some() {
+        replay.segment += 1;
+        replay.start = Some(input.tick);
+    }
+}
+
+fn record_tick(
+    mut recorder: ResMut<Recorder>,
+    input: Res<TickInput>,
+) {
+    let Some((start, recording)) = &mut recorder.recording else {return;};
+    let mut held = input.held().collect::<Vec<_>>();
+    let mut pressed = input.just_pressed_keys().collect::<Vec<_>>();
+    // sets iterate in any order
+    held.sort();
+    pressed.sort();
+    recording.push(input.tick - *start, held, pressed);
+}
+
+/// Overrides the sampled keyboard with the recorded keys, input goes live again at the end.
+fn feed_replay(
+    mut cmd: Commands,
+    mut replay: ResMut<Replay>,
+    mut input: ResMut<TickInput>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+) {
+    if replay.start.is_none() {return;}
+    let Some(tick) = replay.recorded_tick(input.tick) else {
+        warn!("The recording has no gameplay start {}, input is live", replay.segment);
+        keys.release_all();
+        cmd.remove_resource::<Replay>();
+        return;
+    };
+    // an intro that runs longer here waits for its gameplay start without keys
+    if replay.recording.starts.get(replay.segment).is_some_and(|until| tick >= *until) {
+        replay.held.clear();
+        input.replace(default(), default());
+        return;
+    }
+    if tick >= replay.recording.length {
+        info!("Replay finished, input is live");
+        keys.release_all();
+        cmd.remove_resource::<Replay>();
+        return;
+    }
+    let (held, pressed) = replay.recording.keys(tick);
+    replay.held = held.clone();
+    input.replace(held, pressed);
+}
+
+/// Frame based games read `ButtonInput`, they see the keys of the last replayed tick.
+fn mirror_replay(
+    replay: Res<Replay>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut mirrored: Local<HashSet<KeyCode>>,
+) {
+    keys.reset_all();
+    for key in replay.held.iter() {
+        keys.press(*key);
+        if mirrored.contains(key) {
+            keys.clear_just_pressed(*key);
+        }
+    }
+    for key in mirrored.iter().filter(|k| !replay.held.contains(*k)) {
+        keys.press(*key);
+        keys.clear_just_pressed(*key);
+        keys.release(*key);
+    }
+    *mirrored = replay.held.clone();
+}
+
+fn store_recording(
+    recorder: Res<Recorder>,
+    mut exit: MessageReader<AppExit>,
+) {
+    if exit.read().next().is_none() {return;}
+    let Some((_, recording)) = &recorder.recording else {return;};
+    info!("Saving {} recorded ticks to {}", recording.length, recorder.path);
+    crate::saves::store(&recorder.path, recording);
+}
+
+fn spawn_indicator(
+    mut cmd: Commands,
+) {
+    cmd.spawn((
+        ReplayIndicator,
+        Node {
+            // flappy's score sits top-right and dialogs along the bottom
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        GlobalZIndex(i32::MAX),
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1., 0.3, 0.3)),
+    ));
+}
+
+fn update_indicator(
+    replay: Option<Res<Replay>>,
+    recorder: Option<Res<Recorder>>,
+    input: Res<TickInput>,
+    mut text: Query<&mut Text, With<ReplayIndicator>>,
+) {
+    let Ok(mut text) = text.single_mut() else {return;};
+    let s = if let Some(replay) = replay {
+        let tick = replay.recorded_tick(input.tick).unwrap_or(0);
+        format!("REPLAY {tick}/{}", replay.recording.length)
+    } else if let Some((_, recording)) = recorder.as_ref().and_then(|r| r.recording.as_ref()) {
+        format!("REC {}", recording.length)
+    } else {
+        String::new()
+    };
+    if text.0 != s {
+        text.0 = s;
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::prelude::*;
+
+
+/// Reads a RON save, a missing or broken file gives the default.
+pub fn load<T: DeserializeOwned + Default>(path: &str) -> T {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(s) = std::fs::read_to_string(path) {
+        match ron::from_str(&s) {
+            Ok(save) => return save,
+            Err(e) => warn!("Broken save {path}: {e}"),
+        }
+    }
+    T::default()
+}
+
+/// Writes a RON save, creating its directory. Does nothing on the web.
+pub fn store<T: Serialize>(path: &str, save: &T) {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let s = match ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default()) {
+            Ok(s) => s,
+            Err(e) => {warn!("Could not serialize save {path}: {e}"); return;}
+        };
+        if let Some(dir) = std::path::Path::new(path).parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(e) = std::fs::write(path, s) {
+            warn!("Could not write save {path}: {e}");
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = (path, save);
+}
@@ -0,0 +1,77 @@
+use bevy::{app::RunFixedMainLoopSystems, platform::collections::HashSet};
+
+use crate::prelude::*;
+
+
+/// Keyboard sampled for `FixedUpdate` gameplay: a press between two ticks is seen by exactly one tick,
+/// however many frames or ticks pass in between.
+pub struct TickInputPlugin;
+
+impl Plugin for TickInputPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<TickInput>()
+            .add_systems(RunFixedMainLoop, sample_keys.in_set(RunFixedMainLoopSystems::BeforeFixedMainLoop))
+            .add_systems(FixedLast, end_tick)
+            ;
+    }
+}
+
+#[derive(Resource, Default, Clone, Debug)]
+pub struct TickInput {
+    held: HashSet<KeyCode>,
+    pressed: HashSet<KeyCode>,
+    /// Fixed ticks run so far.
+    pub tick: u64,
+}
+
+impl TickInput {
+    /// Held this tick, a tap shorter than a tick still counts.
+    pub fn pressed(&self, key: KeyCode) -> bool {
+        self.held.contains(&key) || self.pressed.contains(&key)
+    }
+
+    pub fn just_pressed(&self, key: KeyCode) -> bool {
+        self.pressed.contains(&key)
+    }
+
+    /// For replays, takes effect on the next tick that reads it.
+    pub fn press(&mut self, key: KeyCode) {
+        self.held.insert(key);
+        self.pressed.insert(key);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.held.remove(&key);
+    }
+
+    pub fn held(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.held.iter().copied()
+    }
+
+    pub fn just_pressed_keys(&self) -> impl Iterator<Item = KeyCode> + '_ {
+        self.pressed.iter().copied()
+    }
+
+    /// Replaces the sampled keys of this tick, for replays.
+    pub fn replace(&mut self, held: HashSet<KeyCode>, pressed: HashSet<KeyCode>) {
+        self.held = held;
+        self.pressed = pressed;
+    }
+}
+
+fn sample_keys(
+    keys: Option<Res<ButtonInput<KeyCode>>>,
+    mut input: ResMut<TickInput>,
+) {
+    let Some(keys) = keys else {return;};
+    input.held = keys.get_pressed().copied().collect();
+    input.pressed.extend(keys.get_just_pressed().copied());
+}
+
+fn end_tick(
+    mut input: ResMut<TickInput>,
+) {
+    input.pressed.clear();
+    input.tick += 1;
+}
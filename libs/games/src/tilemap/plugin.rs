@@ -28,6 +28,10 @@ impl Plugin for MapPlugin {
                 r"^.*::SpawnPoint$",
                 r"^.*::StopTrigger$",
                 r"^.*::NextTrigger$",
+                r"^.*::OneWayPlatform$",
+                r"^.*::PhaseLayer$",
+                r"^.*::PhasePortal$",
+                r"^games::geometry_dash::objects::.*",
                 r"^.*::CameraCenter$",
                 r"^.*::SpawnPoint$",
                 r"^.*::MiamiSpawnPoint$",
@@ -39,6 +43,8 @@ impl Plugin for MapPlugin {
                 r"^.*::EntrypointDialog$",
                 r"^.*::BossDialog$",
                 r"^.*::BossfightSpawner$",
+                r"^.*::MiamiCheckpoint$",
+                r"^.*::MiamiLight$",
                 // r"^.*::MiamiProp$"
                 
             ])
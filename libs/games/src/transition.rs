@@ -0,0 +1,185 @@
+use bevy::{render::{render_resource::AsBindGroup, view::screenshot::{Screenshot, ScreenshotCaptured}}, shader::ShaderRef, sprite_render::{AlphaMode2d, Material2d, Material2dPlugin}, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+const SHADER_ASSET_PATH: &str = "shaders/transition.wgsl";
+
+
+/// State changes behind a transition: `RequestTransition` captures the outgoing frame,
+/// switches the state and plays the style over the new one on the highres layer.
+pub struct TransitionPlugin;
+
+impl Plugin for TransitionPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_plugins(Material2dPlugin::<TransitionMaterial>::default())
+            .add_message::<RequestTransition>()
+            .add_systems(Update, (start_transitions, (attach_materials, tick_transitions).chain()))
+            ;
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransitionStyle {
+    /// Through black.
+    #[default]
+    Fade,
+    /// A soft edge sweeping right.
+    Wipe,
+    /// A growing hole in the middle.
+    Iris,
+    /// Coarser pixels until it is gone.
+    Pixelate,
+    /// The frame breaks into shards that fall away.
+    Shatter,
+    /// The frame slides out to the left.
+    Slide,
+}
+
+impl TransitionStyle {
+    fn index(self) -> f32 {
+        match self {
+            TransitionStyle::Fade => 0.,
+            TransitionStyle::Wipe => 1.,
+            TransitionStyle::Iris => 2.,
+            TransitionStyle::Pixelate => 3.,
+            TransitionStyle::Shatter => 4.,
+            TransitionStyle::Slide => 5.,
+        }
+    }
+}
+
+/// Asks to go to `to` behind a transition, ignored while another one is being captured.
+#[derive(Message, Clone, Copy, Debug)]
+pub struct RequestTransition {
+    pub to: AppState,
+    pub style: TransitionStyle,
+    pub duration: f32,
+}
+
+impl RequestTransition {
+    pub fn new(to: AppState, style: TransitionStyle) -> Self {
+        Self {to, style, duration: TRANSITION_TIME}
+    }
+
+    /// Only captures the outgoing frame into `LastScreenshot`, for states that draw it themselves.
+    pub fn capture(to: AppState) -> Self {
+        Self {to, style: TransitionStyle::default(), duration: 0.}
+    }
+}
+
+/// Plays `style` over `image`, spawn it directly to transition from an older screenshot.
+#[derive(Component)]
+pub struct Transition {
+    pub image: Handle<Image>,
+    pub style: TransitionStyle,
+    pub duration: f32,
+    pub time: f32,
+}
+
+impl Transition {
+    pub fn new(image: Handle<Image>, style: TransitionStyle, duration: f32) -> Self {
+        Self {image, style, duration, time: 0.}
+    }
+}
+
+#[derive(Asset, TypePath, AsBindGroup, Debug, Clone)]
+pub struct TransitionMaterial {
+    /// Progress, style, window width and height.
+    #[uniform(0)]
+    params: Vec4,
+    #[texture(1)]
+    #[sampler(2)]
+    screenshot: Handle<Image>,
+}
+
+impl Material2d for TransitionMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_ASSET_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode2d {
+        AlphaMode2d::Blend
+    }
+}
+
+fn start_transitions(
+    mut cmd: Commands,
+    mut requests: MessageReader<RequestTransition>,
+    mut last_screenshot: ResMut<LastScreenshot>,
+) {
+    for request in requests.read() {
+        if last_screenshot.awaiting {continue;}
+        last_screenshot.awaiting = true;
+        cmd.spawn(Screenshot::primary_window())
+            .observe(capture_and_translate(*request));
+    }
+}
+
+/// Keeps the captured frame in `LastScreenshot`, switches the state and covers the switch with the frame.
+fn capture_and_translate(
+    request: RequestTransition,
+) -> impl FnMut(On<ScreenshotCaptured>, Commands, ResMut<LastScreenshot>, ResMut<Assets<Image>>, ResMut<NextState<AppState>>) {
+    move |
+            screenshot_captured,
+            mut cmd,
+            mut last_screenshot,
+            mut images_mut,
+            mut next_state
+    | {
+        let handle = images_mut.add(screenshot_captured.image.clone());
+        last_screenshot.image = Some(handle.clone());
+        last_screenshot.awaiting = false;
+        next_state.set(request.to);
+        if request.duration > 0. {
+            cmd.spawn(Transition::new(handle, request.style, request.duration));
+        }
+    }
+}
+
+fn attach_materials(
+    mut cmd: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<TransitionMaterial>>,
+    q: Query<(Entity, &Transition), Added<Transition>>,
+) {
+    for (e, transition) in q.iter() {
+        cmd.entity(e).insert((
+            Name::new("Transition"),
+            Mesh2d(meshes.add(Rectangle::default())),
+            MeshMaterial2d(materials.add(TransitionMaterial {
+                params: Vec4::new(0., transition.style.index(), 1., 1.),
+                screenshot: transition.image.clone(),
+            })),
+            Transform::from_xyz(0., 0., TRANSITION_Z),
+            HIGHRES_LAYERS,
+        ));
+    }
+}
+
+fn tick_transitions(
+    mut cmd: Commands,
+    time: Res<Time>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut materials: ResMut<Assets<TransitionMaterial>>,
+    mut q: Query<(Entity, &mut Transition, &MeshMaterial2d<TransitionMaterial>, &mut Transform)>,
+) {
+    let Ok(window) = window.single() else {return;};
+    let size = window.size();
+    for (e, mut transition, material, mut transform) in q.iter_mut() {
+        // the first frame of the new state can be a long one
+        transition.time += time.delta_secs().min(MAX_DT);
+        if transition.time >= transition.duration {
+            cmd.entity(e).despawn();
+            continue;
+        }
+        transform.scale = size.extend(1.);
+        let Some(material) = materials.get_mut(material) else {continue;};
+        material.params = Vec4::new(transition.time / transition.duration, transition.style.index(), size.x, size.y);
+    }
+}
+
+
+const TRANSITION_TIME: f32 = 0.8;
+const TRANSITION_Z: f32 = 900.;